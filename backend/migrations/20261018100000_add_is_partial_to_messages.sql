@@ -0,0 +1,2 @@
+-- set when a streamed reply was cut short, e.g. the client disconnected mid-stream
+ALTER TABLE messages ADD COLUMN is_partial BOOLEAN NOT NULL DEFAULT 0;
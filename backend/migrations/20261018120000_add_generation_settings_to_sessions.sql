@@ -0,0 +1,2 @@
+-- per-session overrides of the sampling/safety defaults, stored as JSON
+ALTER TABLE sessions ADD COLUMN generation_settings TEXT;
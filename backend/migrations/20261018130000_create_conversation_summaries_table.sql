@@ -0,0 +1,8 @@
+-- rolling summary of the turns that no longer fit into the model's context
+CREATE TABLE IF NOT EXISTS conversation_summaries (
+    session_id TEXT PRIMARY KEY NOT NULL,
+    summary TEXT NOT NULL,
+    covered_until TEXT NOT NULL,      -- timestamp of the newest message folded into the summary
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
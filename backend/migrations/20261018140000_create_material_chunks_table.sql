@@ -0,0 +1,28 @@
+-- study material split into passages so only the relevant ones go into each prompt
+CREATE TABLE IF NOT EXISTS material_chunks (
+    row_id INTEGER PRIMARY KEY,       -- key of the full text index, VACUUM may renumber an implicit rowid
+    id TEXT UNIQUE NOT NULL,          -- UUID
+    session_id TEXT NOT NULL,
+    chunk_index INTEGER NOT NULL,     -- position in the material
+    content TEXT NOT NULL,
+    embedding BLOB,                   -- little-endian f32 vector, only set by embedding backends
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_material_chunks_session_id ON material_chunks(session_id, chunk_index);
+
+-- BM25 full text index over the chunks, kept in sync by the triggers below
+CREATE VIRTUAL TABLE IF NOT EXISTS material_chunks_fts USING fts5(
+    content,
+    content = 'material_chunks',
+    content_rowid = 'row_id',
+    tokenize = 'porter unicode61'
+);
+
+CREATE TRIGGER IF NOT EXISTS material_chunks_ai AFTER INSERT ON material_chunks BEGIN
+    INSERT INTO material_chunks_fts (rowid, content) VALUES (new.row_id, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS material_chunks_ad AFTER DELETE ON material_chunks BEGIN
+    INSERT INTO material_chunks_fts (material_chunks_fts, rowid, content) VALUES ('delete', old.row_id, old.content);
+END;
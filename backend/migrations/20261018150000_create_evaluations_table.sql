@@ -0,0 +1,23 @@
+-- rubric based assessment of how well the teacher explained the session's material
+CREATE TABLE IF NOT EXISTS evaluations (
+    id TEXT PRIMARY KEY NOT NULL,     -- UUID
+    session_id TEXT NOT NULL,
+    accuracy_score INTEGER NOT NULL,  -- every score is 1 (poor) to 5 (excellent)
+    accuracy_feedback TEXT NOT NULL,
+    completeness_score INTEGER NOT NULL,
+    completeness_feedback TEXT NOT NULL,
+    clarity_score INTEGER NOT NULL,
+    clarity_feedback TEXT NOT NULL,
+    examples_score INTEGER NOT NULL,
+    examples_feedback TEXT NOT NULL,
+    question_handling_score INTEGER NOT NULL,
+    question_handling_feedback TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    strengths TEXT NOT NULL,          -- JSON array of strings
+    improvements TEXT NOT NULL,       -- JSON array of strings
+    model TEXT NOT NULL,              -- model that graded the lesson
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_evaluations_session_id ON evaluations(session_id, created_at);
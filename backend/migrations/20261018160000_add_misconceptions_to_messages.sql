@@ -0,0 +1,2 @@
+-- JSON array of statements in a teacher message that contradict the material, NULL until checked
+ALTER TABLE messages ADD COLUMN misconceptions TEXT;
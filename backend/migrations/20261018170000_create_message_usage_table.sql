@@ -0,0 +1,15 @@
+-- what generating each assistant message cost and how long it took
+CREATE TABLE IF NOT EXISTS message_usage (
+    message_id TEXT PRIMARY KEY NOT NULL,
+    session_id TEXT NOT NULL,         -- copied from the message so usage can be grouped without a join
+    model TEXT NOT NULL,
+    prompt_tokens INTEGER,            -- NULL when the provider doesn't report usage
+    response_tokens INTEGER,
+    finish_reason TEXT,
+    latency_ms INTEGER NOT NULL,      -- from sending the request until the reply ended
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_message_usage_session_id ON message_usage(session_id);
+CREATE INDEX IF NOT EXISTS idx_message_usage_created_at ON message_usage(created_at);
@@ -0,0 +1,4 @@
+-- what Bodhi reported about its own understanding with a structured reply, NULL for free text replies
+ALTER TABLE messages ADD COLUMN understanding INTEGER; -- 0 to 100
+ALTER TABLE messages ADD COLUMN confusions TEXT;       -- JSON array of open confusion points
+ALTER TABLE messages ADD COLUMN concept TEXT;          -- the concept Bodhi is asking about
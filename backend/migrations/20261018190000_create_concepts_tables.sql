@@ -0,0 +1,23 @@
+-- key concepts of a session's study material, extracted once when the session is created
+CREATE TABLE IF NOT EXISTS concepts (
+    id TEXT PRIMARY KEY NOT NULL,     -- UUID
+    session_id TEXT NOT NULL,
+    position INTEGER NOT NULL,        -- order in which the material introduces it
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_concepts_session_id ON concepts(session_id, position);
+
+-- the concepts each teacher message explained
+CREATE TABLE IF NOT EXISTS message_concepts (
+    message_id TEXT NOT NULL,
+    concept_id TEXT NOT NULL,
+    depth TEXT NOT NULL,              -- 'full' or 'partial'
+    PRIMARY KEY (message_id, concept_id),
+    FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE,
+    FOREIGN KEY (concept_id) REFERENCES concepts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_message_concepts_concept_id ON message_concepts(concept_id);
@@ -0,0 +1,12 @@
+-- end of lesson notes for the teacher, regenerated once the lesson has moved on
+CREATE TABLE IF NOT EXISTS lesson_summaries (
+    session_id TEXT PRIMARY KEY NOT NULL,
+    key_points TEXT NOT NULL,         -- JSON array of strings
+    questions TEXT NOT NULL,          -- JSON array of {question, answered}
+    gaps TEXT NOT NULL,               -- JSON array of strings
+    follow_up TEXT NOT NULL,          -- JSON array of strings
+    message_count INTEGER NOT NULL,   -- messages in the session when the notes were written
+    model TEXT NOT NULL,              -- model that wrote the notes
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
@@ -0,0 +1,25 @@
+-- 'teach': the user teaches Bodhi, 'quiz': Bodhi asks the user questions about the material
+ALTER TABLE sessions ADD COLUMN mode TEXT NOT NULL DEFAULT 'teach';
+
+-- what a quiz session's message is about: the question it asks or answers, and the
+-- score of an answer. JSON, NULL outside quizzes
+ALTER TABLE messages ADD COLUMN quiz TEXT;
+
+-- the questions of a quiz session, generated from its material when the quiz starts
+CREATE TABLE IF NOT EXISTS quiz_questions (
+    id TEXT PRIMARY KEY NOT NULL,     -- UUID
+    session_id TEXT NOT NULL,
+    position INTEGER NOT NULL,        -- asked in this order, starting at 1
+    kind TEXT NOT NULL,               -- 'multiple_choice', 'short_answer' or 'explain_why'
+    question TEXT NOT NULL,
+    options TEXT NOT NULL,            -- JSON array of strings, empty unless multiple choice
+    answer TEXT NOT NULL,             -- the expected answer, the option letter for multiple choice
+    evidence TEXT NOT NULL,           -- quote from the material that supports the answer
+    asked_at TEXT,
+    score INTEGER,                    -- 0 to 10, NULL until the answer is graded
+    feedback TEXT,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- one quiz per session, a second one started at the same time fails to insert
+CREATE UNIQUE INDEX IF NOT EXISTS idx_quiz_questions_session_id ON quiz_questions(session_id, position);
@@ -0,0 +1,3 @@
+-- sessions.mode may now also be 'debate': Bodhi argues against the user's position.
+-- what Bodhi made of the user's arguments in each debate reply. JSON, NULL outside debates
+ALTER TABLE messages ADD COLUMN debate TEXT;
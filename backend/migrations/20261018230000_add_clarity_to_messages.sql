@@ -0,0 +1,3 @@
+-- how plainly each teacher message is written: reading level, sentence length and jargon.
+-- JSON, NULL for Bodhi's replies and messages from before the analysis
+ALTER TABLE messages ADD COLUMN clarity TEXT;
@@ -0,0 +1,3 @@
+-- the headings found in the uploaded material and where they start in material_text.
+-- JSON, NULL for sessions created from pasted text
+ALTER TABLE sessions ADD COLUMN material_sections TEXT;
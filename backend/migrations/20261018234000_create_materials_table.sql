@@ -0,0 +1,24 @@
+-- the study materials of a session in the order they're given to Bodhi. sessions.material_text
+-- holds all of them assembled, each labelled with its source
+CREATE TABLE IF NOT EXISTS materials (
+    id TEXT PRIMARY KEY NOT NULL,     -- UUID
+    session_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    source_name TEXT NOT NULL,        -- the uploaded file's name, or 'Pasted text'
+    format TEXT NOT NULL,             -- 'pdf', 'docx', 'epub', 'markdown', 'html', 'plain_text', 'srt' or 'vtt'
+    extracted_text TEXT NOT NULL,
+    page_count INTEGER,               -- NULL for formats without pages
+    sections TEXT,                    -- JSON, headings found in the file
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_materials_session_id ON materials(session_id, position);
+
+-- existing sessions keep their material as their only source, ids are random v4-style UUIDs
+INSERT INTO materials (id, session_id, position, source_name, format, extracted_text, page_count,
+                       sections, created_at)
+SELECT lower(hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' || substr(hex(randomblob(2)), 2)
+             || '-a' || substr(hex(randomblob(2)), 2) || '-' || hex(randomblob(6))), id, 0, 'Study material', 'plain_text', material_text, NULL,
+       material_sections, created_at
+FROM sessions;
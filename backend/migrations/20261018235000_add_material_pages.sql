@@ -0,0 +1,6 @@
+-- where each page of a PDF material starts in its extracted text. JSON, NULL for other formats
+ALTER TABLE materials ADD COLUMN pages TEXT;
+-- the same for the session's assembled material_text, with each page's source name
+ALTER TABLE sessions ADD COLUMN material_pages TEXT;
+-- the pages of the material a message cites. JSON, NULL when it cites none
+ALTER TABLE messages ADD COLUMN citations TEXT;
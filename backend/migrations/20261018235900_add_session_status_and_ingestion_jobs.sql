@@ -0,0 +1,26 @@
+-- sessions go processing -> ready | failed while an upload is processed, then
+-- ready -> active -> completed, and any of them can be archived. sessions so far were
+-- all 'created' with their material ready
+UPDATE sessions
+SET status = CASE
+    WHEN EXISTS (SELECT 1 FROM messages WHERE messages.session_id = sessions.id) THEN 'active'
+    ELSE 'ready'
+END
+WHERE status = 'created';
+
+-- uploads waiting for or going through the background pipeline, one per session
+CREATE TABLE IF NOT EXISTS ingestion_jobs (
+    session_id TEXT PRIMARY KEY NOT NULL,
+    file_name TEXT,
+    content_type TEXT,
+    pages TEXT,                       -- the PDF pages to keep, e.g. '12-30, 40'
+    data BLOB,                        -- the uploaded file, cleared once it's processed
+    stage TEXT NOT NULL,              -- 'queued', 'extracting', 'indexing', 'concepts', 'done' or 'failed'
+    error_kind TEXT,                  -- set when the stage is 'failed'
+    error_message TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_ingestion_jobs_stage ON ingestion_jobs(stage);
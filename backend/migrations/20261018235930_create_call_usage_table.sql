@@ -0,0 +1,16 @@
+-- what the model calls behind the scenes cost, i.e. all but the replies in message_usage
+CREATE TABLE IF NOT EXISTS call_usage (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    purpose TEXT NOT NULL,            -- e.g. 'misconception_check', 'concept_extraction'
+    model TEXT NOT NULL,
+    prompt_tokens INTEGER,            -- NULL when the provider doesn't report usage
+    response_tokens INTEGER,
+    finish_reason TEXT,
+    latency_ms INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_call_usage_session_id ON call_usage(session_id);
+CREATE INDEX IF NOT EXISTS idx_call_usage_created_at ON call_usage(created_at);
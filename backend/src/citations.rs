@@ -0,0 +1,163 @@
+use crate::models::material::{PageCitation, SourcePage};
+
+// how many words of a quote have to match when it isn't found verbatim
+const MIN_MATCHED_WORDS: usize = 3;
+const MAX_MATCHED_WORDS: usize = 8;
+
+/// the page of the material text at `offset`, None outside the PDF materials
+pub fn cite_offset(pages: &[SourcePage], offset: usize) -> Option<PageCitation> {
+    pages
+        .iter()
+        .find(|page| page.start <= offset && offset < page.end)
+        .map(|page| PageCitation {
+            source: page.source.clone(),
+            page: page.number,
+        })
+}
+
+/// the page a quote or passage of the material comes from
+pub fn cite_excerpt(material: &str, pages: &[SourcePage], excerpt: &str) -> Option<PageCitation> {
+    if pages.is_empty() {
+        return None;
+    }
+    locate(material, excerpt).and_then(|offset| cite_offset(pages, offset))
+}
+
+/// how a page is marked in the material Bodhi reads: "[p. 14]", with the source
+/// ("[p. 14, chapter.pdf]") once several materials have pages
+pub fn page_marker(citation: &PageCitation, pages: &[SourcePage]) -> String {
+    if several_sources(pages) {
+        format!("[p. {}, {}]", citation.page, citation.source)
+    } else {
+        format!("[p. {}]", citation.page)
+    }
+}
+
+/// the material text with a marker at the start of every page, for Bodhi's prompt
+pub fn mark_pages(material: &str, pages: &[SourcePage]) -> String {
+    let mut marked = String::with_capacity(material.len() + pages.len() * 12);
+    let mut copied = 0;
+    for page in pages {
+        let start = page.start.min(material.len());
+        if start < copied || !material.is_char_boundary(start) {
+            continue;
+        }
+
+        marked.push_str(&material[copied..start]);
+        marked.push_str(&page_marker(
+            &PageCitation {
+                source: page.source.clone(),
+                page: page.number,
+            },
+            pages,
+        ));
+        marked.push('\n');
+        copied = start;
+    }
+    marked.push_str(&material[copied..]);
+    marked
+}
+
+/// the pages Bodhi's `reply` cites with markers like "[p. 14]", in order and without
+/// repeats. pages the material doesn't have are dropped
+pub fn cite_reply(reply: &str, pages: &[SourcePage]) -> Vec<PageCitation> {
+    if pages.is_empty() {
+        return Vec::new();
+    }
+
+    let mut citations: Vec<PageCitation> = Vec::new();
+    let mut rest = reply;
+    while let Some(start) = rest.find(['[', '(']) {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find([']', ')']) else {
+            break;
+        };
+
+        if let Some(citation) = parse_marker(&rest[..end], pages) {
+            if !citations.contains(&citation) {
+                citations.push(citation);
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+
+    citations
+}
+
+// "p. 14" or "p. 14, chapter.pdf", checked against the material's pages
+fn parse_marker(marker: &str, pages: &[SourcePage]) -> Option<PageCitation> {
+    let marker = marker.trim();
+    let marker = marker
+        .strip_prefix("p.")
+        .or_else(|| marker.strip_prefix("page"))?
+        .trim_start();
+    let digits = marker
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(marker.len());
+    let number: u32 = marker[..digits].parse().ok()?;
+    let source = marker[digits..]
+        .trim_start_matches([',', ' '])
+        .trim()
+        .to_lowercase();
+
+    pages
+        .iter()
+        .find(|page| {
+            page.number == number && (source.is_empty() || page.source.to_lowercase() == source)
+        })
+        .map(|page| PageCitation {
+            source: page.source.clone(),
+            page: number,
+        })
+}
+
+fn several_sources(pages: &[SourcePage]) -> bool {
+    pages
+        .first()
+        .is_some_and(|first| pages.iter().any(|page| page.source != first.source))
+}
+
+// where `excerpt` starts in `material`. models trim and reflow their quotes, so when it
+// isn't there verbatim its first words are looked for ignoring case, spacing and punctuation
+fn locate(material: &str, excerpt: &str) -> Option<usize> {
+    let excerpt = excerpt
+        .trim()
+        .trim_matches(|c: char| matches!(c, '"' | '\'' | '“' | '”' | '…') || c.is_whitespace());
+    if excerpt.is_empty() {
+        return None;
+    }
+    if let Some(offset) = material.find(excerpt) {
+        return Some(offset);
+    }
+
+    let wanted: Vec<String> = excerpt
+        .split_whitespace()
+        .map(normalize_word)
+        .filter(|word| !word.is_empty())
+        .take(MAX_MATCHED_WORDS)
+        .collect();
+    if wanted.len() < MIN_MATCHED_WORDS {
+        return None;
+    }
+
+    let words: Vec<(usize, String)> = material
+        .split_whitespace()
+        .map(|word| {
+            let offset = word.as_ptr() as usize - material.as_ptr() as usize;
+            (offset, normalize_word(word))
+        })
+        .filter(|(_, word)| !word.is_empty())
+        .collect();
+
+    words
+        .windows(wanted.len())
+        .find(|window| window.iter().zip(&wanted).all(|((_, a), b)| a == b))
+        .map(|window| window[0].0)
+}
+
+fn normalize_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
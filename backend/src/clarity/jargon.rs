@@ -0,0 +1,165 @@
+use std::collections::HashSet;
+
+use crate::clarity::readability::count_syllables;
+
+// shorter words are rarely technical
+const MIN_TERM_CHARS: usize = 7;
+const MIN_TERM_SYLLABLES: usize = 3;
+// acronyms like "DNA" or "HTTPS"
+const MAX_ACRONYM_CHARS: usize = 6;
+
+// long everyday words that pass the length and syllable test without being jargon
+const COMMON_WORDS: &str =
+    "absolutely according activity actually additional another anything apparently
+application available basically beautiful because beginning benefit business calculate
+category certainly community company completely condition consider continue conversation
+corporation currently definitely deliver department determine develop development
+difference different difficult direction discover economy education effective
+electricity especially everybody everyone everything example experience explanation
+finally generally government history however identify imagine immediately important
+including increase individual industry information instead interesting international
+library literally material necessary negative obviously officer operation opportunity
+ordinary organization original otherwise particular particularly personal physical
+political popular population position positive possible potential probably problem
+property question quickly reality recognize relationship remember represent
+responsibility similar situation society somebody something sometimes somewhere specific
+technology therefore together tomorrow understand understanding unfortunately usually
+various whatever wonderful yesterday";
+
+// "X means ...", "X refers to ..."
+const DEFINING_VERBS: &[&str] = &["means", "mean", "refers", "stands", "describes", "denotes"];
+// "X is a ...", "X is when ..." but not "X is important"
+const DEFINITION_STARTS: &str =
+    "a an the when how where what basically just like simply kind type way";
+// "this is called X", "known as X"
+const NAMING_WORDS: &[&str] = &["called", "named", "termed"];
+
+/// the technical terms of a study material
+#[derive(Debug, Clone, Default)]
+pub struct Glossary {
+    terms: HashSet<String>,
+}
+
+/// a term of the material in a teacher message
+#[derive(Debug, Clone)]
+pub struct TermUse {
+    pub key: String,  // the term as the glossary knows it
+    pub term: String, // as the message spells it
+    pub defined: bool,
+}
+
+impl Glossary {
+    /// collects the material's acronyms and its long, many syllable words that aren't
+    /// everyday vocabulary
+    pub fn from_material(material: &str) -> Self {
+        let terms = material
+            .split_whitespace()
+            .filter_map(|token| {
+                let word = trim_word(token);
+                is_term(word).then(|| term_key(word))
+            })
+            .collect();
+
+        Glossary { terms }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// every use of a glossary term in `text`, in order, with whether the use explains it
+    pub fn find(&self, text: &str) -> Vec<TermUse> {
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+
+        tokens
+            .iter()
+            .enumerate()
+            .filter_map(|(i, token)| {
+                let word = trim_word(token);
+                let key = term_key(word);
+                self.terms.contains(&key).then(|| TermUse {
+                    key,
+                    term: word.to_string(),
+                    defined: is_defined_at(&tokens, i),
+                })
+            })
+            .collect()
+    }
+}
+
+// whether `word` is one of the whitespace separated words of `list`
+fn is_listed(list: &str, word: &str) -> bool {
+    list.split_whitespace().any(|listed| listed == word)
+}
+
+fn trim_word(token: &str) -> &str {
+    token.trim_matches(|c: char| !c.is_alphanumeric())
+}
+
+fn is_term(word: &str) -> bool {
+    let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+    let acronym = (2..=MAX_ACRONYM_CHARS).contains(&word.chars().count())
+        && letters >= 2
+        && word
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    if acronym {
+        return true;
+    }
+
+    letters == word.chars().count()
+        && word.chars().count() >= MIN_TERM_CHARS
+        && count_syllables(word) >= MIN_TERM_SYLLABLES
+        && !is_listed(COMMON_WORDS, &term_key(word))
+}
+
+/// lowercase and singular-ish, so "Enzymes" and "enzyme" are the same term
+pub fn term_key(word: &str) -> String {
+    let mut key = word.to_lowercase();
+    if let Some(stripped) = key.strip_suffix("'s") {
+        key = stripped.to_string();
+    }
+    if key.chars().count() > 4
+        && key.ends_with('s')
+        && !key.ends_with("ss")
+        && !key.ends_with("us")
+        && !key.ends_with("is")
+    {
+        key.pop();
+    }
+    key
+}
+
+// whether the words around the term at `i` explain it: "X is a ...", "X means ...",
+// "X (...)", "X: ...", "X, which ...", "is called X", "known as X"
+fn is_defined_at(tokens: &[&str], i: usize) -> bool {
+    let lower = |j: usize| {
+        tokens
+            .get(j)
+            .map(|token| trim_word(token).to_lowercase())
+            .unwrap_or_default()
+    };
+    let token = tokens[i];
+    let next_raw = tokens.get(i + 1).copied().unwrap_or_default();
+    let next = lower(i + 1);
+
+    if token.ends_with(':') || next_raw.starts_with('(') || token.ends_with('(') {
+        return true;
+    }
+    if token.ends_with(',') && matches!(next.as_str(), "which" | "i.e" | "meaning" | "or") {
+        return true;
+    }
+    if DEFINING_VERBS.contains(&next.as_str()) {
+        return true;
+    }
+    if matches!(next.as_str(), "is" | "are") && is_listed(DEFINITION_STARTS, &lower(i + 2)) {
+        return true;
+    }
+
+    if i == 0 {
+        return false;
+    }
+    let previous = lower(i - 1);
+    NAMING_WORDS.contains(&previous.as_str())
+        || (previous == "as" && i >= 2 && matches!(lower(i - 2).as_str(), "known" | "referred"))
+}
@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+
+use crate::models::{
+    clarity::{ClarityPoint, ClarityScore, ClarityTrend},
+    message::{Message, MessageRole},
+};
+
+pub mod jargon;
+pub mod readability;
+
+use jargon::{Glossary, term_key};
+use readability::Readability;
+
+// how many messages at either end of a session are compared for its trend
+const TREND_WINDOW: usize = 3;
+
+/// measures how plainly the teacher's `text` is written: its reading level, sentence
+/// length and how much of it is the material's jargon. a term counts as explained once
+/// this or an earlier teacher message in `history` defines it
+pub fn assess(material: &str, history: &[Message], text: &str) -> ClarityScore {
+    let readability = Readability::measure(text);
+    let glossary = Glossary::from_material(material);
+
+    let explained: HashSet<String> = history
+        .iter()
+        .filter_map(|msg| msg.clarity.as_ref())
+        .flat_map(|clarity| clarity.defined.iter().map(|term| term_key(term)))
+        .collect();
+
+    let uses = if glossary.is_empty() {
+        Vec::new()
+    } else {
+        glossary.find(text)
+    };
+
+    let mut jargon: Vec<String> = Vec::new();
+    let mut defined: Vec<String> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut defined_keys = HashSet::new();
+    for term_use in &uses {
+        if seen.insert(term_use.key.clone()) {
+            jargon.push(term_use.term.clone());
+        }
+        if term_use.defined && defined_keys.insert(term_use.key.clone()) {
+            defined.push(term_use.term.clone());
+        }
+    }
+    let undefined = jargon
+        .iter()
+        .filter(|term| {
+            let key = term_key(term);
+            !defined_keys.contains(&key) && !explained.contains(&key)
+        })
+        .cloned()
+        .collect();
+
+    let jargon_density = if readability.words == 0 {
+        0.0
+    } else {
+        uses.len() as f64 / readability.words as f64
+    };
+
+    ClarityScore {
+        words: readability.words as i64,
+        sentences: readability.sentences as i64,
+        avg_sentence_words: round(readability.avg_sentence_words(), 1),
+        reading_grade: round(readability.grade(), 1),
+        reading_ease: round(readability.ease(), 1),
+        jargon_density: round(jargon_density.min(1.0), 3),
+        jargon,
+        defined,
+        undefined,
+    }
+}
+
+/// the clarity of every analysed teacher message in `messages`, with how the latest
+/// ones compare to the first ones
+pub fn trend(messages: &[Message]) -> ClarityTrend {
+    let points: Vec<ClarityPoint> = messages
+        .iter()
+        .filter(|msg| matches!(msg.role, MessageRole::User))
+        .filter_map(|msg| {
+            msg.clarity.as_ref().map(|clarity| ClarityPoint {
+                message_id: msg.id,
+                timestamp: msg.timestamp,
+                reading_grade: clarity.reading_grade,
+                avg_sentence_words: clarity.avg_sentence_words,
+                jargon_density: clarity.jargon_density,
+            })
+        })
+        .collect();
+
+    let change = |value: fn(&ClarityPoint) -> f64, digits: i32| {
+        let window = (points.len() / 2).min(TREND_WINDOW);
+        if window == 0 {
+            return None;
+        }
+        let mean =
+            |points: &[ClarityPoint]| points.iter().map(value).sum::<f64>() / points.len() as f64;
+        let latest = mean(&points[points.len() - window..]);
+        Some(round(latest - mean(&points[..window]), digits))
+    };
+
+    // explained later counts as explained
+    let mut explained = HashSet::new();
+    let mut undefined = Vec::new();
+    let mut listed = HashSet::new();
+    for clarity in messages.iter().filter_map(|msg| msg.clarity.as_ref()) {
+        explained.extend(clarity.defined.iter().map(|term| term_key(term)));
+        for term in &clarity.undefined {
+            if listed.insert(term_key(term)) {
+                undefined.push(term.clone());
+            }
+        }
+    }
+    undefined.retain(|term| !explained.contains(&term_key(term)));
+
+    ClarityTrend {
+        grade_change: change(|p| p.reading_grade, 1),
+        sentence_change: change(|p| p.avg_sentence_words, 1),
+        jargon_change: change(|p| p.jargon_density, 3),
+        points,
+        undefined,
+    }
+}
+
+fn round(value: f64, digits: i32) -> f64 {
+    let factor = 10f64.powi(digits);
+    (value * factor).round() / factor
+}
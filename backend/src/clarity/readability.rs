@@ -0,0 +1,121 @@
+/// word, sentence and syllable counts of a text, the inputs of the Flesch formulas
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Readability {
+    pub words: usize,
+    pub sentences: usize,
+    pub syllables: usize,
+}
+
+impl Readability {
+    pub fn measure(text: &str) -> Self {
+        let sentences = split_sentences(text);
+        let mut readability = Readability {
+            sentences: sentences.len(),
+            ..Readability::default()
+        };
+
+        for word in sentences.iter().flat_map(|sentence| words(sentence)) {
+            readability.words += 1;
+            readability.syllables += count_syllables(word);
+        }
+
+        readability
+    }
+
+    pub fn avg_sentence_words(&self) -> f64 {
+        if self.sentences == 0 {
+            return 0.0;
+        }
+        self.words as f64 / self.sentences as f64
+    }
+
+    fn avg_word_syllables(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        self.syllables as f64 / self.words as f64
+    }
+
+    /// the Flesch-Kincaid grade level, roughly the school year that can follow the text
+    pub fn grade(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        (0.39 * self.avg_sentence_words() + 11.8 * self.avg_word_syllables() - 15.59).max(0.0)
+    }
+
+    /// the Flesch reading ease, higher is easier
+    pub fn ease(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        (206.835 - 1.015 * self.avg_sentence_words() - 84.6 * self.avg_word_syllables())
+            .clamp(0.0, 100.0)
+    }
+}
+
+/// the sentences of `text`, split after '.', '!' and '?' and at line breaks.
+/// a dot inside a token like "e.g." or "3.5" doesn't end a sentence
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, next)| next);
+        let ends = match c {
+            '\n' => true,
+            '.' | '!' | '?' => next.is_none_or(char::is_whitespace),
+            _ => false,
+        };
+
+        if ends {
+            let end = i + c.len_utf8();
+            if !words(&text[start..end]).is_empty() {
+                sentences.push(text[start..end].trim());
+            }
+            start = end;
+        }
+    }
+
+    if !words(&text[start..]).is_empty() {
+        sentences.push(text[start..].trim());
+    }
+
+    sentences
+}
+
+/// the words of `text`, whitespace separated tokens with at least one letter or digit,
+/// stripped of surrounding punctuation
+pub fn words(text: &str) -> Vec<&str> {
+    text.split_whitespace()
+        .map(|token| token.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// estimates the syllables of an English word from its vowel groups
+pub fn count_syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    if word.chars().any(|c| c.is_ascii_digit()) {
+        return 1;
+    }
+
+    let is_vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for c in word.chars() {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+
+    // a silent final e as in "make", but not the syllable of "table"
+    if word.len() > 2 && word.ends_with('e') && !word.ends_with("le") && count > 1 {
+        count -= 1;
+    }
+
+    count.max(1)
+}
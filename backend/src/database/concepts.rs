@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::models::concept::{
+    Concept, ConceptCoverage, ConceptDraft, ConceptRow, CoverageDepth, CoverageStatus,
+};
+
+/// merges freshly extracted `drafts` into the session's concepts, in the drafts' order.
+/// a concept with the same name keeps its id and so the coverage recorded for it; one
+/// the material no longer has is only kept, after the others, while messages cover it
+pub async fn merge_concepts(
+    pool: &SqlitePool,
+    session_id: Uuid,
+    drafts: &[ConceptDraft],
+) -> Result<Vec<Concept>, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+    let mut tx = pool.begin().await?;
+
+    // writing first takes the write lock, so merges of the same session run one after
+    // the other and each sees what the last one stored. the old positions go below zero
+    // to tell the concepts that weren't merged yet from the others
+    sqlx::query!(
+        r#"
+        UPDATE concepts
+        SET position = -1 - position
+        WHERE session_id = $1
+        "#,
+        session_id_str
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let existing = sqlx::query_as!(
+        ConceptRow,
+        r#"
+        SELECT id, position, name, description
+        FROM concepts
+        WHERE session_id = $1
+        "#,
+        session_id_str
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut existing: HashMap<String, String> = existing
+        .into_iter()
+        .map(|row| (concept_key(&row.name), row.id))
+        .collect();
+
+    for (position, draft) in drafts.iter().enumerate() {
+        let position = position as i64;
+
+        match existing.remove(&concept_key(&draft.name)) {
+            Some(id_str) => {
+                sqlx::query!(
+                    r#"
+                    UPDATE concepts
+                    SET position = $1, name = $2, description = $3
+                    WHERE id = $4
+                    "#,
+                    position,
+                    draft.name,
+                    draft.description,
+                    id_str
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+            None => {
+                let id_str = Uuid::new_v4().to_string();
+                sqlx::query!(
+                    r#"
+                    INSERT INTO concepts (id, session_id, position, name, description)
+                    VALUES ($1, $2, $3, $4, $5)
+                    "#,
+                    id_str,
+                    session_id_str,
+                    position,
+                    draft.name,
+                    draft.description
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+    }
+
+    sqlx::query!(
+        r#"
+        DELETE FROM concepts
+        WHERE session_id = $1 AND position < 0
+          AND id NOT IN (SELECT concept_id FROM message_concepts)
+        "#,
+        session_id_str
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let kept_from = drafts.len() as i64;
+    sqlx::query!(
+        r#"
+        UPDATE concepts
+        SET position = $1 - 1 - position
+        WHERE session_id = $2 AND position < 0
+        "#,
+        kept_from,
+        session_id_str
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let rows = sqlx::query_as!(
+        ConceptRow,
+        r#"
+        SELECT id, position, name, description
+        FROM concepts
+        WHERE session_id = $1
+        ORDER BY position ASC
+        "#,
+        session_id_str
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    rows.into_iter().map(Concept::try_from).collect()
+}
+
+// concepts are matched by name, ignoring case and surrounding spaces
+fn concept_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// the session's concepts in material order
+pub async fn list_concepts(
+    pool: &SqlitePool,
+    session_id: Uuid,
+) -> Result<Vec<Concept>, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+
+    let rows = sqlx::query_as!(
+        ConceptRow,
+        r#"
+        SELECT id, position, name, description
+        FROM concepts
+        WHERE session_id = $1
+        ORDER BY position ASC
+        "#,
+        session_id_str
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter().map(Concept::try_from).collect()
+}
+
+/// records which concepts a teacher message explained
+pub async fn add_message_concepts(
+    pool: &SqlitePool,
+    message_id: Uuid,
+    covered: &[(Uuid, CoverageDepth)],
+) -> Result<(), sqlx::Error> {
+    let message_id_str = message_id.to_string();
+    let mut tx = pool.begin().await?;
+
+    for (concept_id, depth) in covered {
+        let concept_id_str = concept_id.to_string();
+        let depth_str = depth.as_str();
+
+        // a concept merged away while the message was mapped is skipped
+        sqlx::query!(
+            r#"
+            INSERT OR REPLACE INTO message_concepts (message_id, concept_id, depth)
+            SELECT $1, id, $2
+            FROM concepts
+            WHERE id = $3
+            "#,
+            message_id_str,
+            depth_str,
+            concept_id_str
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await
+}
+
+/// every concept of the session with how far the teacher's messages covered it
+pub async fn get_coverage(
+    pool: &SqlitePool,
+    session_id: Uuid,
+) -> Result<Vec<ConceptCoverage>, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT c.id AS "id!: String", c.position, c.name, c.description,
+               COUNT(mc.message_id) AS "mentions!: i64",
+               COALESCE(SUM(mc.depth = 'full'), 0) AS "full_mentions!: i64"
+        FROM concepts c
+        LEFT JOIN message_concepts mc ON mc.concept_id = c.id
+        WHERE c.session_id = $1
+        GROUP BY c.id
+        ORDER BY c.position ASC
+        "#,
+        session_id_str
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            let status = if row.full_mentions > 0 {
+                CoverageStatus::Taught
+            } else if row.mentions > 0 {
+                CoverageStatus::Partial
+            } else {
+                CoverageStatus::Untouched
+            };
+
+            Ok(ConceptCoverage {
+                concept: Concept::try_from(ConceptRow {
+                    id: row.id,
+                    position: row.position,
+                    name: row.name,
+                    description: row.description,
+                })?,
+                status,
+                mentions: row.mentions,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::{
+        database::{messages::create_exchange, sessions::create_session},
+        models::{
+            message::{CreateMessage, MessageRole},
+            session::{CreateSession, SessionMode},
+        },
+    };
+
+    async fn test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!().run(&pool).await.unwrap();
+        pool
+    }
+
+    async fn test_session(pool: &SqlitePool) -> Uuid {
+        let session = create_session(
+            pool,
+            CreateSession {
+                topic: "Plants".to_string(),
+                material_text: "Photosynthesis and respiration.".to_string(),
+                persona_id: None,
+                generation_settings: None,
+                mode: SessionMode::Teach,
+                source: None,
+                upload: None,
+            },
+        )
+        .await
+        .unwrap();
+        session.id
+    }
+
+    async fn test_message(pool: &SqlitePool, session_id: Uuid) -> Uuid {
+        let message = |role, content: &str| CreateMessage {
+            role,
+            content: content.to_string(),
+            quiz: None,
+            debate: None,
+            clarity: None,
+            citations: Vec::new(),
+        };
+        let (user, _) = create_exchange(
+            pool,
+            session_id,
+            message(MessageRole::User, "Plants make sugar from light."),
+            message(MessageRole::Assistant, "Oh, how?"),
+            false,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        user.id
+    }
+
+    fn drafts(names: &[&str]) -> Vec<ConceptDraft> {
+        names
+            .iter()
+            .map(|name| ConceptDraft {
+                name: name.to_string(),
+                description: format!("about {}", name),
+            })
+            .collect()
+    }
+
+    fn names(concepts: &[Concept]) -> Vec<&str> {
+        concepts.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn merging_keeps_matching_concepts_and_their_coverage() {
+        let pool = test_pool().await;
+        let session_id = test_session(&pool).await;
+        let first = merge_concepts(
+            &pool,
+            session_id,
+            &drafts(&["Photosynthesis", "Respiration"]),
+        )
+        .await
+        .unwrap();
+
+        let message_id = test_message(&pool, session_id).await;
+        add_message_concepts(&pool, message_id, &[(first[0].id, CoverageDepth::Full)])
+            .await
+            .unwrap();
+
+        let merged = merge_concepts(
+            &pool,
+            session_id,
+            &drafts(&["Chlorophyll", "photosynthesis "]),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(names(&merged), ["Chlorophyll", "photosynthesis "]);
+        assert_eq!(merged[1].id, first[0].id);
+        let coverage = get_coverage(&pool, session_id).await.unwrap();
+        assert_eq!(coverage[1].status, CoverageStatus::Taught);
+        assert_eq!(coverage[1].mentions, 1);
+    }
+
+    #[tokio::test]
+    async fn merging_keeps_covered_concepts_the_material_lost() {
+        let pool = test_pool().await;
+        let session_id = test_session(&pool).await;
+        let first = merge_concepts(
+            &pool,
+            session_id,
+            &drafts(&["Photosynthesis", "Respiration"]),
+        )
+        .await
+        .unwrap();
+
+        let message_id = test_message(&pool, session_id).await;
+        add_message_concepts(&pool, message_id, &[(first[1].id, CoverageDepth::Partial)])
+            .await
+            .unwrap();
+
+        let merged = merge_concepts(&pool, session_id, &drafts(&["Chlorophyll"]))
+            .await
+            .unwrap();
+
+        // the uncovered concept is dropped, the covered one follows the new ones
+        assert_eq!(names(&merged), ["Chlorophyll", "Respiration"]);
+        assert_eq!(merged[1].id, first[1].id);
+    }
+
+    #[tokio::test]
+    async fn coverage_of_a_merged_away_concept_is_skipped() {
+        let pool = test_pool().await;
+        let session_id = test_session(&pool).await;
+        let first = merge_concepts(&pool, session_id, &drafts(&["Photosynthesis"]))
+            .await
+            .unwrap();
+        merge_concepts(&pool, session_id, &drafts(&["Chlorophyll"]))
+            .await
+            .unwrap();
+
+        let message_id = test_message(&pool, session_id).await;
+        add_message_concepts(&pool, message_id, &[(first[0].id, CoverageDepth::Full)])
+            .await
+            .unwrap();
+    }
+}
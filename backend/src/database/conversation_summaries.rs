@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::models::conversation_summary::ConversationSummary;
+
+pub async fn get_conversation_summary(
+    pool: &SqlitePool,
+    session_id: Uuid,
+) -> Result<Option<ConversationSummary>, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+
+    let row = sqlx::query!(
+        r#"
+        SELECT summary, covered_until, updated_at FROM conversation_summaries
+        WHERE session_id = $1
+        "#,
+        session_id_str
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(|row| {
+        Ok(ConversationSummary {
+            session_id,
+            summary: row.summary,
+            covered_until: row
+                .covered_until
+                .parse::<DateTime<Utc>>()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            updated_at: row
+                .updated_at
+                .parse::<DateTime<Utc>>()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        })
+    })
+    .transpose()
+}
+
+/// stores the session's rolling summary, replacing the previous one
+pub async fn upsert_conversation_summary(
+    pool: &SqlitePool,
+    session_id: Uuid,
+    summary: &str,
+    covered_until: DateTime<Utc>,
+) -> Result<ConversationSummary, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+    let covered_until_str = covered_until.to_rfc3339();
+    let now = Utc::now();
+    let updated_at_str = now.to_rfc3339();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO conversation_summaries (session_id, summary, covered_until, updated_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (session_id) DO UPDATE
+        SET summary = excluded.summary,
+            covered_until = excluded.covered_until,
+            updated_at = excluded.updated_at
+        "#,
+        session_id_str,
+        summary,
+        covered_until_str,
+        updated_at_str
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(ConversationSummary {
+        session_id,
+        summary: summary.to_string(),
+        covered_until,
+        updated_at: now,
+    })
+}
@@ -0,0 +1,83 @@
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::models::evaluation::{Evaluation, EvaluationDraft, EvaluationRow};
+
+pub async fn create_evaluation(
+    pool: &SqlitePool,
+    session_id: Uuid,
+    draft: EvaluationDraft,
+    model: &str,
+) -> Result<Evaluation, sqlx::Error> {
+    let id_str = Uuid::new_v4().to_string();
+    let session_id_str = session_id.to_string();
+    let created_at_str = Utc::now().to_rfc3339();
+    let strengths =
+        serde_json::to_string(&draft.strengths).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    let improvements =
+        serde_json::to_string(&draft.improvements).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+    let created_evaluation = sqlx::query_as!(
+        EvaluationRow,
+        r#"
+        INSERT INTO evaluations (id, session_id, accuracy_score, accuracy_feedback, completeness_score,
+                                 completeness_feedback, clarity_score, clarity_feedback, examples_score,
+                                 examples_feedback, question_handling_score, question_handling_feedback,
+                                 summary, strengths, improvements, model, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+        RETURNING id, session_id, accuracy_score, accuracy_feedback, completeness_score,
+                  completeness_feedback, clarity_score, clarity_feedback, examples_score,
+                  examples_feedback, question_handling_score, question_handling_feedback,
+                  summary, strengths, improvements, model, created_at
+        "#,
+        id_str,
+        session_id_str,
+        draft.accuracy.score,
+        draft.accuracy.feedback,
+        draft.completeness.score,
+        draft.completeness.feedback,
+        draft.clarity.score,
+        draft.clarity.feedback,
+        draft.examples.score,
+        draft.examples.feedback,
+        draft.question_handling.score,
+        draft.question_handling.feedback,
+        draft.summary,
+        strengths,
+        improvements,
+        model,
+        created_at_str
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Evaluation::try_from(created_evaluation)
+}
+
+/// the session's most recent evaluation
+pub async fn get_latest_evaluation(
+    pool: &SqlitePool,
+    session_id: Uuid,
+) -> Result<Evaluation, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+
+    let fetched_evaluation = sqlx::query_as!(
+        EvaluationRow,
+        r#"
+        SELECT id, session_id, accuracy_score, accuracy_feedback, completeness_score,
+               completeness_feedback, clarity_score, clarity_feedback, examples_score,
+               examples_feedback, question_handling_score, question_handling_feedback,
+               summary, strengths, improvements, model, created_at
+        FROM evaluations
+        WHERE session_id = $1
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+        session_id_str
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Evaluation::try_from(fetched_evaluation)
+}
@@ -0,0 +1,168 @@
+use chrono::Utc;
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
+use uuid::Uuid;
+
+use crate::models::ingestion::{CreateIngestionJob, IngestionJob, IngestionJobRow, IngestionStage};
+
+/// stores an upload for the worker, on a connection so it's saved in the same
+/// transaction as its session
+pub async fn insert_job(
+    conn: &mut SqliteConnection,
+    session_id: Uuid,
+    upload: &CreateIngestionJob,
+) -> Result<(), sqlx::Error> {
+    let session_id_str = session_id.to_string();
+    let pages = upload.pages.as_ref().map(ToString::to_string);
+    let stage = IngestionStage::Queued.as_str();
+    let now_str = Utc::now().to_rfc3339();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO ingestion_jobs (session_id, file_name, content_type, pages, data, stage,
+                                    created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        session_id_str,
+        upload.file_name,
+        upload.content_type,
+        pages,
+        upload.data,
+        stage,
+        now_str,
+        now_str
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// the session's upload job, without the file
+pub async fn get_job(pool: &SqlitePool, session_id: Uuid) -> Result<IngestionJob, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+
+    let row = sqlx::query_as!(
+        IngestionJobRow,
+        r#"
+        SELECT session_id, file_name, stage, error_kind, error_message, created_at, updated_at
+        FROM ingestion_jobs
+        WHERE session_id = $1
+        "#,
+        session_id_str
+    )
+    .fetch_one(pool)
+    .await?;
+
+    IngestionJob::try_from(row)
+}
+
+/// the stored file of a job that hasn't been processed yet
+pub async fn load_upload(
+    pool: &SqlitePool,
+    session_id: Uuid,
+) -> Result<CreateIngestionJob, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+
+    let row = sqlx::query!(
+        r#"
+        SELECT file_name, content_type, pages, data AS "data!: Vec<u8>"
+        FROM ingestion_jobs
+        WHERE session_id = $1 AND data IS NOT NULL
+        "#,
+        session_id_str
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(CreateIngestionJob {
+        file_name: row.file_name,
+        content_type: row.content_type,
+        pages: row
+            .pages
+            .map(|pages| pages.parse())
+            .transpose()
+            .map_err(|e: String| {
+                sqlx::Error::Decode(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    e,
+                )))
+            })?,
+        data: row.data,
+    })
+}
+
+/// the sessions whose uploads were stored but not finished, oldest first, e.g.
+/// because the server stopped while they were processed
+pub async fn list_unfinished_jobs(pool: &SqlitePool) -> Result<Vec<Uuid>, sqlx::Error> {
+    let rows = sqlx::query_scalar!(
+        r#"
+        SELECT session_id
+        FROM ingestion_jobs
+        WHERE stage NOT IN ('done', 'failed')
+        ORDER BY created_at
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter()
+        .map(|id| Uuid::parse_str(id).map_err(|e| sqlx::Error::Decode(Box::new(e))))
+        .collect()
+}
+
+/// moves the job on to `stage`; once it's done the file isn't needed anymore
+pub async fn set_stage<'e>(
+    executor: impl SqliteExecutor<'e>,
+    session_id: Uuid,
+    stage: IngestionStage,
+) -> Result<(), sqlx::Error> {
+    let session_id_str = session_id.to_string();
+    let stage_str = stage.as_str();
+    let done = stage == IngestionStage::Done;
+    let updated_at_str = Utc::now().to_rfc3339();
+
+    sqlx::query!(
+        r#"
+        UPDATE ingestion_jobs
+        SET stage = $1, data = CASE WHEN $2 THEN NULL ELSE data END, updated_at = $3
+        WHERE session_id = $4
+        "#,
+        stage_str,
+        done,
+        updated_at_str,
+        session_id_str
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// records why the job failed; the file is kept in case it's worth a look
+pub async fn fail_job(
+    pool: &SqlitePool,
+    session_id: Uuid,
+    error_kind: &str,
+    error_message: &str,
+) -> Result<(), sqlx::Error> {
+    let session_id_str = session_id.to_string();
+    let stage = IngestionStage::Failed.as_str();
+    let updated_at_str = Utc::now().to_rfc3339();
+
+    sqlx::query!(
+        r#"
+        UPDATE ingestion_jobs
+        SET stage = $1, error_kind = $2, error_message = $3, updated_at = $4
+        WHERE session_id = $5
+        "#,
+        stage,
+        error_kind,
+        error_message,
+        updated_at_str,
+        session_id_str
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
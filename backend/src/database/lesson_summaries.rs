@@ -0,0 +1,75 @@
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::models::lesson_summary::{LessonSummary, LessonSummaryDraft, LessonSummaryRow};
+
+pub async fn get_lesson_summary(
+    pool: &SqlitePool,
+    session_id: Uuid,
+) -> Result<Option<LessonSummary>, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+
+    let row = sqlx::query_as!(
+        LessonSummaryRow,
+        r#"
+        SELECT session_id, key_points, questions, gaps, follow_up, message_count, model, created_at
+        FROM lesson_summaries
+        WHERE session_id = $1
+        "#,
+        session_id_str
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(LessonSummary::try_from).transpose()
+}
+
+/// stores the session's lesson notes, replacing the previous ones
+pub async fn upsert_lesson_summary(
+    pool: &SqlitePool,
+    session_id: Uuid,
+    draft: LessonSummaryDraft,
+    message_count: i64,
+    model: &str,
+) -> Result<LessonSummary, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+    let created_at_str = Utc::now().to_rfc3339();
+    let key_points =
+        serde_json::to_string(&draft.key_points).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    let questions =
+        serde_json::to_string(&draft.questions).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    let gaps = serde_json::to_string(&draft.gaps).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    let follow_up =
+        serde_json::to_string(&draft.follow_up).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+    let saved = sqlx::query_as!(
+        LessonSummaryRow,
+        r#"
+        INSERT INTO lesson_summaries (session_id, key_points, questions, gaps, follow_up, message_count,
+                                      model, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (session_id) DO UPDATE
+        SET key_points = excluded.key_points,
+            questions = excluded.questions,
+            gaps = excluded.gaps,
+            follow_up = excluded.follow_up,
+            message_count = excluded.message_count,
+            model = excluded.model,
+            created_at = excluded.created_at
+        RETURNING session_id, key_points, questions, gaps, follow_up, message_count, model, created_at
+        "#,
+        session_id_str,
+        key_points,
+        questions,
+        gaps,
+        follow_up,
+        message_count,
+        model,
+        created_at_str
+    )
+    .fetch_one(pool)
+    .await?;
+
+    LessonSummary::try_from(saved)
+}
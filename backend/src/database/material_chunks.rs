@@ -0,0 +1,142 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::models::material_chunk::MaterialChunk;
+
+/// replaces every chunk of a session's material in one transaction
+pub async fn replace_chunks(
+    pool: &SqlitePool,
+    session_id: Uuid,
+    chunks: Vec<(String, Option<Vec<f32>>)>,
+) -> Result<(), sqlx::Error> {
+    let session_id_str = session_id.to_string();
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM material_chunks
+        WHERE session_id = $1
+        "#,
+        session_id_str
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    for (index, (content, embedding)) in chunks.into_iter().enumerate() {
+        let id = Uuid::new_v4().to_string();
+        let chunk_index = index as i64;
+        let embedding = embedding.map(|vector| encode_embedding(&vector));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO material_chunks (id, session_id, chunk_index, content, embedding)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            id,
+            session_id_str,
+            chunk_index,
+            content,
+            embedding
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await
+}
+
+pub async fn count_chunks(pool: &SqlitePool, session_id: Uuid) -> Result<i64, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+
+    let row = sqlx::query!(
+        r#"
+        SELECT COUNT(*) AS "count!: i64" FROM material_chunks
+        WHERE session_id = $1
+        "#,
+        session_id_str
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.count)
+}
+
+/// ranks the session's chunks against an FTS5 `match_query` by BM25, best first
+pub async fn search_chunks(
+    pool: &SqlitePool,
+    session_id: Uuid,
+    match_query: &str,
+    limit: i64,
+) -> Result<Vec<MaterialChunk>, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT c.chunk_index AS "chunk_index!: i64", c.content AS "content!: String"
+        FROM material_chunks_fts
+        JOIN material_chunks c ON c.row_id = material_chunks_fts.rowid
+        WHERE material_chunks_fts MATCH $1 AND c.session_id = $2
+        ORDER BY bm25(material_chunks_fts)
+        LIMIT $3
+        "#,
+        match_query,
+        session_id_str,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| MaterialChunk {
+            chunk_index: row.chunk_index,
+            content: row.content,
+        })
+        .collect())
+}
+
+/// lists the session's chunks in material order together with their stored embeddings
+pub async fn list_chunks_with_embeddings(
+    pool: &SqlitePool,
+    session_id: Uuid,
+) -> Result<Vec<(MaterialChunk, Option<Vec<f32>>)>, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT chunk_index, content, embedding FROM material_chunks
+        WHERE session_id = $1
+        ORDER BY chunk_index ASC
+        "#,
+        session_id_str
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            (
+                MaterialChunk {
+                    chunk_index: row.chunk_index,
+                    content: row.content,
+                },
+                row.embedding.map(|bytes| decode_embedding(&bytes)),
+            )
+        })
+        .collect())
+}
+
+fn encode_embedding(vector: &[f32]) -> Vec<u8> {
+    vector
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+fn decode_embedding(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
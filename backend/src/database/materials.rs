@@ -0,0 +1,134 @@
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+use crate::models::material::{CreateMaterial, Material, MaterialRow};
+
+// JSON for a list column, NULL when it's empty
+fn encode_list<T: Serialize>(items: &[T]) -> Result<Option<String>, sqlx::Error> {
+    if items.is_empty() {
+        return Ok(None);
+    }
+    serde_json::to_string(items)
+        .map(Some)
+        .map_err(|e| sqlx::Error::Encode(Box::new(e)))
+}
+
+/// stores `new` as the session's material at `position`, on a connection so the session
+/// and its first material can be created in one transaction
+pub async fn insert_material(
+    conn: &mut SqliteConnection,
+    session_id: Uuid,
+    position: i64,
+    new: &CreateMaterial,
+) -> Result<Material, sqlx::Error> {
+    let id_str = Uuid::new_v4().to_string();
+    let session_id_str = session_id.to_string();
+    let format = new.format.as_str();
+    let sections = encode_list(&new.sections)?;
+    let pages = encode_list(&new.pages)?;
+    let created_at_str = Utc::now().to_rfc3339();
+
+    let row = sqlx::query_as!(
+        MaterialRow,
+        r#"
+        INSERT INTO materials (id, session_id, position, source_name, format, extracted_text, page_count,
+                               sections, pages, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        RETURNING id, session_id, position, source_name, format, extracted_text, page_count, sections,
+                  pages, created_at
+        "#,
+        id_str,
+        session_id_str,
+        position,
+        new.source_name,
+        format,
+        new.extracted_text,
+        new.page_count,
+        sections,
+        pages,
+        created_at_str
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Material::try_from(row)
+}
+
+/// adds `new` after the session's other materials
+pub async fn add_material(
+    pool: &SqlitePool,
+    session_id: Uuid,
+    new: &CreateMaterial,
+) -> Result<Material, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+    let mut tx = pool.begin().await?;
+
+    let position = sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(MAX(position) + 1, 0) AS "position!: i64"
+        FROM materials
+        WHERE session_id = $1
+        "#,
+        session_id_str
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let material = insert_material(&mut tx, session_id, position, new).await?;
+
+    tx.commit().await?;
+    Ok(material)
+}
+
+/// the session's materials in the order Bodhi reads them
+pub async fn list_materials(
+    pool: &SqlitePool,
+    session_id: Uuid,
+) -> Result<Vec<Material>, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+
+    let rows = sqlx::query_as!(
+        MaterialRow,
+        r#"
+        SELECT id, session_id, position, source_name, format, extracted_text, page_count, sections,
+               pages, created_at
+        FROM materials
+        WHERE session_id = $1
+        ORDER BY position
+        "#,
+        session_id_str
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter().map(Material::try_from).collect()
+}
+
+/// removes one of the session's materials, `RowNotFound` if the session has no such material
+pub async fn delete_material(
+    pool: &SqlitePool,
+    session_id: Uuid,
+    material_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    let session_id_str = session_id.to_string();
+    let material_id_str = material_id.to_string();
+
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM materials
+        WHERE id = $1 AND session_id = $2
+        "#,
+        material_id_str,
+        session_id_str
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        Err(sqlx::Error::RowNotFound)
+    } else {
+        Ok(())
+    }
+}
@@ -1,13 +1,119 @@
-use crate::models::message::{CreateMessage, Message, MessageRole};
+use crate::models::{
+    message::{
+        CreateMessage, Message, MessageRole, decode_meta, decode_misconceptions, decode_signals,
+    },
+    misconception::Misconception,
+    session::SessionStatus,
+    turn::TurnSignals,
+    usage::MessageUsage,
+};
 use chrono::{DateTime, Utc};
-use sqlx::SqlitePool;
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
 use uuid::Uuid;
 
-/// inserts a new message into the database for a given session.
-pub async fn create_message(
+/// inserts the teacher's message and Bodhi's reply, with what generating it cost when a
+/// model wrote it and the signals of a structured reply, in one transaction so a turn is
+/// either saved completely or not at all. a ready or completed session becomes active
+/// with it.
+pub async fn create_exchange(
     pool: &SqlitePool,
     session_id: Uuid,
+    user_message: CreateMessage,
+    assistant_message: CreateMessage,
+    is_partial: bool,
+    usage: Option<MessageUsage>,
+    signals: Option<TurnSignals>,
+) -> Result<(Message, Message), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let exchange = insert_exchange(
+        &mut tx,
+        session_id,
+        user_message,
+        assistant_message,
+        is_partial,
+        usage,
+        signals,
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(exchange)
+}
+
+/// `create_exchange` inside a transaction the caller commits, for what has to be saved
+/// together with the exchange
+pub async fn insert_exchange(
+    conn: &mut SqliteConnection,
+    session_id: Uuid,
+    user_message: CreateMessage,
+    assistant_message: CreateMessage,
+    is_partial: bool,
+    usage: Option<MessageUsage>,
+    signals: Option<TurnSignals>,
+) -> Result<(Message, Message), sqlx::Error> {
+    let user = insert_message(&mut *conn, session_id, user_message, false, None).await?;
+    let mut assistant = insert_message(
+        &mut *conn,
+        session_id,
+        assistant_message,
+        is_partial,
+        signals,
+    )
+    .await?;
+
+    let message_id_str = assistant.id.to_string();
+    let session_id_str = session_id.to_string();
+    let created_at_str = assistant.timestamp.to_rfc3339();
+
+    // e.g. a multiple-choice answer graded without a model cost nothing
+    if let Some(usage) = &usage {
+        sqlx::query!(
+            r#"
+            INSERT INTO message_usage (message_id, session_id, model, prompt_tokens,
+                                       response_tokens, finish_reason, latency_ms, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+            message_id_str,
+            session_id_str,
+            usage.model,
+            usage.prompt_tokens,
+            usage.response_tokens,
+            usage.finish_reason,
+            usage.latency_ms,
+            created_at_str
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    let active = SessionStatus::Active.as_str();
+    let ready = SessionStatus::Ready.as_str();
+    let completed = SessionStatus::Completed.as_str();
+    sqlx::query!(
+        r#"
+        UPDATE sessions
+        SET status = $1, updated_at = $2
+        WHERE id = $3 AND status IN ($4, $5)
+        "#,
+        active,
+        created_at_str,
+        session_id_str,
+        ready,
+        completed
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    assistant.usage = usage;
+    Ok((user, assistant))
+}
+
+async fn insert_message<'e>(
+    executor: impl SqliteExecutor<'e>,
+    session_id: Uuid,
     new_message: CreateMessage,
+    is_partial: bool,
+    signals: Option<TurnSignals>,
 ) -> Result<Message, sqlx::Error> {
     let id = Uuid::new_v4().to_string();
     let session_id_str = session_id.to_string();
@@ -16,29 +122,78 @@ pub async fn create_message(
         MessageRole::Assistant => "Assistant".to_string(),
     };
     let timestamp_str = Utc::now().to_rfc3339();
+    let understanding = signals.as_ref().map(|s| s.understanding);
+    let confusions = signals
+        .as_ref()
+        .map(|s| serde_json::to_string(&s.confusions))
+        .transpose()
+        .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    let concept = signals.as_ref().and_then(|s| s.concept.clone());
+    let quiz = new_message
+        .quiz
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    let debate = new_message
+        .debate
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    let clarity = new_message
+        .clarity
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    let citations = if new_message.citations.is_empty() {
+        None
+    } else {
+        Some(
+            serde_json::to_string(&new_message.citations)
+                .map_err(|e| sqlx::Error::Encode(Box::new(e)))?,
+        )
+    };
 
     let message = sqlx::query!(
         r#"
-        INSERT INTO messages (id, session_id, role, content, timestamp)
-        VALUES ($1, $2, $3, $4, $5)
-        RETURNING id, session_id, role, content, timestamp
+        INSERT INTO messages (id, session_id, role, content, timestamp, is_partial,
+                              understanding, confusions, concept, quiz, debate, clarity, citations)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        RETURNING id, session_id, role, content, timestamp, is_partial, misconceptions
         "#,
         id,
         session_id_str,
         role_str,
         new_message.content,
-        timestamp_str
+        timestamp_str,
+        is_partial,
+        understanding,
+        confusions,
+        concept,
+        quiz,
+        debate,
+        clarity,
+        citations
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
-    let result = Message::from_query_row(
+    let mut result = Message::from_query_row(
         message.id,
         message.session_id,
         message.role,
         message.content,
         message.timestamp,
+        message.is_partial,
+        message.misconceptions,
     )?;
+    result.signals = signals;
+    result.quiz = new_message.quiz;
+    result.debate = new_message.debate;
+    result.clarity = new_message.clarity;
+    result.citations = new_message.citations;
 
     Ok(result)
 }
@@ -52,9 +207,15 @@ pub async fn list_messages_for_session(
 
     let rows = sqlx::query!(
         r#"
-        SELECT id, session_id, role, content, timestamp FROM messages
-        WHERE session_id = $1
-        ORDER BY timestamp ASC
+        SELECT m.id, m.session_id, m.role, m.content, m.timestamp, m.is_partial, m.misconceptions,
+               m.understanding, m.confusions, m.concept, m.quiz, m.debate, m.clarity,
+               m.citations,
+               u.model AS "usage_model?", u.prompt_tokens, u.response_tokens, u.finish_reason,
+               u.latency_ms AS "latency_ms?"
+        FROM messages m
+        LEFT JOIN message_usage u ON u.message_id = m.id
+        WHERE m.session_id = $1
+        ORDER BY m.timestamp ASC
         "#,
         session_id_str
     )
@@ -78,9 +239,48 @@ pub async fn list_messages_for_session(
                 .timestamp
                 .parse::<DateTime<Utc>>()
                 .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            is_partial: row.is_partial,
+            misconceptions: decode_misconceptions(row.misconceptions)?,
+            usage: row.usage_model.map(|model| MessageUsage {
+                model,
+                prompt_tokens: row.prompt_tokens,
+                response_tokens: row.response_tokens,
+                finish_reason: row.finish_reason,
+                latency_ms: row.latency_ms.unwrap_or_default(),
+            }),
+            signals: decode_signals(row.understanding, row.confusions, row.concept)?,
+            quiz: decode_meta(row.quiz)?,
+            debate: decode_meta(row.debate)?,
+            clarity: decode_meta(row.clarity)?,
+            citations: decode_meta(row.citations)?.unwrap_or_default(),
         };
         messages.push(message);
     }
 
     Ok(messages)
 }
+
+/// stores the result of checking a teacher message against the material
+pub async fn set_misconceptions(
+    pool: &SqlitePool,
+    message_id: Uuid,
+    misconceptions: &[Misconception],
+) -> Result<(), sqlx::Error> {
+    let id_str = message_id.to_string();
+    let json =
+        serde_json::to_string(misconceptions).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+    sqlx::query!(
+        r#"
+        UPDATE messages
+        SET misconceptions = $1
+        WHERE id = $2
+        "#,
+        json,
+        id_str
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -1,2 +1,12 @@
+pub mod concepts;
+pub mod conversation_summaries;
+pub mod evaluations;
+pub mod ingestion_jobs;
+pub mod lesson_summaries;
+pub mod material_chunks;
+pub mod materials;
 pub mod messages;
+pub mod personas;
+pub mod quiz_questions;
 pub mod sessions;
+pub mod usage;
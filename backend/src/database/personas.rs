@@ -0,0 +1,32 @@
+use sqlx::SqlitePool;
+
+use crate::models::persona::Persona;
+
+pub async fn get_persona(pool: &SqlitePool, id: &str) -> Result<Persona, sqlx::Error> {
+    let persona = sqlx::query_as!(
+        Persona,
+        r#"
+        SELECT id, name, level, personality, question_style, verbosity FROM personas
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(persona)
+}
+
+pub async fn list_personas(pool: &SqlitePool) -> Result<Vec<Persona>, sqlx::Error> {
+    let personas = sqlx::query_as!(
+        Persona,
+        r#"
+        SELECT id, name, level, personality, question_style, verbosity FROM personas
+        ORDER BY name ASC
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(personas)
+}
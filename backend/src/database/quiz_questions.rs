@@ -0,0 +1,114 @@
+use chrono::Utc;
+use sqlx::{SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+use crate::models::quiz::{QuizGrade, QuizQuestion, QuizQuestionDraft, QuizQuestionRow};
+
+/// stores the questions of a new quiz in the order they will be asked
+pub async fn create_quiz_questions(
+    pool: &SqlitePool,
+    session_id: Uuid,
+    drafts: &[QuizQuestionDraft],
+) -> Result<Vec<QuizQuestion>, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+    let mut tx = pool.begin().await?;
+
+    let mut questions = Vec::with_capacity(drafts.len());
+    for (index, draft) in drafts.iter().enumerate() {
+        let id_str = Uuid::new_v4().to_string();
+        let position = index as i64 + 1;
+        let kind = draft.kind.as_str();
+        let options =
+            serde_json::to_string(&draft.options).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+        let row = sqlx::query_as!(
+            QuizQuestionRow,
+            r#"
+            INSERT INTO quiz_questions (id, session_id, position, kind, question, options, answer, evidence)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, position, kind, question, options, answer, evidence, asked_at, score
+            "#,
+            id_str,
+            session_id_str,
+            position,
+            kind,
+            draft.question,
+            options,
+            draft.answer,
+            draft.evidence
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        questions.push(QuizQuestion::try_from(row)?);
+    }
+
+    tx.commit().await?;
+    Ok(questions)
+}
+
+/// the session's quiz questions in the order they are asked
+pub async fn list_quiz_questions(
+    pool: &SqlitePool,
+    session_id: Uuid,
+) -> Result<Vec<QuizQuestion>, sqlx::Error> {
+    let session_id_str = session_id.to_string();
+
+    let rows = sqlx::query_as!(
+        QuizQuestionRow,
+        r#"
+        SELECT id, position, kind, question, options, answer, evidence, asked_at, score
+        FROM quiz_questions
+        WHERE session_id = $1
+        ORDER BY position ASC
+        "#,
+        session_id_str
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter().map(QuizQuestion::try_from).collect()
+}
+
+/// records the grade of an answered question and that the next one was asked, in the
+/// transaction that saves the exchange so the quiz never skips or repeats a question
+pub async fn advance_quiz(
+    conn: &mut SqliteConnection,
+    graded: Option<(Uuid, &QuizGrade)>,
+    asked: Option<Uuid>,
+) -> Result<(), sqlx::Error> {
+    let now_str = Utc::now().to_rfc3339();
+
+    if let Some((question_id, grade)) = graded {
+        let id_str = question_id.to_string();
+        sqlx::query!(
+            r#"
+            UPDATE quiz_questions
+            SET score = $1, feedback = $2
+            WHERE id = $3
+            "#,
+            grade.score,
+            grade.feedback,
+            id_str
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    if let Some(question_id) = asked {
+        let id_str = question_id.to_string();
+        sqlx::query!(
+            r#"
+            UPDATE quiz_questions
+            SET asked_at = $1
+            WHERE id = $2
+            "#,
+            now_str,
+            id_str
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
@@ -1,8 +1,46 @@
 use chrono::Utc;
-use sqlx::SqlitePool;
+use serde::Serialize;
+use sqlx::{SqliteExecutor, SqlitePool};
 use uuid::Uuid;
 
-use crate::models::session::{CreateSession, Session};
+use crate::{
+    database::{ingestion_jobs::insert_job, materials::insert_material},
+    models::{
+        generation::GenerationSettings,
+        material::{CreateMaterial, MaterialSection, SourcePage},
+        persona::Persona,
+        session::{CreateSession, Session, SessionRow, SessionStatus},
+    },
+};
+
+/// why a session's status couldn't be changed
+#[derive(Debug, thiserror::Error)]
+pub enum StatusError {
+    #[error("a session can't go from {from} to {to}")]
+    InvalidTransition {
+        from: SessionStatus,
+        to: SessionStatus,
+    },
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+// JSON for a list column, NULL when it's empty
+fn encode_list<T: Serialize>(items: &[T]) -> Result<Option<String>, sqlx::Error> {
+    if items.is_empty() {
+        return Ok(None);
+    }
+    serde_json::to_string(items)
+        .map(Some)
+        .map_err(|e| sqlx::Error::Encode(Box::new(e)))
+}
+
+fn encode_settings(settings: Option<&GenerationSettings>) -> Result<Option<String>, sqlx::Error> {
+    settings
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| sqlx::Error::Encode(Box::new(e)))
+}
 
 pub async fn create_session(
     pool: &SqlitePool,
@@ -13,44 +51,80 @@ pub async fn create_session(
     let id_str = id.to_string();
     let created_at_str = now.to_rfc3339();
     let updated_at_str = now.to_rfc3339();
+    let persona_id = new_session
+        .persona_id
+        .unwrap_or_else(|| Persona::DEFAULT_ID.to_string());
+    let generation_settings = encode_settings(new_session.generation_settings.as_ref())?;
+    let mode = new_session.mode.as_str();
+    // the session's text is its first material, unless an upload has yet to be extracted
+    let source = match new_session.upload {
+        Some(_) => None,
+        None => Some(
+            new_session
+                .source
+                .unwrap_or_else(|| CreateMaterial::pasted(new_session.material_text.clone())),
+        ),
+    };
+    let status = match source {
+        Some(_) => SessionStatus::Ready,
+        None => SessionStatus::Processing,
+    }
+    .as_str();
+    let (material_sections, material_pages) = match &source {
+        Some(source) => (
+            encode_list(&source.sections)?,
+            encode_list(&source.source_pages(0))?,
+        ),
+        None => (None, None),
+    };
+    let mut tx = pool.begin().await?;
 
-    let created_session = sqlx::query!(
+    let created_session = sqlx::query_as!(
+        SessionRow,
         r#"
-        INSERT INTO sessions (id, topic, material_text, status, created_at, updated_at, user_id)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
-        RETURNING id, topic, material_text, status, created_at, updated_at, user_id
+        INSERT INTO sessions (id, topic, material_text, status, created_at, updated_at, user_id, persona_id,
+                              generation_settings, mode, material_sections, material_pages)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        RETURNING id, topic, material_text, status, created_at, updated_at, user_id, persona_id,
+                  generation_settings, mode, material_sections, material_pages
         "#,
         id_str,
         new_session.topic,
         new_session.material_text,
-        "created",
+        status,
         created_at_str,
         updated_at_str,
-        "temp_user" // placeholder user_id
+        "temp_user", // placeholder user_id
+        persona_id,
+        generation_settings,
+        mode,
+        material_sections,
+        material_pages
     )
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
 
-    let session = Session::from_strings(
-        created_session.id,
-        created_session.topic,
-        created_session.material_text,
-        created_session.status,
-        created_session.created_at,
-        created_session.updated_at,
-        created_session.user_id.unwrap(),
-    )
-    .unwrap();
+    match (&source, &new_session.upload) {
+        (Some(source), _) => {
+            insert_material(&mut tx, id, 0, source).await?;
+        }
+        (None, Some(upload)) => insert_job(&mut tx, id, upload).await?,
+        (None, None) => {}
+    }
+    tx.commit().await?;
 
-    Ok(session)
+    Session::try_from(created_session)
 }
 
 pub async fn get_session(pool: &SqlitePool, id: Uuid) -> Result<Session, sqlx::Error> {
     let id_str = id.to_string();
 
-    let fetched_session = sqlx::query!(
+    let fetched_session = sqlx::query_as!(
+        SessionRow,
         r#"
-      SELECT * FROM sessions
+      SELECT id, topic, material_text, status, created_at, updated_at, user_id, persona_id,
+             generation_settings, mode, material_sections, material_pages
+      FROM sessions
       WHERE id = $1
       "#,
         id_str,
@@ -58,46 +132,139 @@ pub async fn get_session(pool: &SqlitePool, id: Uuid) -> Result<Session, sqlx::E
     .fetch_one(pool)
     .await?;
 
-    let session = Session::from_strings(
-        fetched_session.id,
-        fetched_session.topic,
-        fetched_session.material_text,
-        fetched_session.status,
-        fetched_session.created_at,
-        fetched_session.updated_at,
-        fetched_session.user_id.unwrap(),
-    )
-    .unwrap();
-
-    Ok(session)
+    Session::try_from(fetched_session)
 }
 
 pub async fn list_sessions(pool: &SqlitePool) -> Result<Vec<Session>, sqlx::Error> {
-    let fetched_sessions = sqlx::query!(
+    let fetched_sessions = sqlx::query_as!(
+        SessionRow,
         r#"
-        SELECT * FROM sessions
+        SELECT id, topic, material_text, status, created_at, updated_at, user_id, persona_id,
+               generation_settings, mode, material_sections, material_pages
+        FROM sessions
         ORDER BY created_at DESC
         "#
     )
     .fetch_all(pool)
     .await?;
-    let sessions: Result<Vec<Session>, _> = fetched_sessions
+
+    fetched_sessions
         .into_iter()
-        .map(|fetched_session| {
-            Session::from_strings(
-                fetched_session.id,
-                fetched_session.topic,
-                fetched_session.material_text,
-                fetched_session.status,
-                fetched_session.created_at,
-                fetched_session.updated_at,
-                fetched_session.user_id.unwrap(),
-            )
-        })
-        .collect();
-
-    let sessions = sessions.map_err(|_| sqlx::Error::RowNotFound)?;
-    Ok(sessions)
+        .map(Session::try_from)
+        .collect()
+}
+
+/// replaces the session's generation overrides, `None` goes back to the app defaults
+pub async fn update_generation_settings(
+    pool: &SqlitePool,
+    id: Uuid,
+    settings: Option<GenerationSettings>,
+) -> Result<Session, sqlx::Error> {
+    let id_str = id.to_string();
+    let updated_at_str = Utc::now().to_rfc3339();
+    let settings = encode_settings(settings.as_ref())?;
+
+    let updated_session = sqlx::query_as!(
+        SessionRow,
+        r#"
+        UPDATE sessions
+        SET generation_settings = $1, updated_at = $2
+        WHERE id = $3
+        RETURNING id, topic, material_text, status, created_at, updated_at, user_id, persona_id,
+                  generation_settings, mode, material_sections, material_pages
+        "#,
+        settings,
+        updated_at_str,
+        id_str,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Session::try_from(updated_session)
+}
+
+/// moves the session to `next` if its current status allows it. a session that's
+/// already there is left as it is
+pub async fn update_status(
+    pool: &SqlitePool,
+    id: Uuid,
+    next: SessionStatus,
+) -> Result<Session, StatusError> {
+    let session = get_session(pool, id).await?;
+    if session.status == next {
+        return Ok(session);
+    }
+    if !session.status.can_become(next) {
+        return Err(StatusError::InvalidTransition {
+            from: session.status,
+            to: next,
+        });
+    }
+
+    let id_str = id.to_string();
+    let current = session.status.as_str();
+    let next_str = next.as_str();
+    let updated_at_str = Utc::now().to_rfc3339();
+
+    // the status is checked again in the update in case it changed in between
+    let updated_session = sqlx::query_as!(
+        SessionRow,
+        r#"
+        UPDATE sessions
+        SET status = $1, updated_at = $2
+        WHERE id = $3 AND status = $4
+        RETURNING id, topic, material_text, status, created_at, updated_at, user_id, persona_id,
+                  generation_settings, mode, material_sections, material_pages
+        "#,
+        next_str,
+        updated_at_str,
+        id_str,
+        current,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match updated_session {
+        Some(row) => Ok(Session::try_from(row)?),
+        None => Err(StatusError::InvalidTransition {
+            from: session.status,
+            to: next,
+        }),
+    }
+}
+
+/// replaces the session's assembled material after its materials changed
+pub async fn update_material<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id: Uuid,
+    material_text: &str,
+    sections: &[MaterialSection],
+    pages: &[SourcePage],
+) -> Result<Session, sqlx::Error> {
+    let id_str = id.to_string();
+    let updated_at_str = Utc::now().to_rfc3339();
+    let sections = encode_list(sections)?;
+    let pages = encode_list(pages)?;
+
+    let updated_session = sqlx::query_as!(
+        SessionRow,
+        r#"
+        UPDATE sessions
+        SET material_text = $1, material_sections = $2, material_pages = $3, updated_at = $4
+        WHERE id = $5
+        RETURNING id, topic, material_text, status, created_at, updated_at, user_id, persona_id,
+                  generation_settings, mode, material_sections, material_pages
+        "#,
+        material_text,
+        sections,
+        pages,
+        updated_at_str,
+        id_str,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Session::try_from(updated_session)
 }
 
 pub async fn delete_session(pool: &SqlitePool, id: Uuid) -> Result<(), sqlx::Error> {
@@ -0,0 +1,140 @@
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::models::usage::{CallPurpose, DailyUsage, MessageUsage, PurposeUsage, SessionUsage};
+
+/// records what a model call outside of Bodhi's replies cost the session
+pub async fn record_call(
+    pool: &SqlitePool,
+    session_id: Uuid,
+    purpose: CallPurpose,
+    usage: &MessageUsage,
+) -> Result<(), sqlx::Error> {
+    let session_id_str = session_id.to_string();
+    let purpose_str = purpose.as_str();
+    let created_at_str = Utc::now().to_rfc3339();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO call_usage (session_id, purpose, model, prompt_tokens, response_tokens,
+                                finish_reason, latency_ms, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        session_id_str,
+        purpose_str,
+        usage.model,
+        usage.prompt_tokens,
+        usage.response_tokens,
+        usage.finish_reason,
+        usage.latency_ms,
+        created_at_str
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// usage totals per session, most expensive first
+pub async fn usage_by_session(pool: &SqlitePool) -> Result<Vec<SessionUsage>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        WITH usage AS (
+            SELECT session_id, prompt_tokens, response_tokens, latency_ms, 1 AS is_reply
+            FROM message_usage
+            UNION ALL
+            SELECT session_id, prompt_tokens, response_tokens, latency_ms, 0 AS is_reply
+            FROM call_usage
+        )
+        SELECT u.session_id AS "session_id!: String",
+               s.topic AS "topic!: String",
+               SUM(u.is_reply) AS "messages!: i64",
+               COUNT(*) - SUM(u.is_reply) AS "calls!: i64",
+               COALESCE(SUM(u.prompt_tokens), 0) AS "prompt_tokens!: i64",
+               COALESCE(SUM(u.response_tokens), 0) AS "response_tokens!: i64",
+               COALESCE(AVG(CASE WHEN u.is_reply THEN u.latency_ms END), 0.0)
+                   AS "avg_latency_ms!: f64"
+        FROM usage u
+        JOIN sessions s ON s.id = u.session_id
+        GROUP BY u.session_id
+        ORDER BY COALESCE(SUM(u.prompt_tokens), 0) + COALESCE(SUM(u.response_tokens), 0) DESC
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(SessionUsage {
+                session_id: Uuid::parse_str(&row.session_id)
+                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+                topic: row.topic,
+                messages: row.messages,
+                calls: row.calls,
+                prompt_tokens: row.prompt_tokens,
+                response_tokens: row.response_tokens,
+                avg_latency_ms: row.avg_latency_ms,
+            })
+        })
+        .collect()
+}
+
+/// usage totals per UTC day and model, newest day first
+pub async fn usage_by_day(pool: &SqlitePool) -> Result<Vec<DailyUsage>, sqlx::Error> {
+    let usage = sqlx::query_as!(
+        DailyUsage,
+        r#"
+        WITH usage AS (
+            SELECT model, prompt_tokens, response_tokens, latency_ms, created_at, 1 AS is_reply
+            FROM message_usage
+            UNION ALL
+            SELECT model, prompt_tokens, response_tokens, latency_ms, created_at, 0 AS is_reply
+            FROM call_usage
+        )
+        SELECT substr(created_at, 1, 10) AS "day!: String",
+               model AS "model!: String",
+               SUM(is_reply) AS "messages!: i64",
+               COUNT(*) - SUM(is_reply) AS "calls!: i64",
+               COALESCE(SUM(prompt_tokens), 0) AS "prompt_tokens!: i64",
+               COALESCE(SUM(response_tokens), 0) AS "response_tokens!: i64",
+               COALESCE(AVG(CASE WHEN is_reply THEN latency_ms END), 0.0)
+                   AS "avg_latency_ms!: f64"
+        FROM usage
+        GROUP BY 1, model
+        ORDER BY 1 DESC, model ASC
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(usage)
+}
+
+/// usage totals per purpose, the most expensive first
+pub async fn usage_by_purpose(pool: &SqlitePool) -> Result<Vec<PurposeUsage>, sqlx::Error> {
+    let usage = sqlx::query_as!(
+        PurposeUsage,
+        r#"
+        WITH usage AS (
+            SELECT 'reply' AS purpose, prompt_tokens, response_tokens, latency_ms
+            FROM message_usage
+            UNION ALL
+            SELECT purpose, prompt_tokens, response_tokens, latency_ms
+            FROM call_usage
+        )
+        SELECT purpose AS "purpose!: String",
+               COUNT(*) AS "calls!: i64",
+               COALESCE(SUM(prompt_tokens), 0) AS "prompt_tokens!: i64",
+               COALESCE(SUM(response_tokens), 0) AS "response_tokens!: i64",
+               AVG(latency_ms) AS "avg_latency_ms!: f64"
+        FROM usage
+        GROUP BY purpose
+        ORDER BY COALESCE(SUM(prompt_tokens), 0) + COALESCE(SUM(response_tokens), 0) DESC
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(usage)
+}
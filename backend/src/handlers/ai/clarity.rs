@@ -0,0 +1,37 @@
+use std::env;
+
+use crate::{
+    handlers::ai::{prompt::JARGON_QUESTION_PROMPT, providers::LlmRequest},
+    models::{
+        clarity::ClarityScore,
+        session::{Session, SessionMode},
+    },
+};
+
+/// whether Bodhi asks about jargon the teacher hasn't explained, from `JARGON_QUESTIONS`
+/// (on or off). off by default, the chat shows the unexplained terms either way
+pub fn jargon_questions_enabled() -> bool {
+    match env::var("JARGON_QUESTIONS").as_deref() {
+        Ok("off") | Err(_) => false,
+        Ok("on") => true,
+        Ok(other) => {
+            tracing::warn!("Unknown JARGON_QUESTIONS '{}', using 'off'", other);
+            false
+        }
+    }
+}
+
+/// lets Bodhi ask what an unexplained term of the teacher's message means, only while
+/// being taught
+pub fn ask_about_jargon(request: &mut LlmRequest, session: &Session, clarity: &ClarityScore) {
+    if session.mode != SessionMode::Teach
+        || clarity.undefined.is_empty()
+        || !jargon_questions_enabled()
+    {
+        return;
+    }
+
+    request
+        .system_prompt
+        .push_str(&JARGON_QUESTION_PROMPT.replace("{}", &clarity.undefined.join(", ")));
+}
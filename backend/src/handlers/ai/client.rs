@@ -1,109 +1,450 @@
-use std::env;
+use std::time::Instant;
 
-use futures_util::Stream;
-use reqwest::Client;
-use tokio_stream::StreamExt;
+use anyhow::anyhow;
+use serde::de::DeserializeOwned;
+use sqlx::SqlitePool;
+use uuid::Uuid;
 
-use crate::handlers::ai::{
-    model::{Content, GeminiRequest, GeminiResponse, Part},
-    prompt::CREATE_BODHI_PROMPT,
+use crate::{
+    citations::{cite_excerpt, mark_pages, page_marker},
+    database::{
+        conversation_summaries::{get_conversation_summary, upsert_conversation_summary},
+        usage::record_call,
+    },
+    handlers::ai::{
+        context::{ContextBudget, estimate_tokens, estimate_turn_tokens, messages_to_fold},
+        error::AiError,
+        prompt::{
+            CREATE_BODHI_PROMPT, DEBATE_BODHI_PROMPT, DEBATE_OPENING_PROMPT, DEBATE_STANCE_PROMPT,
+            DEBATE_TURN_PROMPT, EARLIER_LESSON_PROMPT, PAGE_CITATION_PROMPT,
+            STRUCTURED_TURN_PROMPT, SUMMARIZE_HISTORY_PROMPT,
+        },
+        providers::{ChatRole, ChatTurn, Completion, LlmProvider, LlmRequest, TokenUsage},
+        turn::{bodhi_turn_schema, debate_turn_schema, structured_turns_enabled},
+    },
+    models::{
+        generation::GenerationSettings,
+        material::{Material, MaterialSection, SourcePage},
+        message::{Message, MessageRole},
+        persona::Persona,
+        session::{Session, SessionMode},
+        usage::{CallPurpose, MessageUsage},
+    },
+    retrieval::Retriever,
 };
 
-pub async fn call_gemini_api(
-    study_material: String,
-    mut conversation_history: Vec<Content>,
-) -> Result<String, anyhow::Error> {
-    let api_key = env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY must be set");
-    let api_url =
-        "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:generateContent";
-
-    let client = Client::new();
-
-    let system_prompt = CREATE_BODHI_PROMPT.replace("{}", &study_material);
-
-    // combine the system prompt with the actual user/model conversation
-    let mut full_prompt_contents = vec![
-        Content {
-            role: "user".into(),
-            parts: vec![Part {
-                text: system_prompt,
-            }],
-        },
-        Content {
-            role: "model".into(),
-            parts: vec![Part {
-                text: "I'm ready to learn! Let's begin.".into(),
-            }],
-        },
-    ];
+// a few pages; anything longer is narrowed down to the passages the teacher is talking about
+const MAX_VERBATIM_MATERIAL_TOKENS: usize = 2_000;
 
-    full_prompt_contents.append(&mut conversation_history);
+/// fills the Bodhi prompt of the session's mode with its persona, study material and
+/// rolling summary. in a debate `stance` is the position Bodhi took so far, if any
+pub fn build_system_prompt(
+    study_material: &str,
+    persona: &Persona,
+    mode: SessionMode,
+    stance: Option<&str>,
+    summary: Option<&str>,
+) -> String {
+    let personality = persona
+        .personality
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| format!("- {}", line.trim()))
+        .collect::<Vec<_>>()
+        .join("\n");
 
-    let payload = GeminiRequest {
-        contents: full_prompt_contents,
+    let template = match mode {
+        SessionMode::Debate => DEBATE_BODHI_PROMPT,
+        _ => CREATE_BODHI_PROMPT,
     };
+    let stance = match stance {
+        Some(stance) => DEBATE_STANCE_PROMPT.replace("{}", stance),
+        None => DEBATE_OPENING_PROMPT.to_string(),
+    };
+
+    let mut prompt = template
+        .replace("{stance}", &stance)
+        .replace("{level}", &persona.level)
+        .replace("{personality}", &personality)
+        .replace("{question_style}", &persona.question_style)
+        .replace("{verbosity}", &persona.verbosity)
+        .replace("{material}", study_material);
+
+    if let Some(summary) = summary {
+        prompt.push_str(&EARLIER_LESSON_PROMPT.replace("{}", summary));
+    }
+
+    prompt
+}
 
-    // send the request and get the response
-    let response = client
-        .post(api_url)
-        .query(&[("key", &api_key)])
-        .json(&payload)
-        .send()
-        .await?
-        .json::<GeminiResponse>()
-        .await?;
-
-    // extract the text from the response
-    let bot_response_text = response
-        .candidates
-        .get(0)
-        .and_then(|c| c.content.parts.get(0))
-        .map(|p| p.text.clone())
-        .unwrap_or_else(|| "I'm sorry, I'm not sure how to respond to that.".to_string());
-
-    Ok(bot_response_text)
+/// a session's materials as the one text Bodhi studies
+pub struct AssembledMaterial {
+    pub text: String,
+    pub sections: Vec<MaterialSection>,
+    pub pages: Vec<SourcePage>,
 }
 
-pub fn stream_gemini_api(
-    study_material: String,
-    mut conversation_history: Vec<Content>,
-) -> impl Stream<Item = Result<String, anyhow::Error>> {
-    async_stream::try_stream! {
-        let api_key = env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY must be set");
-        let api_url = "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:streamGenerateContent";
-
-        let client = Client::new();
-
-        let system_prompt = CREATE_BODHI_PROMPT.replace("{}", &study_material);
-        let mut full_prompt_contents = vec![
-            Content { role: "user".into(), parts: vec![Part { text: system_prompt }] },
-            Content { role: "model".into(), parts: vec![Part { text: "I'm ready to learn! Let's begin.".into() }] }
-        ];
-        full_prompt_contents.append(&mut conversation_history);
-
-        let payload = GeminiRequest {
-            contents: full_prompt_contents,
+/// the session's materials as the one text Bodhi studies, with their sections and pages.
+/// several sources each start with a label naming the file, a single one is used as it is
+pub fn assemble_material(materials: &[Material]) -> AssembledMaterial {
+    if let [material] = materials {
+        return AssembledMaterial {
+            text: material.extracted_text.clone(),
+            sections: material.sections.clone(),
+            pages: material.source_pages(0),
         };
+    }
 
-        // get a stream of bytes from the response
-        let mut byte_stream = client
-            .post(api_url)
-            .query(&[("key", &api_key)])
-            .json(&payload)
-            .send()
-            .await?
-            .bytes_stream();
-
-        // process the stream
-        while let Some(chunk) = byte_stream.next().await {
-            let chunk = chunk?;
-            // NOTE: This is a simplification. It assumes each chunk from the API is a self-contained, valid JSON object.
-            // A more robust solution would buffer bytes and parse multiple JSON objects from a single chunk.
-            if let Ok(response) = serde_json::from_slice::<GeminiResponse>(&chunk) {
-                if let Some(text) = response.candidates.get(0).and_then(|c| c.content.parts.get(0)).map(|p| p.text.clone()) {
-                    yield text;
-                }
+    let mut text = String::new();
+    let mut sections = Vec::new();
+    let mut pages = Vec::new();
+    for (i, material) in materials.iter().enumerate() {
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+
+        let page_count = match material.page_count {
+            Some(1) => ", 1 page".to_string(),
+            Some(count) => format!(", {} pages", count),
+            None => String::new(),
+        };
+        sections.push(MaterialSection {
+            title: material.source_name.clone(),
+            level: 1,
+            offset: text.len(),
+        });
+        text.push_str(&format!(
+            "[Source {} of {}: {}{}]\n\n",
+            i + 1,
+            materials.len(),
+            material.source_name,
+            page_count
+        ));
+
+        let shift = text.len();
+        sections.extend(material.sections.iter().map(|section| MaterialSection {
+            offset: section.offset + shift,
+            ..section.clone()
+        }));
+        pages.extend(material.source_pages(shift));
+        text.push_str(&material.extracted_text);
+    }
+
+    AssembledMaterial {
+        text,
+        sections,
+        pages,
+    }
+}
+
+/// builds the provider neutral request for Bodhi's next turn within the model's context budget.
+///
+/// `pending_user_text` is a teacher message that isn't saved yet. when the conversation no
+/// longer fits, the oldest turns are folded into the session's persisted rolling summary.
+/// short material is sent whole, longer material only as the passages `retriever` finds
+/// for the latest teacher message.
+pub async fn prepare_bodhi_request(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    retriever: &Retriever,
+    session: &Session,
+    persona: &Persona,
+    history: Vec<Message>,
+    pending_user_text: Option<&str>,
+) -> Result<LlmRequest, anyhow::Error> {
+    // session overrides win over the app defaults
+    let settings = session
+        .generation_settings
+        .clone()
+        .unwrap_or_default()
+        .or(GenerationSettings::defaults());
+
+    let budget = ContextBudget::for_model(llm.model());
+    let prompt_budget = budget.prompt_tokens(settings.max_output_tokens.unwrap_or(0) as usize);
+    let pending_tokens = pending_user_text.map(estimate_turn_tokens).unwrap_or(0);
+
+    // the position Bodhi defends survives the turns that get folded away
+    let stance = history
+        .iter()
+        .rev()
+        .filter_map(|msg| msg.debate.as_ref())
+        .map(|debate| debate.stance.clone())
+        .find(|stance| !stance.is_empty());
+
+    // turns already folded into the summary are never sent again
+    let stored_summary = get_conversation_summary(pool, session.id).await?;
+    let mut summary = stored_summary.as_ref().map(|s| s.summary.clone());
+    let mut history: Vec<Message> = match &stored_summary {
+        Some(stored) => history
+            .into_iter()
+            .filter(|msg| msg.timestamp > stored.covered_until)
+            .collect(),
+        None => history,
+    };
+
+    // the material may claim at most half of the budget before old turns get folded,
+    // beyond that fewer passages are retrieved instead
+    let material_tokens = estimate_tokens(&session.material_text).min(prompt_budget / 2);
+    let base_tokens = estimate_tokens(&build_system_prompt(
+        "",
+        persona,
+        session.mode,
+        stance.as_deref(),
+        summary.as_deref(),
+    ));
+    let fold = messages_to_fold(
+        &history,
+        base_tokens + material_tokens + pending_tokens,
+        prompt_budget,
+        budget.recent_turns,
+    );
+
+    if fold > 0 {
+        let folded: Vec<Message> = history.drain(..fold).collect();
+        match summarize_turns(pool, llm, session.id, summary.as_deref(), &folded).await {
+            Ok(new_summary) => {
+                let covered_until = folded[folded.len() - 1].timestamp;
+                upsert_conversation_summary(pool, session.id, &new_summary, covered_until).await?;
+                summary = Some(new_summary);
             }
+            // the old turns are still dropped for this request, they'll be folded next time
+            Err(e) => tracing::warn!("Failed to summarise older turns: {}", e),
         }
     }
+
+    // whatever is left of the budget goes to the material
+    let turn_tokens: usize = history
+        .iter()
+        .map(|msg| estimate_turn_tokens(&msg.content))
+        .sum();
+    let base_tokens = estimate_tokens(&build_system_prompt(
+        "",
+        persona,
+        session.mode,
+        stance.as_deref(),
+        summary.as_deref(),
+    ));
+    let material_budget = prompt_budget.saturating_sub(base_tokens + turn_tokens + pending_tokens);
+
+    // PDF pages are marked so Bodhi can cite them
+    let pages = &session.material_pages;
+    let material = if estimate_tokens(&session.material_text)
+        <= material_budget.min(MAX_VERBATIM_MATERIAL_TOKENS)
+    {
+        mark_pages(&session.material_text, pages)
+    } else {
+        let query = pending_user_text
+            .or_else(|| {
+                history
+                    .iter()
+                    .rev()
+                    .find(|msg| matches!(msg.role, MessageRole::User))
+                    .map(|msg| msg.content.as_str())
+            })
+            .unwrap_or(&session.topic);
+
+        retriever
+            .retrieve(
+                pool,
+                session.id,
+                &session.material_text,
+                query,
+                material_budget,
+            )
+            .await?
+            .into_iter()
+            .map(
+                |chunk| match cite_excerpt(&session.material_text, pages, &chunk.content) {
+                    Some(citation) => {
+                        format!("{}\n{}", page_marker(&citation, pages), chunk.content)
+                    }
+                    None => chunk.content,
+                },
+            )
+            .collect::<Vec<_>>()
+            .join("\n\n[...]\n\n")
+    };
+
+    let mut system_prompt = build_system_prompt(
+        &material,
+        persona,
+        session.mode,
+        stance.as_deref(),
+        summary.as_deref(),
+    );
+    if !pages.is_empty() {
+        system_prompt.push_str(PAGE_CITATION_PROMPT);
+    }
+    // a debate is always structured, its verdicts on the teacher's arguments are the point
+    let response_schema = match session.mode {
+        SessionMode::Debate => {
+            system_prompt.push_str(DEBATE_TURN_PROMPT);
+            Some(debate_turn_schema())
+        }
+        _ => structured_turns_enabled().then(|| {
+            system_prompt.push_str(STRUCTURED_TURN_PROMPT);
+            bodhi_turn_schema()
+        }),
+    };
+
+    // convert our internal Message structs to the provider neutral turns
+    let mut turns: Vec<ChatTurn> = history
+        .into_iter()
+        .map(|msg| ChatTurn {
+            role: match msg.role {
+                MessageRole::User => ChatRole::User,
+                MessageRole::Assistant => ChatRole::Assistant,
+            },
+            text: msg.content,
+        })
+        .collect();
+
+    if let Some(text) = pending_user_text {
+        turns.push(ChatTurn {
+            role: ChatRole::User,
+            text: text.to_string(),
+        });
+    }
+
+    Ok(LlmRequest {
+        system_prompt,
+        turns,
+        settings,
+        response_schema,
+    })
+}
+
+/// merges `messages` into the previous rolling summary
+async fn summarize_turns(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    session_id: Uuid,
+    previous_summary: Option<&str>,
+    messages: &[Message],
+) -> Result<String, AiError> {
+    let transcript = format_transcript(messages);
+
+    let request = LlmRequest {
+        system_prompt: SUMMARIZE_HISTORY_PROMPT.to_string(),
+        turns: vec![ChatTurn {
+            role: ChatRole::User,
+            text: format!(
+                "Previous summary:\n{}\n\nNew conversation:\n{}",
+                previous_summary.unwrap_or("(none yet)"),
+                transcript
+            ),
+        }],
+        settings: GenerationSettings {
+            temperature: Some(0.2),
+            max_output_tokens: Some(512),
+            ..GenerationSettings::defaults()
+        },
+        response_schema: None,
+    };
+
+    let completion =
+        complete_and_record(pool, llm, session_id, CallPurpose::HistorySummary, request).await?;
+    Ok(completion.text)
+}
+
+/// renders messages as a plain "Teacher: ... / Bodhi: ..." transcript
+pub fn format_transcript(messages: &[Message]) -> String {
+    messages
+        .iter()
+        .map(|msg| {
+            let speaker = match msg.role {
+                MessageRole::User => "Teacher",
+                MessageRole::Assistant => "Bodhi",
+            };
+            format!("{}: {}", speaker, msg.content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// the whole lesson as a transcript of at most `max_tokens`, for grading or summarizing it.
+///
+/// turns already folded into the rolling summary are replaced by the summary and the
+/// oldest remaining turns beyond the budget are left out. also returns the turns that
+/// made it into the transcript.
+pub async fn lesson_transcript(
+    pool: &SqlitePool,
+    session_id: Uuid,
+    history: Vec<Message>,
+    max_tokens: usize,
+    recent_turns: usize,
+) -> Result<(Vec<Message>, String), sqlx::Error> {
+    let stored_summary = get_conversation_summary(pool, session_id).await?;
+    let mut history: Vec<Message> = match &stored_summary {
+        Some(stored) => history
+            .into_iter()
+            .filter(|msg| msg.timestamp > stored.covered_until)
+            .collect(),
+        None => history,
+    };
+
+    let fold = messages_to_fold(&history, 0, max_tokens, recent_turns);
+    history.drain(..fold);
+
+    let mut transcript = format_transcript(&history);
+    if let Some(stored) = &stored_summary {
+        transcript = format!(
+            "{}\n\n{}",
+            EARLIER_LESSON_PROMPT.replace("{}", &stored.summary).trim(),
+            transcript
+        );
+    }
+
+    Ok((history, transcript))
+}
+
+/// parses a reply that was asked to be JSON, tolerating markdown fences and chatter around it
+pub fn parse_json_reply<T: DeserializeOwned>(reply: &str) -> Result<T, anyhow::Error> {
+    let start = reply.find('{');
+    let end = reply.rfind('}');
+
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => Ok(serde_json::from_str(&reply[start..=end])?),
+        _ => Err(anyhow!("Reply contains no JSON object: {}", reply)),
+    }
+}
+
+/// what generating a reply cost, `started` is when the request was sent
+pub fn message_usage(
+    llm: &dyn LlmProvider,
+    usage: Option<TokenUsage>,
+    finish_reason: Option<String>,
+    started: Instant,
+) -> MessageUsage {
+    MessageUsage {
+        model: llm.model().to_string(),
+        prompt_tokens: usage.map(|u| i64::from(u.prompt_tokens)),
+        response_tokens: usage.map(|u| i64::from(u.response_tokens)),
+        finish_reason,
+        latency_ms: started.elapsed().as_millis() as i64,
+    }
+}
+
+/// sends a request made for the session besides Bodhi's replies and records what it cost
+/// under `purpose`. failing to record it is only logged
+pub async fn complete_and_record(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    session_id: Uuid,
+    purpose: CallPurpose,
+    request: LlmRequest,
+) -> Result<Completion, AiError> {
+    let started = Instant::now();
+    let completion = llm.complete(request).await?;
+
+    let usage = message_usage(
+        llm,
+        completion.usage,
+        completion.finish_reason.clone(),
+        started,
+    );
+    if let Err(e) = record_call(pool, session_id, purpose, &usage).await {
+        tracing::error!("Failed to record {} usage: {}", purpose.as_str(), e);
+    }
+    Ok(completion)
 }
@@ -0,0 +1,255 @@
+use futures_util::{FutureExt, future::BoxFuture};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    database::concepts::{add_message_concepts, list_concepts, merge_concepts},
+    handlers::ai::{
+        client::{complete_and_record, parse_json_reply},
+        context::{ContextBudget, estimate_tokens},
+        error::AiError,
+        prompt::{EXTRACT_CONCEPTS_PROMPT, MAP_CONCEPTS_PROMPT},
+        providers::{ChatRole, ChatTurn, LlmProvider, LlmRequest, SharedLlmProvider},
+    },
+    models::{
+        concept::{Concept, ConceptDraft, CoverageDepth},
+        generation::GenerationSettings,
+        session::Session,
+        usage::CallPurpose,
+    },
+    retrieval::chunking::chunk_material,
+};
+
+// enough for a lesson plan, few enough to read at a glance
+const MAX_CONCEPTS: usize = 15;
+// a name and one sentence per concept
+const EXTRACTION_REPLY_TOKENS: u32 = 1024;
+// longer material is sampled evenly instead of sent whole
+const MAX_EXTRACTION_MATERIAL_TOKENS: usize = 12_000;
+// "ok" or "next please" can't explain anything
+const MIN_MAPPED_WORDS: usize = 4;
+
+/// concepts a teacher message may still be running the mapping for
+pub type CoverageCheck = BoxFuture<'static, Vec<(Uuid, CoverageDepth)>>;
+
+#[derive(Deserialize)]
+struct ExtractionReply {
+    #[serde(default)]
+    concepts: Vec<ConceptDraft>,
+}
+
+#[derive(Deserialize)]
+struct MappingReply {
+    #[serde(default)]
+    covered: Vec<CoveredConcept>,
+}
+
+#[derive(Deserialize)]
+struct CoveredConcept {
+    concept: usize, // number in the list sent to the model, starting at 1
+    depth: CoverageDepth,
+}
+
+/// asks the model for the key concepts of the session's material, in the order it
+/// introduces them
+pub async fn extract_concepts(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    session: &Session,
+) -> Result<Vec<ConceptDraft>, AiError> {
+    let budget = ContextBudget::for_model(llm.model());
+    let material_budget = budget
+        .prompt_tokens(EXTRACTION_REPLY_TOKENS as usize)
+        .saturating_sub(estimate_tokens(EXTRACT_CONCEPTS_PROMPT))
+        .min(MAX_EXTRACTION_MATERIAL_TOKENS);
+
+    let request = LlmRequest {
+        system_prompt: EXTRACT_CONCEPTS_PROMPT.to_string(),
+        turns: vec![ChatTurn {
+            role: ChatRole::User,
+            text: format!(
+                "Study material:\n{}",
+                sample_material(&session.material_text, material_budget)
+            ),
+        }],
+        settings: GenerationSettings {
+            temperature: Some(0.2),
+            max_output_tokens: Some(EXTRACTION_REPLY_TOKENS),
+            ..GenerationSettings::defaults()
+        },
+        response_schema: None,
+    };
+
+    let reply = complete_and_record(
+        pool,
+        llm,
+        session.id,
+        CallPurpose::ConceptExtraction,
+        request,
+    )
+    .await?
+    .text;
+    let parsed: ExtractionReply =
+        parse_json_reply(&reply).map_err(|e| AiError::Malformed(e.to_string()))?;
+
+    // drop blanks and duplicates the model sometimes repeats
+    let mut concepts: Vec<ConceptDraft> = Vec::new();
+    for mut draft in parsed.concepts {
+        draft.name = draft.name.trim().to_string();
+        draft.description = draft.description.trim().to_string();
+        if !draft.name.is_empty()
+            && !concepts
+                .iter()
+                .any(|c| c.name.eq_ignore_ascii_case(&draft.name))
+        {
+            concepts.push(draft);
+        }
+    }
+    concepts.truncate(MAX_CONCEPTS);
+
+    Ok(concepts)
+}
+
+/// passages spread evenly over the whole material, so concepts from its end are
+/// found as well as those from its start
+pub fn sample_material(material: &str, max_tokens: usize) -> String {
+    if estimate_tokens(material) <= max_tokens {
+        return material.to_string();
+    }
+
+    let chunks = chunk_material(material);
+    let total: usize = chunks.iter().map(|c| estimate_tokens(c)).sum();
+    // keep every nth passage
+    let step = total.div_ceil(max_tokens.max(1)).max(1);
+
+    chunks
+        .into_iter()
+        .step_by(step)
+        .collect::<Vec<_>>()
+        .join("\n\n[...]\n\n")
+}
+
+/// extracts the session's concepts and merges them into any it had
+pub async fn index_concepts(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    session: &Session,
+) -> Result<Vec<Concept>, anyhow::Error> {
+    let drafts = extract_concepts(pool, llm, session).await?;
+    Ok(merge_concepts(pool, session.id, &drafts).await?)
+}
+
+/// the session's concepts, extracted first if that never happened or failed before
+pub async fn ensure_concepts(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    session: &Session,
+) -> Result<Vec<Concept>, anyhow::Error> {
+    let concepts = list_concepts(pool, session.id).await?;
+    if !concepts.is_empty() {
+        return Ok(concepts);
+    }
+
+    index_concepts(pool, llm, session).await
+}
+
+/// asks the model which of `concepts` the teacher's `text` explains.
+/// best effort: a failed mapping is logged and counts as nothing covered.
+pub async fn map_message(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    session_id: Uuid,
+    concepts: &[Concept],
+    text: &str,
+) -> Vec<(Uuid, CoverageDepth)> {
+    if concepts.is_empty() || text.split_whitespace().count() < MIN_MAPPED_WORDS {
+        return Vec::new();
+    }
+
+    let list = concepts
+        .iter()
+        .enumerate()
+        .map(|(i, c)| format!("{}. {}: {}", i + 1, c.name, c.description))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let request = LlmRequest {
+        system_prompt: MAP_CONCEPTS_PROMPT.replace("{concepts}", &list),
+        turns: vec![ChatTurn {
+            role: ChatRole::User,
+            text: format!("Teacher's message:\n{}", text),
+        }],
+        settings: GenerationSettings {
+            temperature: Some(0.0),
+            max_output_tokens: Some(256),
+            ..GenerationSettings::defaults()
+        },
+        response_schema: None,
+    };
+
+    let reply = match complete_and_record(
+        pool,
+        llm,
+        session_id,
+        CallPurpose::ConceptMapping,
+        request,
+    )
+    .await
+    {
+        Ok(completion) => completion.text,
+        Err(e) => {
+            tracing::warn!("Concept mapping failed: {}", e);
+            return Vec::new();
+        }
+    };
+
+    match parse_json_reply::<MappingReply>(&reply) {
+        Ok(parsed) => parsed
+            .covered
+            .into_iter()
+            .filter_map(|covered| {
+                let concept = concepts.get(covered.concept.checked_sub(1)?)?;
+                Some((concept.id, covered.depth))
+            })
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Malformed concept mapping reply: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// starts mapping the teacher's `text` to the session's concepts in the background
+pub fn start_coverage(
+    pool: &SqlitePool,
+    llm: &SharedLlmProvider,
+    session: &Session,
+    text: &str,
+) -> CoverageCheck {
+    let (pool, llm, session, text) = (pool.clone(), llm.clone(), session.clone(), text.to_string());
+
+    let handle = tokio::spawn(async move {
+        match ensure_concepts(&pool, llm.as_ref(), &session).await {
+            Ok(concepts) => map_message(&pool, llm.as_ref(), session.id, &concepts, &text).await,
+            Err(e) => {
+                tracing::warn!("No concepts for session {}: {}", session.id, e);
+                Vec::new()
+            }
+        }
+    });
+
+    async move { handle.await.unwrap_or_default() }.boxed()
+}
+
+/// waits for `check` and stores its result for the teacher's saved message
+pub async fn record_coverage(pool: &SqlitePool, message_id: Uuid, check: CoverageCheck) {
+    let covered = check.await;
+    if covered.is_empty() {
+        return;
+    }
+
+    if let Err(e) = add_message_concepts(pool, message_id, &covered).await {
+        tracing::error!("Failed to save concept coverage: {}", e);
+    }
+}
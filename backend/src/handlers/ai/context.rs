@@ -0,0 +1,85 @@
+use std::env;
+
+use crate::models::message::Message;
+
+// rough average for English text, good enough to stay clear of the real limit
+const CHARS_PER_TOKEN: usize = 4;
+// role markers and separators each turn costs on top of its text
+const TOKENS_PER_TURN: usize = 4;
+
+/// estimates how many tokens `text` will cost without calling a tokenizer
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+pub fn estimate_turn_tokens(text: &str) -> usize {
+    estimate_tokens(text) + TOKENS_PER_TURN
+}
+
+/// how much context a model gets per request
+#[derive(Debug, Clone, Copy)]
+pub struct ContextBudget {
+    /// prompt plus reply, in tokens
+    pub context_tokens: usize,
+    /// newest turns that are always sent verbatim, never summarised
+    pub recent_turns: usize,
+}
+
+impl ContextBudget {
+    /// budget for `model`, overridable with the `LLM_CONTEXT_TOKENS` env var
+    pub fn for_model(model: &str) -> Self {
+        let context_tokens = env::var("LLM_CONTEXT_TOKENS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_else(|| Self::default_context_tokens(model));
+
+        ContextBudget {
+            context_tokens,
+            recent_turns: 6,
+        }
+    }
+
+    // well below the real windows: long prompts are slow and costly even when they fit
+    fn default_context_tokens(model: &str) -> usize {
+        if model.starts_with("gemini") {
+            32_000
+        } else if model.starts_with("gpt-4") {
+            16_000
+        } else if model == "mock" {
+            8_000
+        } else {
+            // llama.cpp and Ollama default to small windows
+            4_096
+        }
+    }
+
+    /// tokens left for the prompt once `reply_tokens` are set aside for the answer
+    pub fn prompt_tokens(&self, reply_tokens: usize) -> usize {
+        self.context_tokens.saturating_sub(reply_tokens)
+    }
+}
+
+/// how many of the oldest `messages` have to be folded into the summary so the rest,
+/// plus `fixed_tokens` of prompt, fits into `prompt_budget`. the newest
+/// `recent_turns` messages are never folded.
+pub fn messages_to_fold(
+    messages: &[Message],
+    fixed_tokens: usize,
+    prompt_budget: usize,
+    recent_turns: usize,
+) -> usize {
+    let foldable = messages.len().saturating_sub(recent_turns);
+    let mut total: usize = fixed_tokens
+        + messages
+            .iter()
+            .map(|msg| estimate_turn_tokens(&msg.content))
+            .sum::<usize>();
+
+    let mut folded = 0;
+    while total > prompt_budget && folded < foldable {
+        total -= estimate_turn_tokens(&messages[folded].content);
+        folded += 1;
+    }
+
+    folded
+}
@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use axum::{
+    Json,
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use reqwest::header::HeaderMap;
+use serde_json::json;
+
+/// why a model call failed, precise enough to decide on retries and to tell the teacher
+#[derive(Debug, thiserror::Error)]
+pub enum AiError {
+    /// the API key is missing, invalid or lacks access to the model
+    #[error("authentication failed: {0}")]
+    Auth(String),
+    /// 429 or an exhausted quota, `retry_after` comes from the `Retry-After` header
+    #[error("rate limited: {message}")]
+    RateLimited {
+        retry_after: Option<Duration>,
+        message: String,
+    },
+    /// the prompt or the reply was blocked by the vendor's safety filters
+    #[error("blocked by safety filter: {0}")]
+    Blocked(String),
+    #[error("the model did not answer in time")]
+    Timeout,
+    /// the API answered with something we couldn't make sense of
+    #[error("malformed response: {0}")]
+    Malformed(String),
+    /// 5xx or connection failures, usually temporary
+    #[error("model API unavailable: {0}")]
+    Unavailable(String),
+    /// any other rejected request, e.g. an unknown model or an invalid parameter
+    #[error("request rejected ({status}): {message}")]
+    Rejected { status: u16, message: String },
+}
+
+impl AiError {
+    /// maps an HTTP error status and body text of a model API to an error
+    pub fn from_status(status: u16, message: String, retry_after: Option<Duration>) -> Self {
+        match status {
+            401 | 403 => AiError::Auth(message),
+            429 => AiError::RateLimited {
+                retry_after,
+                message,
+            },
+            408 | 504 => AiError::Timeout,
+            500..=599 => AiError::Unavailable(message),
+            _ => AiError::Rejected { status, message },
+        }
+    }
+
+    /// whether the same request may succeed if sent again a bit later
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            AiError::RateLimited { .. } | AiError::Timeout | AiError::Unavailable(_)
+        )
+    }
+
+    /// stable identifier the frontend can switch on
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AiError::Auth(_) => "auth",
+            AiError::RateLimited { .. } => "rate_limited",
+            AiError::Blocked(_) => "blocked",
+            AiError::Timeout => "timeout",
+            AiError::Malformed(_) => "malformed_response",
+            AiError::Unavailable(_) => "unavailable",
+            AiError::Rejected { .. } => "rejected",
+        }
+    }
+
+    /// what to tell the teacher; details stay in the logs
+    pub fn user_message(&self) -> &'static str {
+        match self {
+            AiError::Auth(_) => "Bodhi can't reach the AI service, the API key was rejected.",
+            AiError::RateLimited { .. } => {
+                "Bodhi is getting too many questions right now. Please try again in a moment."
+            }
+            AiError::Blocked(_) => "Bodhi's reply was blocked by the safety filter.",
+            AiError::Timeout => "Bodhi took too long to answer. Please try again.",
+            AiError::Malformed(_) => "Bodhi's reply came back garbled. Please try again.",
+            AiError::Unavailable(_) => "The AI service is unavailable. Please try again later.",
+            AiError::Rejected { .. } => "The AI service rejected the request.",
+        }
+    }
+
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AiError::Blocked(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AiError::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            AiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AiError::Auth(_) | AiError::Malformed(_) | AiError::Rejected { .. } => {
+                StatusCode::BAD_GATEWAY
+            }
+        }
+    }
+
+    /// the JSON body sent to the frontend, also used for SSE error events
+    pub fn to_json(&self) -> serde_json::Value {
+        let retry_after = match self {
+            AiError::RateLimited { retry_after, .. } => retry_after.map(|d| d.as_secs()),
+            _ => None,
+        };
+
+        json!({
+            "error": self.kind(),
+            "message": self.user_message(),
+            "retry_after_secs": retry_after,
+        })
+    }
+}
+
+impl From<reqwest::Error> for AiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            AiError::Timeout
+        } else if e.is_decode() {
+            AiError::Malformed(e.to_string())
+        } else if let Some(status) = e.status() {
+            AiError::from_status(status.as_u16(), e.to_string(), None)
+        } else {
+            AiError::Unavailable(e.to_string())
+        }
+    }
+}
+
+impl IntoResponse for AiError {
+    fn into_response(self) -> Response {
+        let mut response = (self.status_code(), Json(self.to_json())).into_response();
+
+        if let AiError::RateLimited {
+            retry_after: Some(delay),
+            ..
+        } = &self
+        {
+            if let Ok(value) = HeaderValue::from_str(&delay.as_secs().to_string()) {
+                response.headers_mut().insert(header::RETRY_AFTER, value);
+            }
+        }
+
+        response
+    }
+}
+
+/// reads a `Retry-After` header given in seconds
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
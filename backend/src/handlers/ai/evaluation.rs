@@ -0,0 +1,112 @@
+use sqlx::SqlitePool;
+
+use crate::{
+    handlers::ai::{
+        client::{complete_and_record, lesson_transcript, parse_json_reply},
+        context::{ContextBudget, estimate_tokens},
+        error::AiError,
+        prompt::EVALUATE_TEACHING_PROMPT,
+        providers::{ChatRole, ChatTurn, LlmProvider, LlmRequest},
+    },
+    models::{
+        evaluation::{EvaluationDraft, MAX_SCORE, MIN_SCORE},
+        generation::GenerationSettings,
+        message::{Message, MessageRole},
+        session::Session,
+        usage::CallPurpose,
+    },
+    retrieval::Retriever,
+};
+
+// the rubric JSON with five short comments and a few bullet points
+const EVALUATION_REPLY_TOKENS: u32 = 1024;
+
+/// grades the teacher's explanations in `history` against the session's material.
+///
+/// turns already folded into the rolling summary are graded through the summary,
+/// and long material is narrowed down to the passages the teacher talked about.
+pub async fn evaluate_lesson(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    retriever: &Retriever,
+    session: &Session,
+    history: Vec<Message>,
+) -> Result<EvaluationDraft, anyhow::Error> {
+    let settings = GenerationSettings {
+        temperature: Some(0.2),
+        max_output_tokens: Some(EVALUATION_REPLY_TOKENS),
+        ..GenerationSettings::defaults()
+    };
+    let budget = ContextBudget::for_model(llm.model());
+    let prompt_budget = budget.prompt_tokens(EVALUATION_REPLY_TOKENS as usize);
+
+    // the transcript gets half of the budget
+    let (history, transcript) = lesson_transcript(
+        pool,
+        session.id,
+        history,
+        prompt_budget / 2,
+        budget.recent_turns,
+    )
+    .await?;
+
+    let material_budget = prompt_budget
+        .saturating_sub(estimate_tokens(EVALUATE_TEACHING_PROMPT) + estimate_tokens(&transcript));
+    let material = if estimate_tokens(&session.material_text) <= material_budget {
+        session.material_text.clone()
+    } else {
+        let query = history
+            .iter()
+            .filter(|msg| matches!(msg.role, MessageRole::User))
+            .map(|msg| msg.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        retriever
+            .retrieve(
+                pool,
+                session.id,
+                &session.material_text,
+                &query,
+                material_budget,
+            )
+            .await?
+            .into_iter()
+            .map(|chunk| chunk.content)
+            .collect::<Vec<_>>()
+            .join("\n\n[...]\n\n")
+    };
+
+    let request = LlmRequest {
+        system_prompt: EVALUATE_TEACHING_PROMPT.replace("{material}", &material),
+        turns: vec![ChatTurn {
+            role: ChatRole::User,
+            text: format!("Lesson transcript:\n{}", transcript),
+        }],
+        settings,
+        response_schema: None,
+    };
+
+    let reply = complete_and_record(pool, llm, session.id, CallPurpose::Evaluation, request)
+        .await?
+        .text;
+    let draft: EvaluationDraft =
+        parse_json_reply(&reply).map_err(|e| AiError::Malformed(e.to_string()))?;
+
+    Ok(clamp_scores(draft))
+}
+
+// models occasionally answer 0 or 10 despite the instructions
+fn clamp_scores(mut draft: EvaluationDraft) -> EvaluationDraft {
+    for criterion in [
+        &mut draft.accuracy,
+        &mut draft.completeness,
+        &mut draft.clarity,
+        &mut draft.examples,
+        &mut draft.question_handling,
+    ] {
+        criterion.score = criterion.score.clamp(MIN_SCORE, MAX_SCORE);
+    }
+
+    draft
+}
@@ -0,0 +1,73 @@
+use sqlx::SqlitePool;
+
+use crate::{
+    handlers::ai::{
+        client::{complete_and_record, lesson_transcript, parse_json_reply},
+        context::{ContextBudget, estimate_tokens},
+        error::AiError,
+        prompt::{OPEN_CONFUSIONS_PROMPT, SUMMARIZE_LESSON_PROMPT},
+        providers::{ChatRole, ChatTurn, LlmProvider, LlmRequest},
+    },
+    models::{
+        generation::GenerationSettings, lesson_summary::LessonSummaryDraft, message::Message,
+        session::Session, usage::CallPurpose,
+    },
+};
+
+// a handful of short bullet points per section
+const SUMMARY_REPLY_TOKENS: u32 = 1024;
+
+/// writes the teacher's notes for the lesson in `history`
+pub async fn summarize_lesson(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    session: &Session,
+    history: Vec<Message>,
+) -> Result<LessonSummaryDraft, anyhow::Error> {
+    let budget = ContextBudget::for_model(llm.model());
+    let prompt_budget = budget
+        .prompt_tokens(SUMMARY_REPLY_TOKENS as usize)
+        .saturating_sub(estimate_tokens(SUMMARIZE_LESSON_PROMPT));
+
+    // what Bodhi reported last is the most reliable source for the gaps
+    let confusions = history
+        .iter()
+        .rev()
+        .find_map(|msg| msg.signals.as_ref())
+        .map(|signals| signals.confusions.join("; "))
+        .unwrap_or_default();
+
+    let (_, mut transcript) = lesson_transcript(
+        pool,
+        session.id,
+        history,
+        prompt_budget,
+        budget.recent_turns,
+    )
+    .await?;
+    if !confusions.is_empty() {
+        transcript.push_str(&OPEN_CONFUSIONS_PROMPT.replace("{}", &confusions));
+    }
+
+    let request = LlmRequest {
+        system_prompt: SUMMARIZE_LESSON_PROMPT.to_string(),
+        turns: vec![ChatTurn {
+            role: ChatRole::User,
+            text: format!("Lesson on \"{}\":\n{}", session.topic, transcript),
+        }],
+        settings: GenerationSettings {
+            temperature: Some(0.2),
+            max_output_tokens: Some(SUMMARY_REPLY_TOKENS),
+            ..GenerationSettings::defaults()
+        },
+        response_schema: None,
+    };
+
+    let reply = complete_and_record(pool, llm, session.id, CallPurpose::LessonNotes, request)
+        .await?
+        .text;
+    let draft: LessonSummaryDraft =
+        parse_json_reply(&reply).map_err(|e| AiError::Malformed(e.to_string()))?;
+
+    Ok(draft)
+}
@@ -0,0 +1,218 @@
+use std::env;
+
+use futures_util::{FutureExt, future::BoxFuture};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::{
+    citations::cite_excerpt,
+    database::messages::set_misconceptions,
+    handlers::ai::{
+        client::{complete_and_record, parse_json_reply},
+        prompt::{DETECT_MISCONCEPTIONS_PROMPT, MISCONCEPTION_HINT_PROMPT},
+        providers::{ChatRole, ChatTurn, LlmProvider, LlmRequest, SharedLlmProvider},
+    },
+    models::{
+        generation::GenerationSettings, message::Message, misconception::Misconception,
+        session::Session, usage::CallPurpose,
+    },
+    retrieval::Retriever,
+};
+
+// short replies like "yes, exactly" have nothing worth checking
+const MIN_CHECK_WORDS: usize = 6;
+// the passages closest to the teacher's message are plenty to judge it
+const CHECK_MATERIAL_TOKENS: usize = 1_500;
+
+/// a check that may still be running, resolves to what it found or `None` if it failed
+pub type MisconceptionCheck = BoxFuture<'static, Option<Vec<Misconception>>>;
+
+/// what happens with teacher messages that contradict the material, from `MISCONCEPTION_MODE`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MisconceptionMode {
+    /// no checks at all
+    Off,
+    /// flag the message for the chat UI, Bodhi doesn't know
+    Flag,
+    /// flag the message and let Bodhi react with gentle confusion
+    Surface,
+}
+
+impl MisconceptionMode {
+    pub fn from_env() -> Self {
+        match env::var("MISCONCEPTION_MODE").as_deref() {
+            Ok("off") => MisconceptionMode::Off,
+            Ok("surface") => MisconceptionMode::Surface,
+            Ok("flag") | Err(_) => MisconceptionMode::Flag,
+            Ok(other) => {
+                tracing::warn!("Unknown MISCONCEPTION_MODE '{}', using 'flag'", other);
+                MisconceptionMode::Flag
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CheckReply {
+    #[serde(default)]
+    misconceptions: Vec<Misconception>,
+}
+
+/// asks the model whether `text` contradicts the passages of the material it talks about.
+/// best effort: a failed check is logged and returns `None`, so the message stays unchecked.
+pub async fn check_message(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    retriever: &Retriever,
+    session: &Session,
+    text: &str,
+) -> Option<Vec<Misconception>> {
+    if text.split_whitespace().count() < MIN_CHECK_WORDS {
+        return Some(Vec::new());
+    }
+
+    let passages = match retriever
+        .retrieve(
+            pool,
+            session.id,
+            &session.material_text,
+            text,
+            CHECK_MATERIAL_TOKENS,
+        )
+        .await
+    {
+        Ok(passages) => passages,
+        Err(e) => {
+            tracing::warn!("Failed to retrieve passages for misconception check: {}", e);
+            return None;
+        }
+    };
+
+    let material = passages
+        .into_iter()
+        .map(|chunk| chunk.content)
+        .collect::<Vec<_>>()
+        .join("\n\n[...]\n\n");
+
+    let request = LlmRequest {
+        system_prompt: DETECT_MISCONCEPTIONS_PROMPT.replace("{material}", &material),
+        turns: vec![ChatTurn {
+            role: ChatRole::User,
+            text: format!("Teacher's message:\n{}", text),
+        }],
+        settings: GenerationSettings {
+            temperature: Some(0.0),
+            max_output_tokens: Some(512),
+            ..GenerationSettings::defaults()
+        },
+        response_schema: None,
+    };
+
+    let reply = match complete_and_record(
+        pool,
+        llm,
+        session.id,
+        CallPurpose::MisconceptionCheck,
+        request,
+    )
+    .await
+    {
+        Ok(completion) => completion.text,
+        Err(e) => {
+            tracing::warn!("Misconception check failed: {}", e);
+            return None;
+        }
+    };
+
+    match parse_json_reply::<CheckReply>(&reply) {
+        // the quotes are looked up so the teacher can find them in the material
+        Ok(parsed) => Some(
+            parsed
+                .misconceptions
+                .into_iter()
+                .map(|misconception| Misconception {
+                    page: cite_excerpt(
+                        &session.material_text,
+                        &session.material_pages,
+                        &misconception.evidence,
+                    ),
+                    ..misconception
+                })
+                .collect(),
+        ),
+        Err(e) => {
+            tracing::warn!("Malformed misconception check reply: {}", e);
+            None
+        }
+    }
+}
+
+/// starts checking the teacher's `text` as configured by `MISCONCEPTION_MODE`.
+///
+/// in surface mode the check finishes first and Bodhi's `request` is told about what it
+/// found; in flag mode it runs in the background while Bodhi replies.
+pub async fn start_check(
+    pool: &SqlitePool,
+    llm: &SharedLlmProvider,
+    retriever: &Retriever,
+    session: Session,
+    text: &str,
+    request: &mut LlmRequest,
+) -> Option<MisconceptionCheck> {
+    match MisconceptionMode::from_env() {
+        MisconceptionMode::Off => None,
+        MisconceptionMode::Flag => {
+            let (pool, llm, retriever, text) = (
+                pool.clone(),
+                llm.clone(),
+                retriever.clone(),
+                text.to_string(),
+            );
+            let handle = tokio::spawn(async move {
+                check_message(&pool, llm.as_ref(), &retriever, &session, &text).await
+            });
+
+            Some(async move { handle.await.ok().flatten() }.boxed())
+        }
+        MisconceptionMode::Surface => {
+            let found = check_message(pool, llm.as_ref(), retriever, &session, text).await;
+            add_misconception_hint(request, found.as_deref().unwrap_or_default());
+
+            Some(futures_util::future::ready(found).boxed())
+        }
+    }
+}
+
+/// waits for `check` and stores its result on the teacher's saved `message`, a failed
+/// check leaves it unchecked
+pub async fn record_check(pool: &SqlitePool, message: &mut Message, check: MisconceptionCheck) {
+    let Some(found) = check.await else {
+        return;
+    };
+
+    match set_misconceptions(pool, message.id, &found).await {
+        Ok(()) => message.misconceptions = found,
+        Err(e) => tracing::error!("Failed to save misconceptions: {}", e),
+    }
+}
+
+fn add_misconception_hint(request: &mut LlmRequest, found: &[Misconception]) {
+    if found.is_empty() {
+        return;
+    }
+
+    let details = found
+        .iter()
+        .map(|m| {
+            format!(
+                "- They said: {}\n  The material says: {}",
+                m.claim, m.correction
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    request
+        .system_prompt
+        .push_str(&MISCONCEPTION_HINT_PROMPT.replace("{}", &details));
+}
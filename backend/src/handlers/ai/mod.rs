@@ -1,3 +1,14 @@
+pub mod clarity;
 pub mod client;
+pub mod concepts;
+pub mod context;
+pub mod error;
+pub mod evaluation;
+pub mod lesson_summary;
+pub mod misconceptions;
 pub mod model;
 pub mod prompt;
+pub mod providers;
+pub mod quiz;
+pub mod sse;
+pub mod turn;
@@ -1,41 +1,219 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GeminiRequest {
     pub contents: Vec<Content>,
+    pub system_instruction: SystemInstruction,
+    pub generation_config: GenerationConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub safety_settings: Vec<SafetySetting>,
 }
 
-#[derive(Serialize)]
+// the system prompt, which Gemini treats as instructions rather than a conversation turn
+#[derive(Serialize, Deserialize)]
+pub struct SystemInstruction {
+    pub parts: Vec<Part>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+    // "application/json" together with a schema for structured replies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SafetySetting {
+    pub category: String,
+    pub threshold: String,
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Content {
     pub role: String, // "user" or "model"
     pub parts: Vec<Part>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Part {
     pub text: String,
 }
 
 // --- Gemini API Response Structures ---
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GeminiResponse {
+    #[serde(default)]
     pub candidates: Vec<Candidate>,
+    pub prompt_feedback: Option<PromptFeedback>,
+    pub usage_metadata: Option<UsageMetadata>,
 }
 
-#[derive(Deserialize)]
+// token counts; in a stream every event carries the running totals
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageMetadata {
+    #[serde(default)]
+    pub prompt_token_count: u32,
+    #[serde(default)]
+    pub candidates_token_count: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Candidate {
-    pub content: ContentResponse,
+    // missing when the candidate was blocked before producing anything
+    pub content: Option<ContentResponse>,
+    pub finish_reason: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ContentResponse {
+    #[serde(default)]
     pub parts: Vec<PartResponse>,
+    #[serde(rename = "role", default)]
     pub _role: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct PartResponse {
+    #[serde(default)]
     pub text: String,
 }
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptFeedback {
+    pub block_reason: Option<String>,
+}
+
+// error body returned with non-2xx statuses, and occasionally inside a stream
+#[derive(Serialize, Deserialize)]
+pub struct GeminiErrorResponse {
+    pub error: GeminiError,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GeminiError {
+    pub code: u16,
+    pub message: String,
+    pub status: Option<String>,
+}
+
+// --- OpenAI-compatible Chat Completions Structures ---
+// used for OpenAI itself as well as local llama.cpp / Ollama servers
+
+#[derive(Serialize)]
+pub struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<ChatCompletionMessage>,
+    pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+}
+
+// constrains the reply to a JSON schema
+#[derive(Serialize)]
+pub struct ResponseFormat {
+    #[serde(rename = "type")]
+    pub kind: String, // "json_schema"
+    pub json_schema: JsonSchemaFormat,
+}
+
+#[derive(Serialize)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    pub strict: bool,
+    pub schema: serde_json::Value,
+}
+
+// asks for a final chunk with the token usage of a streamed reply
+#[derive(Serialize)]
+pub struct StreamOptions {
+    pub include_usage: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChatCompletionMessage {
+    pub role: String, // "system", "user" or "assistant"
+    pub content: String,
+}
+
+#[derive(Deserialize)]
+pub struct ChatCompletionResponse {
+    pub choices: Vec<ChatCompletionChoice>,
+    #[serde(default)]
+    pub usage: Option<ChatCompletionUsage>,
+}
+
+#[derive(Deserialize)]
+pub struct ChatCompletionUsage {
+    #[serde(default)]
+    pub prompt_tokens: u32,
+    #[serde(default)]
+    pub completion_tokens: u32,
+}
+
+#[derive(Deserialize)]
+pub struct ChatCompletionChoice {
+    pub message: ChatCompletionMessage,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChatCompletionChunk {
+    #[serde(default)]
+    pub choices: Vec<ChatCompletionChunkChoice>,
+    // only set on the last chunk, which has no choices
+    #[serde(default)]
+    pub usage: Option<ChatCompletionUsage>,
+}
+
+#[derive(Deserialize)]
+pub struct ChatCompletionChunkChoice {
+    pub delta: ChatCompletionDelta,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChatCompletionDelta {
+    pub content: Option<String>,
+}
+
+// error body returned with non-2xx statuses
+#[derive(Deserialize)]
+pub struct ChatCompletionErrorResponse {
+    pub error: ChatCompletionError,
+}
+
+#[derive(Deserialize)]
+pub struct ChatCompletionError {
+    pub message: String,
+    #[serde(rename = "type", default)]
+    pub kind: Option<String>,
+}
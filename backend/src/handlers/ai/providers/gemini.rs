@@ -0,0 +1,435 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::{StreamExt, stream::BoxStream};
+use reqwest::{Client, Response};
+use serde_json::{Map, Value};
+use tokio::time::timeout;
+
+use crate::handlers::ai::{
+    error::{AiError, retry_after},
+    model::{
+        Content, GeminiErrorResponse, GeminiRequest, GeminiResponse, GenerationConfig, Part,
+        SafetySetting, SystemInstruction,
+    },
+    providers::{
+        ChatRole, Completion, LlmProvider, LlmRequest, REQUEST_TIMEOUT, STREAM_IDLE_TIMEOUT,
+        StreamItem, TokenUsage,
+    },
+    sse::SseDecoder,
+};
+
+pub const DEFAULT_MODEL: &str = "gemini-2.5-flash";
+pub const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+
+// finish reasons that mean the reply was cut off by a filter rather than completed
+const BLOCKED_FINISH_REASONS: &[&str] = &[
+    "SAFETY",
+    "RECITATION",
+    "BLOCKLIST",
+    "PROHIBITED_CONTENT",
+    "SPII",
+    "IMAGE_SAFETY",
+];
+
+pub struct GeminiProvider {
+    client: Client,
+    base_url: String,
+    api_key: String,
+    model: String,
+}
+
+impl GeminiProvider {
+    pub fn new(base_url: String, api_key: String, model: String) -> Self {
+        GeminiProvider {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model,
+        }
+    }
+
+    fn endpoint(&self, method: &str) -> String {
+        format!("{}/{}:{}", self.base_url, self.model, method)
+    }
+}
+
+// harm categories the safety threshold is applied to
+const HARM_CATEGORIES: &[&str] = &[
+    "HARM_CATEGORY_HARASSMENT",
+    "HARM_CATEGORY_HATE_SPEECH",
+    "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+    "HARM_CATEGORY_DANGEROUS_CONTENT",
+];
+
+/// converts a neutral request into Gemini's request body
+fn build_payload(request: LlmRequest) -> GeminiRequest {
+    let contents = request
+        .turns
+        .into_iter()
+        .map(|turn| Content {
+            role: match turn.role {
+                ChatRole::User => "user".to_string(),
+                ChatRole::Assistant => "model".to_string(),
+            },
+            parts: vec![Part { text: turn.text }],
+        })
+        .collect();
+
+    let settings = request.settings;
+    let safety_settings = settings
+        .safety_threshold
+        .map(|threshold| {
+            HARM_CATEGORIES
+                .iter()
+                .map(|category| SafetySetting {
+                    category: category.to_string(),
+                    threshold: threshold.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    GeminiRequest {
+        contents,
+        system_instruction: SystemInstruction {
+            parts: vec![Part {
+                text: request.system_prompt,
+            }],
+        },
+        generation_config: GenerationConfig {
+            temperature: settings.temperature,
+            top_p: settings.top_p,
+            max_output_tokens: settings.max_output_tokens,
+            stop_sequences: settings.stop_sequences,
+            response_mime_type: request
+                .response_schema
+                .as_ref()
+                .map(|_| "application/json".to_string()),
+            response_schema: request
+                .response_schema
+                .map(|schema| gemini_schema(schema.schema)),
+        },
+        safety_settings,
+    }
+}
+
+/// translates JSON Schema to Gemini's OpenAPI flavoured subset: upper case types, no
+/// `additionalProperties`, and properties generated in the order they are required
+fn gemini_schema(schema: Value) -> Value {
+    match schema {
+        Value::Object(map) => {
+            let mut converted: Map<String, Value> = map
+                .into_iter()
+                .filter(|(key, _)| key != "additionalProperties")
+                .map(|(key, value)| match (key.as_str(), value) {
+                    ("type", Value::String(kind)) => (key, Value::String(kind.to_uppercase())),
+                    (_, value) => (key, gemini_schema(value)),
+                })
+                .collect();
+
+            if let Some(required) = converted.get("required").cloned() {
+                converted.insert("propertyOrdering".to_string(), required);
+            }
+
+            Value::Object(converted)
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(gemini_schema).collect()),
+        other => other,
+    }
+}
+
+/// joins every text part of the first candidate
+fn first_text(response: &GeminiResponse) -> Option<String> {
+    let content = response.candidates.first()?.content.as_ref()?;
+    let text: String = content.parts.iter().map(|p| p.text.as_str()).collect();
+
+    (!text.is_empty()).then_some(text)
+}
+
+/// the reason the prompt or the first candidate was blocked, if it was
+fn block_reason(response: &GeminiResponse) -> Option<String> {
+    if let Some(reason) = response
+        .prompt_feedback
+        .as_ref()
+        .and_then(|feedback| feedback.block_reason.clone())
+    {
+        return Some(reason);
+    }
+
+    response
+        .candidates
+        .first()
+        .and_then(|c| c.finish_reason.clone())
+        .filter(|reason| BLOCKED_FINISH_REASONS.contains(&reason.as_str()))
+}
+
+fn usage(response: &GeminiResponse) -> Option<TokenUsage> {
+    response.usage_metadata.as_ref().map(|usage| TokenUsage {
+        prompt_tokens: usage.prompt_token_count,
+        response_tokens: usage.candidates_token_count,
+    })
+}
+
+/// turns one `streamGenerateContent` event into the items it carries
+fn decode_stream_event(data: &str) -> Vec<Result<StreamItem, AiError>> {
+    // errors can be sent in place of a response, even after a 200 status
+    if let Ok(payload) = serde_json::from_str::<GeminiErrorResponse>(data) {
+        return vec![Err(api_error(payload, None))];
+    }
+
+    let response = match serde_json::from_str::<GeminiResponse>(data) {
+        Ok(response) => response,
+        Err(e) => {
+            return vec![Err(AiError::Malformed(format!(
+                "Malformed stream event: {}",
+                e
+            )))];
+        }
+    };
+
+    let mut items = Vec::new();
+    if let Some(text) = first_text(&response) {
+        items.push(Ok(StreamItem::Text(text)));
+    }
+
+    if let Some(usage) = usage(&response) {
+        items.push(Ok(StreamItem::Usage(usage)));
+    }
+
+    if let Some(reason) = block_reason(&response) {
+        items.push(Err(AiError::Blocked(reason)));
+    } else if let Some(reason) = response
+        .candidates
+        .first()
+        .and_then(|c| c.finish_reason.clone())
+    {
+        items.push(Ok(StreamItem::Finished { reason }));
+    }
+
+    items
+}
+
+/// parses an error body, which Gemini sometimes wraps in a JSON array
+fn decode_error_body(status: u16, body: &str, retry_after: Option<Duration>) -> AiError {
+    let payload = serde_json::from_str::<GeminiErrorResponse>(body)
+        .ok()
+        .or_else(|| {
+            serde_json::from_str::<Vec<GeminiErrorResponse>>(body)
+                .ok()?
+                .pop()
+        });
+
+    match payload {
+        Some(payload) => api_error(payload, retry_after),
+        None => AiError::from_status(status, body.trim().to_string(), retry_after),
+    }
+}
+
+/// turns a non-success response into the matching error
+async fn error_for_status(response: Response) -> Result<Response, AiError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let retry_after = retry_after(response.headers());
+    let body = response.text().await?;
+    Err(decode_error_body(status.as_u16(), &body, retry_after))
+}
+
+fn api_error(payload: GeminiErrorResponse, retry_after: Option<Duration>) -> AiError {
+    let error = payload.error;
+
+    // an invalid key comes back as a plain 400 INVALID_ARGUMENT
+    if error.message.contains("API key") {
+        return AiError::Auth(error.message);
+    }
+
+    let message = match error.status {
+        Some(status) => format!("{}: {}", status, error.message),
+        None => error.message,
+    };
+
+    AiError::from_status(error.code, message, retry_after)
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn complete(&self, request: LlmRequest) -> Result<Completion, AiError> {
+        let payload = build_payload(request);
+
+        // send the request and get the response
+        let response = self
+            .client
+            .post(self.endpoint("generateContent"))
+            .query(&[("key", &self.api_key)])
+            .json(&payload)
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?;
+
+        let body = error_for_status(response).await?.text().await?;
+        let response = serde_json::from_str::<GeminiResponse>(&body)
+            .map_err(|e| AiError::Malformed(e.to_string()))?;
+
+        if let Some(reason) = block_reason(&response) {
+            return Err(AiError::Blocked(reason));
+        }
+
+        // extract the text from the response
+        let text = first_text(&response)
+            .ok_or_else(|| AiError::Malformed("Reply contains no text".to_string()))?;
+
+        Ok(Completion {
+            text,
+            usage: usage(&response),
+            finish_reason: response
+                .candidates
+                .first()
+                .and_then(|c| c.finish_reason.clone()),
+        })
+    }
+
+    fn stream(&self, request: LlmRequest) -> BoxStream<'static, Result<StreamItem, AiError>> {
+        let client = self.client.clone();
+        let api_key = self.api_key.clone();
+        let api_url = self.endpoint("streamGenerateContent");
+        let payload = build_payload(request);
+
+        async_stream::try_stream! {
+            // `alt=sse` frames every response as an SSE event instead of one big JSON array
+            let request = client
+                .post(api_url)
+                .query(&[("key", api_key.as_str()), ("alt", "sse")])
+                .json(&payload)
+                .send();
+            let response = timeout(STREAM_IDLE_TIMEOUT, request)
+                .await
+                .map_err(|_| AiError::Timeout)??;
+
+            let mut byte_stream = error_for_status(response).await?.bytes_stream();
+            let mut decoder = SseDecoder::new();
+
+            // process the stream, reassembling events split across chunks
+            while let Some(chunk) = timeout(STREAM_IDLE_TIMEOUT, byte_stream.next())
+                .await
+                .map_err(|_| AiError::Timeout)?
+            {
+                for data in decoder.push(&chunk?) {
+                    for item in decode_stream_event(&data) {
+                        yield item?;
+                    }
+                }
+            }
+
+            if let Some(data) = decoder.finish() {
+                for item in decode_stream_event(&data) {
+                    yield item?;
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a streamGenerateContent?alt=sse body as Gemini sends it, with CRLF line endings
+    const STREAM_BODY: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/gemini_stream.sse"
+    ));
+
+    fn decode_body(chunks: &[&[u8]]) -> Vec<Result<StreamItem, AiError>> {
+        let mut decoder = SseDecoder::new();
+        let mut events = Vec::new();
+        for chunk in chunks {
+            events.extend(decoder.push(chunk));
+        }
+        events.extend(decoder.finish());
+
+        events
+            .iter()
+            .flat_map(|event| decode_stream_event(event))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_the_recorded_stream_wherever_it_is_split() {
+        let expected = vec![
+            StreamItem::Text("Photosynthèse ".to_string()),
+            StreamItem::Usage(TokenUsage {
+                prompt_tokens: 12,
+                response_tokens: 0,
+            }),
+            StreamItem::Text("turns light into sugar 🌱".to_string()),
+            StreamItem::Usage(TokenUsage {
+                prompt_tokens: 12,
+                response_tokens: 9,
+            }),
+            StreamItem::Finished {
+                reason: "STOP".to_string(),
+            },
+        ];
+
+        // includes offsets inside "è", inside "🌱" and between '\r' and '\n'
+        for split in 0..=STREAM_BODY.len() {
+            let items: Vec<StreamItem> =
+                decode_body(&[&STREAM_BODY[..split], &STREAM_BODY[split..]])
+                    .into_iter()
+                    .map(|item| item.unwrap())
+                    .collect();
+
+            assert_eq!(items, expected, "split at byte {}", split);
+        }
+    }
+
+    #[test]
+    fn a_blocked_prompt_is_an_error() {
+        let items = decode_stream_event(r#"{"promptFeedback": {"blockReason": "SAFETY"}}"#);
+
+        assert!(matches!(&items[..], [Err(AiError::Blocked(reason))] if reason == "SAFETY"));
+    }
+
+    #[test]
+    fn a_reply_cut_off_by_a_filter_keeps_its_text() {
+        let items = decode_stream_event(
+            r#"{"candidates": [{"content": {"parts": [{"text": "Some"}]}, "finishReason": "RECITATION"}]}"#,
+        );
+
+        assert!(matches!(
+            &items[..],
+            [Ok(StreamItem::Text(text)), Err(AiError::Blocked(reason))]
+                if text == "Some" && reason == "RECITATION"
+        ));
+    }
+
+    #[test]
+    fn an_error_sent_after_a_200_is_mapped() {
+        let body =
+            b"data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hi\"}]}}]}\r\n\r\n\
+            data: {\"error\": {\"code\": 429, \"message\": \"Resource has been exhausted\", \
+            \"status\": \"RESOURCE_EXHAUSTED\"}}\r\n\r\n";
+        let items = decode_body(&[body]);
+
+        assert!(matches!(&items[0], Ok(StreamItem::Text(text)) if text == "Hi"));
+        assert!(matches!(
+            &items[1],
+            Err(AiError::RateLimited { retry_after: None, message })
+                if message == "RESOURCE_EXHAUSTED: Resource has been exhausted"
+        ));
+        assert_eq!(items.len(), 2);
+
+        let unavailable = decode_stream_event(
+            r#"{"error": {"code": 503, "message": "The model is overloaded.", "status": "UNAVAILABLE"}}"#,
+        );
+        assert!(matches!(&unavailable[..], [Err(AiError::Unavailable(_))]));
+    }
+}
@@ -0,0 +1,291 @@
+use std::{collections::VecDeque, env, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use futures_util::{StreamExt, stream::BoxStream};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::handlers::ai::{
+    context::estimate_tokens,
+    error::AiError,
+    providers::{ChatRole, Completion, LlmProvider, LlmRequest, StreamItem, TokenUsage},
+};
+
+/// a failure the mock can be told to produce, mirroring `AiError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MockFailure {
+    Auth,
+    RateLimited,
+    Blocked,
+    Timeout,
+    Malformed,
+    Unavailable,
+}
+
+impl MockFailure {
+    pub fn to_error(self) -> AiError {
+        match self {
+            MockFailure::Auth => AiError::Auth("mock: API key not valid".to_string()),
+            MockFailure::RateLimited => AiError::RateLimited {
+                retry_after: Some(Duration::from_secs(1)),
+                message: "mock: quota exceeded".to_string(),
+            },
+            MockFailure::Blocked => AiError::Blocked("SAFETY".to_string()),
+            MockFailure::Timeout => AiError::Timeout,
+            MockFailure::Malformed => AiError::Malformed("mock: garbled reply".to_string()),
+            MockFailure::Unavailable => AiError::Unavailable("mock: overloaded".to_string()),
+        }
+    }
+}
+
+/// one scripted answer. with only `error` the call fails before anything is sent,
+/// with both a stream sends `text` and then fails (a whole reply fails outright)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MockReply {
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub error: Option<MockFailure>,
+}
+
+impl MockReply {
+    pub fn text(text: impl Into<String>) -> Self {
+        MockReply {
+            text: text.into(),
+            error: None,
+        }
+    }
+}
+
+/// answers in-process, for offline work and tests. scripted replies are used in
+/// order, once they run out every reply is derived only from the request
+#[derive(Default)]
+pub struct MockProvider {
+    script: Mutex<VecDeque<MockReply>>,
+    /// wait before the first byte of every reply
+    latency: Duration,
+    /// wait between two streamed chunks
+    chunk_delay: Duration,
+    /// characters per streamed chunk, 0 streams word by word
+    chunk_chars: usize,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        MockProvider::default()
+    }
+
+    /// configured by `MOCK_SCRIPT` (path to a JSON array of replies),
+    /// `MOCK_LATENCY_MS`, `MOCK_CHUNK_DELAY_MS` and `MOCK_CHUNK_CHARS`
+    pub fn from_env() -> Result<Self, anyhow::Error> {
+        let mut provider = MockProvider::new()
+            .with_latency(Duration::from_millis(env_number("MOCK_LATENCY_MS")?))
+            .with_chunk_delay(Duration::from_millis(env_number("MOCK_CHUNK_DELAY_MS")?))
+            .with_chunk_chars(env_number("MOCK_CHUNK_CHARS")? as usize);
+
+        if let Ok(path) = env::var("MOCK_SCRIPT") {
+            let json = std::fs::read_to_string(&path)
+                .map_err(|e| anyhow::anyhow!("Failed to read MOCK_SCRIPT {}: {}", path, e))?;
+            let script: Vec<MockReply> = serde_json::from_str(&json)
+                .map_err(|e| anyhow::anyhow!("Invalid MOCK_SCRIPT {}: {}", path, e))?;
+            provider = provider.with_script(script);
+        }
+
+        Ok(provider)
+    }
+
+    pub fn with_script(self, script: impl IntoIterator<Item = MockReply>) -> Self {
+        MockProvider {
+            script: Mutex::new(script.into_iter().collect()),
+            ..self
+        }
+    }
+
+    pub fn with_latency(self, latency: Duration) -> Self {
+        MockProvider { latency, ..self }
+    }
+
+    pub fn with_chunk_delay(self, chunk_delay: Duration) -> Self {
+        MockProvider {
+            chunk_delay,
+            ..self
+        }
+    }
+
+    pub fn with_chunk_chars(self, chunk_chars: usize) -> Self {
+        MockProvider {
+            chunk_chars,
+            ..self
+        }
+    }
+
+    fn next_reply(&self, request: &LlmRequest) -> MockReply {
+        self.script.lock().unwrap().pop_front().unwrap_or_else(|| {
+            let echo = Self::echo(request);
+            match &request.response_schema {
+                Some(schema) => {
+                    // grows with the conversation so progress displays have something to show
+                    let taught = request
+                        .turns
+                        .iter()
+                        .filter(|turn| turn.role == ChatRole::User)
+                        .count() as i64;
+                    let number = (taught * 15).min(100);
+
+                    let mut used = false;
+                    let sample = sample_for_schema(&schema.schema, &echo, number, &mut used);
+                    MockReply::text(sample.to_string())
+                }
+                None => MockReply::text(echo),
+            }
+        })
+    }
+
+    fn echo(request: &LlmRequest) -> String {
+        let last_user_turn = request
+            .turns
+            .iter()
+            .rev()
+            .find(|turn| turn.role == ChatRole::User)
+            .map(|turn| turn.text.trim());
+
+        match last_user_turn {
+            Some(text) => format!(
+                "Thanks for explaining that! So you're saying: \"{}\". Could you give me an example?",
+                text
+            ),
+            None => "I'm excited to learn about this topic! Where should we begin?".to_string(),
+        }
+    }
+
+    // estimated the same way the context budget is, there's no tokenizer to ask
+    fn usage_for(request: &LlmRequest, reply: &str) -> TokenUsage {
+        let prompt_tokens = estimate_tokens(&request.system_prompt)
+            + request
+                .turns
+                .iter()
+                .map(|turn| estimate_tokens(&turn.text))
+                .sum::<usize>();
+
+        TokenUsage {
+            prompt_tokens: prompt_tokens as u32,
+            response_tokens: estimate_tokens(reply) as u32,
+        }
+    }
+
+    fn chunks(&self, text: &str) -> Vec<String> {
+        if self.chunk_chars == 0 {
+            return text.split_inclusive(' ').map(str::to_string).collect();
+        }
+
+        let chars: Vec<char> = text.chars().collect();
+        chars
+            .chunks(self.chunk_chars)
+            .map(|chunk| chunk.iter().collect())
+            .collect()
+    }
+}
+
+/// a value matching `schema` whose first string is `text` and whose numbers are
+/// `number`, the rest placeholders
+fn sample_for_schema(schema: &Value, text: &str, number: i64, used: &mut bool) -> Value {
+    let kind = schema["type"].as_str().unwrap_or_default().to_lowercase();
+
+    match kind.as_str() {
+        "object" => {
+            let properties = schema["properties"].as_object();
+            // required properties first, in the order the schema lists them
+            let mut names: Vec<&str> = schema["required"]
+                .as_array()
+                .map(|required| required.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            for name in properties.into_iter().flat_map(|p| p.keys()) {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+
+            let mut object = Map::new();
+            for name in names {
+                if let Some(property) = properties.and_then(|p| p.get(name)) {
+                    object.insert(
+                        name.to_string(),
+                        sample_for_schema(property, text, number, used),
+                    );
+                }
+            }
+            Value::Object(object)
+        }
+        "string" if !*used => {
+            *used = true;
+            Value::String(text.to_string())
+        }
+        "string" => Value::String(String::new()),
+        "integer" | "number" => Value::from(number),
+        "boolean" => Value::Bool(false),
+        "array" => Value::Array(Vec::new()),
+        _ => Value::Null,
+    }
+}
+
+fn env_number(name: &str) -> Result<u64, anyhow::Error> {
+    match env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| anyhow::anyhow!("{} must be a whole number, got {}", name, value)),
+        Err(_) => Ok(0),
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockProvider {
+    fn model(&self) -> &str {
+        "mock"
+    }
+
+    async fn complete(&self, request: LlmRequest) -> Result<Completion, AiError> {
+        let reply = self.next_reply(&request);
+        tokio::time::sleep(self.latency).await;
+
+        if let Some(failure) = reply.error {
+            return Err(failure.to_error());
+        }
+
+        Ok(Completion {
+            usage: Some(Self::usage_for(&request, &reply.text)),
+            text: reply.text,
+            finish_reason: Some("STOP".to_string()),
+        })
+    }
+
+    fn stream(&self, request: LlmRequest) -> BoxStream<'static, Result<StreamItem, AiError>> {
+        let reply = self.next_reply(&request);
+        let usage = Self::usage_for(&request, &reply.text);
+        let chunks = self.chunks(&reply.text);
+        let latency = self.latency;
+        let chunk_delay = self.chunk_delay;
+
+        async_stream::stream! {
+            tokio::time::sleep(latency).await;
+
+            for (i, chunk) in chunks.into_iter().enumerate() {
+                if i > 0 {
+                    tokio::time::sleep(chunk_delay).await;
+                }
+                yield Ok(StreamItem::Text(chunk));
+            }
+
+            match reply.error {
+                Some(failure) => yield Err(failure.to_error()),
+                None => {
+                    yield Ok(StreamItem::Usage(usage));
+                    yield Ok(StreamItem::Finished {
+                        reason: "STOP".to_string(),
+                    });
+                }
+            }
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,239 @@
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::{HeaderValue, StatusCode, header},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, Sse},
+    },
+    routing::post,
+};
+use futures_util::{StreamExt, stream};
+use serde::Deserialize;
+
+use crate::{
+    handlers::ai::{
+        error::AiError,
+        model::{
+            Candidate, ContentResponse, GeminiError, GeminiErrorResponse, GeminiRequest,
+            GeminiResponse, Part, PartResponse, UsageMetadata,
+        },
+        providers::{
+            ChatRole, ChatTurn, Completion, LlmProvider, LlmRequest, ResponseSchema, StreamItem,
+            TokenUsage, mock::MockProvider,
+        },
+    },
+    models::generation::GenerationSettings,
+};
+
+/// a local stand-in for the Gemini API that answers with a `MockProvider`, so the
+/// real Gemini provider (request encoding, SSE decoding, error mapping, retries)
+/// can run without network access. point `GEMINI_BASE_URL` at
+/// `http://<addr>/v1beta/models` to use it.
+pub fn router(mock: Arc<MockProvider>) -> Router {
+    Router::new()
+        .route("/v1beta/models/{call}", post(generate_handler))
+        .with_state(mock)
+}
+
+pub async fn serve(addr: SocketAddr, mock: Arc<MockProvider>) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("Mock Gemini server listening on {}", addr);
+    axum::serve(listener, router(mock)).await
+}
+
+#[derive(Deserialize)]
+struct CallQuery {
+    alt: Option<String>,
+}
+
+/// handles `{model}:generateContent` and `{model}:streamGenerateContent?alt=sse`
+async fn generate_handler(
+    State(mock): State<Arc<MockProvider>>,
+    Path(call): Path<String>,
+    Query(query): Query<CallQuery>,
+    Json(payload): Json<GeminiRequest>,
+) -> Response {
+    let request = to_llm_request(payload);
+
+    match call.split_once(':').map(|(_, method)| method) {
+        Some("generateContent") => match mock.complete(request).await {
+            Ok(completion) => Json(completion_response(completion)).into_response(),
+            Err(e) => error_response(e),
+        },
+        Some("streamGenerateContent") if query.alt.as_deref() == Some("sse") => {
+            stream_response(mock.as_ref(), request).await
+        }
+        Some("streamGenerateContent") => (
+            StatusCode::BAD_REQUEST,
+            "only alt=sse streaming is supported",
+        )
+            .into_response(),
+        _ => error_response(AiError::Rejected {
+            status: 404,
+            message: format!("Unknown method {}", call),
+        }),
+    }
+}
+
+/// the neutral request the Gemini provider encoded, decoded again
+fn to_llm_request(payload: GeminiRequest) -> LlmRequest {
+    let join = |parts: Vec<Part>| parts.into_iter().map(|p| p.text).collect::<String>();
+
+    let turns = payload
+        .contents
+        .into_iter()
+        .map(|content| ChatTurn {
+            role: match content.role.as_str() {
+                "model" => ChatRole::Assistant,
+                _ => ChatRole::User,
+            },
+            text: join(content.parts),
+        })
+        .collect();
+
+    let config = payload.generation_config;
+    LlmRequest {
+        system_prompt: join(payload.system_instruction.parts),
+        turns,
+        settings: GenerationSettings {
+            temperature: config.temperature,
+            top_p: config.top_p,
+            max_output_tokens: config.max_output_tokens,
+            stop_sequences: config.stop_sequences,
+            safety_threshold: payload
+                .safety_settings
+                .into_iter()
+                .next()
+                .map(|setting| setting.threshold),
+        },
+        response_schema: config.response_schema.map(|schema| ResponseSchema {
+            name: "response",
+            schema,
+        }),
+    }
+}
+
+/// answers a stream; failures before the first chunk become an HTTP error like the
+/// real API, later ones are sent as events
+async fn stream_response(mock: &MockProvider, request: LlmRequest) -> Response {
+    let mut items = mock.stream(request);
+
+    let first = match items.next().await {
+        Some(Err(e)) => return error_response(e),
+        first => first,
+    };
+
+    let events = stream::iter(first)
+        .chain(items)
+        .map(|item| Ok::<_, Infallible>(stream_event(item)));
+
+    Sse::new(events).into_response()
+}
+
+fn stream_event(item: Result<StreamItem, AiError>) -> Event {
+    let data = match item {
+        Ok(StreamItem::Text(text)) => json(&response(Some(text), None, None)),
+        Ok(StreamItem::Usage(usage)) => json(&response(None, None, Some(usage))),
+        Ok(StreamItem::Finished { reason }) => json(&response(None, Some(reason), None)),
+        // a blocked candidate is reported through its finish reason, not as an error
+        Err(AiError::Blocked(reason)) => json(&response(None, Some(reason), None)),
+        Err(AiError::Malformed(_)) => "{ this is not json".to_string(),
+        Err(e) => json(&error_body(&e)),
+    };
+
+    Event::default().data(data)
+}
+
+fn completion_response(completion: Completion) -> GeminiResponse {
+    response(
+        Some(completion.text),
+        completion.finish_reason,
+        completion.usage,
+    )
+}
+
+fn response(
+    text: Option<String>,
+    finish_reason: Option<String>,
+    usage: Option<TokenUsage>,
+) -> GeminiResponse {
+    let candidates = if text.is_some() || finish_reason.is_some() {
+        vec![Candidate {
+            content: text.map(|text| ContentResponse {
+                parts: vec![PartResponse { text }],
+                _role: "model".to_string(),
+            }),
+            finish_reason,
+        }]
+    } else {
+        Vec::new()
+    };
+
+    GeminiResponse {
+        candidates,
+        prompt_feedback: None,
+        usage_metadata: usage.map(|usage| UsageMetadata {
+            prompt_token_count: usage.prompt_tokens,
+            candidates_token_count: usage.response_tokens,
+        }),
+    }
+}
+
+/// the error body and status Gemini uses for `e`
+fn error_body(e: &AiError) -> GeminiErrorResponse {
+    let (code, status) = match e {
+        // Gemini reports a bad key as a plain invalid argument
+        AiError::Auth(_) => (400, "INVALID_ARGUMENT"),
+        AiError::RateLimited { .. } => (429, "RESOURCE_EXHAUSTED"),
+        AiError::Timeout => (504, "DEADLINE_EXCEEDED"),
+        AiError::Unavailable(_) => (503, "UNAVAILABLE"),
+        AiError::Rejected { status, .. } => (*status, "INVALID_ARGUMENT"),
+        AiError::Blocked(_) | AiError::Malformed(_) => (500, "INTERNAL"),
+    };
+
+    let message = match e {
+        AiError::Auth(_) => "API key not valid. Please pass a valid API key.".to_string(),
+        other => other.to_string(),
+    };
+
+    GeminiErrorResponse {
+        error: GeminiError {
+            code,
+            message,
+            status: Some(status.to_string()),
+        },
+    }
+}
+
+fn error_response(e: AiError) -> Response {
+    match e {
+        // both arrive with a 200 status from the real API
+        AiError::Blocked(reason) => Json(response(None, Some(reason), None)).into_response(),
+        AiError::Malformed(_) => (StatusCode::OK, "{ this is not json").into_response(),
+        e => {
+            let body = error_body(&e);
+            let status =
+                StatusCode::from_u16(body.error.code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            let mut response = (status, Json(body)).into_response();
+
+            if let AiError::RateLimited {
+                retry_after: Some(delay),
+                ..
+            } = e
+            {
+                if let Ok(value) = HeaderValue::from_str(&delay.as_secs().to_string()) {
+                    response.headers_mut().insert(header::RETRY_AFTER, value);
+                }
+            }
+
+            response
+        }
+    }
+}
+
+fn json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
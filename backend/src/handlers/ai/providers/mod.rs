@@ -0,0 +1,161 @@
+use std::{env, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures_util::stream::BoxStream;
+
+use crate::{
+    handlers::ai::{
+        error::AiError,
+        providers::{
+            gemini::GeminiProvider,
+            mock::MockProvider,
+            openai::OpenAiProvider,
+            resilient::{ResilientProvider, RetryPolicy},
+        },
+    },
+    models::generation::GenerationSettings,
+};
+
+pub mod gemini;
+pub mod mock;
+pub mod mock_server;
+pub mod openai;
+pub mod resilient;
+
+/// how long a whole non-streaming call may take
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// how long a stream may go quiet before it counts as timed out
+pub const STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// provider shared between all handlers through the app state
+pub type SharedLlmProvider = Arc<dyn LlmProvider>;
+
+/// who said a turn in a vendor neutral conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatRole {
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChatTurn {
+    pub role: ChatRole,
+    pub text: String,
+}
+
+/// everything a provider needs to produce Bodhi's next reply
+#[derive(Debug, Clone)]
+pub struct LlmRequest {
+    /// instructions and teaching material, sent apart from the conversation
+    pub system_prompt: String,
+    pub turns: Vec<ChatTurn>,
+    /// already merged with the app defaults; providers ignore what they can't express
+    pub settings: GenerationSettings,
+    /// asks for a JSON reply following this schema instead of free text
+    pub response_schema: Option<ResponseSchema>,
+}
+
+/// a JSON schema for structured replies, written in plain JSON Schema with every
+/// property required; providers translate it to their own dialect
+#[derive(Debug, Clone)]
+pub struct ResponseSchema {
+    pub name: &'static str,
+    pub schema: serde_json::Value,
+}
+
+/// tokens billed for one call, as reported by the API
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub response_tokens: u32,
+}
+
+/// a whole reply from `complete`
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub text: String,
+    /// `None` when the API doesn't report usage
+    pub usage: Option<TokenUsage>,
+    pub finish_reason: Option<String>,
+}
+
+/// one decoded piece of a streamed reply
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamItem {
+    /// the next fragment of reply text
+    Text(String),
+    /// token counts so far; a later item replaces an earlier one
+    Usage(TokenUsage),
+    /// the model stopped normally, e.g. `STOP` or `MAX_TOKENS`. usage may still follow
+    Finished { reason: String },
+}
+
+/// a backend that can answer a Bodhi conversation, either in one go or chunk by chunk
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// the model name answering requests, mainly for logging
+    fn model(&self) -> &str;
+
+    /// waits for the whole reply
+    async fn complete(&self, request: LlmRequest) -> Result<Completion, AiError>;
+
+    /// yields the reply piece by piece as the model produces it, ending with a
+    /// `Finished` item or an error
+    fn stream(&self, request: LlmRequest) -> BoxStream<'static, Result<StreamItem, AiError>>;
+}
+
+/// builds the provider selected by the `LLM_PROVIDER` env var (gemini, openai or mock),
+/// wrapped with retries and the concurrency limit from `LLM_MAX_RETRIES` and
+/// `LLM_MAX_CONCURRENT_REQUESTS`
+pub fn provider_from_env() -> Result<SharedLlmProvider, anyhow::Error> {
+    let kind = env::var("LLM_PROVIDER").unwrap_or_else(|_| "gemini".to_string());
+    let model = env::var("LLM_MODEL").ok();
+
+    let provider: SharedLlmProvider = match kind.to_lowercase().as_str() {
+        "gemini" => {
+            let api_key = env::var("GEMINI_API_KEY").map_err(|_| {
+                anyhow::anyhow!("GEMINI_API_KEY must be set for the gemini provider")
+            })?;
+            // pointed at the mock server for offline runs of the real wire format
+            let base_url = env::var("GEMINI_BASE_URL")
+                .unwrap_or_else(|_| gemini::DEFAULT_BASE_URL.to_string());
+            Arc::new(GeminiProvider::new(
+                base_url,
+                api_key,
+                model.unwrap_or_else(|| gemini::DEFAULT_MODEL.to_string()),
+            ))
+        }
+        "openai" => {
+            let base_url =
+                env::var("LLM_BASE_URL").unwrap_or_else(|_| openai::DEFAULT_BASE_URL.to_string());
+            // local llama.cpp / Ollama servers usually run without a key
+            let api_key = env::var("LLM_API_KEY").ok();
+            Arc::new(OpenAiProvider::new(
+                base_url,
+                api_key,
+                model.unwrap_or_else(|| openai::DEFAULT_MODEL.to_string()),
+            ))
+        }
+        "mock" => Arc::new(MockProvider::from_env()?),
+        other => anyhow::bail!("Unknown LLM_PROVIDER: {}", other),
+    };
+
+    let max_concurrent = env::var("LLM_MAX_CONCURRENT_REQUESTS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(4);
+    let policy = RetryPolicy {
+        max_retries: env::var("LLM_MAX_RETRIES")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(3),
+        ..RetryPolicy::default()
+    };
+
+    tracing::info!("Using {} provider with model {}", kind, provider.model());
+    Ok(Arc::new(ResilientProvider::new(
+        provider,
+        policy,
+        max_concurrent,
+    )))
+}
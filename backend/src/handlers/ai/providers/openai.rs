@@ -0,0 +1,217 @@
+use async_trait::async_trait;
+use futures_util::{StreamExt, stream::BoxStream};
+use reqwest::{Client, RequestBuilder, Response};
+use tokio::time::timeout;
+
+use crate::handlers::ai::{
+    error::{AiError, retry_after},
+    model::{
+        ChatCompletionChunk, ChatCompletionErrorResponse, ChatCompletionMessage,
+        ChatCompletionRequest, ChatCompletionResponse, ChatCompletionUsage, JsonSchemaFormat,
+        ResponseFormat, StreamOptions,
+    },
+    providers::{
+        ChatRole, Completion, LlmProvider, LlmRequest, REQUEST_TIMEOUT, STREAM_IDLE_TIMEOUT,
+        StreamItem, TokenUsage,
+    },
+    sse::SseDecoder,
+};
+
+// finish reason of a reply cut off by the server's moderation
+const CONTENT_FILTER: &str = "content_filter";
+
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+pub const DEFAULT_MODEL: &str = "gpt-4o-mini";
+
+/// speaks the `/chat/completions` API, which also covers llama.cpp and Ollama servers
+pub struct OpenAiProvider {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+impl OpenAiProvider {
+    pub fn new(base_url: String, api_key: Option<String>, model: String) -> Self {
+        OpenAiProvider {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model,
+        }
+    }
+
+    fn post(&self, payload: &ChatCompletionRequest) -> RequestBuilder {
+        let request = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(payload);
+
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    fn build_payload(&self, request: LlmRequest, stream: bool) -> ChatCompletionRequest {
+        let mut messages = vec![ChatCompletionMessage {
+            role: "system".to_string(),
+            content: request.system_prompt,
+        }];
+
+        messages.extend(request.turns.into_iter().map(|turn| ChatCompletionMessage {
+            role: match turn.role {
+                ChatRole::User => "user".to_string(),
+                ChatRole::Assistant => "assistant".to_string(),
+            },
+            content: turn.text,
+        }));
+
+        // safety thresholds are Gemini specific and have no equivalent here
+        let settings = request.settings;
+        ChatCompletionRequest {
+            model: self.model.clone(),
+            messages,
+            stream,
+            temperature: settings.temperature,
+            top_p: settings.top_p,
+            max_tokens: settings.max_output_tokens,
+            stop: settings.stop_sequences,
+            stream_options: stream.then_some(StreamOptions {
+                include_usage: true,
+            }),
+            response_format: request.response_schema.map(|schema| ResponseFormat {
+                kind: "json_schema".to_string(),
+                json_schema: JsonSchemaFormat {
+                    name: schema.name.to_string(),
+                    strict: true,
+                    schema: schema.schema,
+                },
+            }),
+        }
+    }
+}
+
+fn token_usage(usage: ChatCompletionUsage) -> TokenUsage {
+    TokenUsage {
+        prompt_tokens: usage.prompt_tokens,
+        response_tokens: usage.completion_tokens,
+    }
+}
+
+/// turns one streamed chunk into the items it carries
+fn decode_stream_event(data: &str) -> Vec<Result<StreamItem, AiError>> {
+    let chunk = match serde_json::from_str::<ChatCompletionChunk>(data) {
+        Ok(chunk) => chunk,
+        Err(_) => {
+            return vec![Err(AiError::Malformed(format!(
+                "Malformed stream event: {}",
+                data
+            )))];
+        }
+    };
+
+    let mut items = Vec::new();
+    if let Some(usage) = chunk.usage {
+        items.push(Ok(StreamItem::Usage(token_usage(usage))));
+    }
+
+    let Some(choice) = chunk.choices.into_iter().next() else {
+        return items;
+    };
+
+    if let Some(text) = choice.delta.content.filter(|text| !text.is_empty()) {
+        items.push(Ok(StreamItem::Text(text)));
+    }
+
+    match choice.finish_reason {
+        Some(reason) if reason == CONTENT_FILTER => items.push(Err(AiError::Blocked(reason))),
+        Some(reason) => items.push(Ok(StreamItem::Finished { reason })),
+        None => {}
+    }
+
+    items
+}
+
+/// turns a non-success response into the matching error
+async fn error_for_status(response: Response) -> Result<Response, AiError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let retry_after = retry_after(response.headers());
+    let body = response.text().await?;
+    let message = match serde_json::from_str::<ChatCompletionErrorResponse>(&body) {
+        Ok(payload) => match payload.error.kind {
+            Some(kind) => format!("{}: {}", kind, payload.error.message),
+            None => payload.error.message,
+        },
+        Err(_) => body.trim().to_string(),
+    };
+
+    Err(AiError::from_status(status.as_u16(), message, retry_after))
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn complete(&self, request: LlmRequest) -> Result<Completion, AiError> {
+        let payload = self.build_payload(request, false);
+
+        let response = self.post(&payload).timeout(REQUEST_TIMEOUT).send().await?;
+        let body = error_for_status(response).await?.text().await?;
+        let response = serde_json::from_str::<ChatCompletionResponse>(&body)
+            .map_err(|e| AiError::Malformed(e.to_string()))?;
+
+        let choice = response
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| AiError::Malformed("Reply contains no choices".to_string()))?;
+
+        if choice.finish_reason.as_deref() == Some(CONTENT_FILTER) {
+            return Err(AiError::Blocked(CONTENT_FILTER.to_string()));
+        }
+
+        Ok(Completion {
+            text: choice.message.content,
+            usage: response.usage.map(token_usage),
+            finish_reason: choice.finish_reason,
+        })
+    }
+
+    fn stream(&self, request: LlmRequest) -> BoxStream<'static, Result<StreamItem, AiError>> {
+        let payload = self.build_payload(request, true);
+        let request = self.post(&payload);
+
+        async_stream::try_stream! {
+            let response = timeout(STREAM_IDLE_TIMEOUT, request.send())
+                .await
+                .map_err(|_| AiError::Timeout)??;
+
+            let mut byte_stream = error_for_status(response).await?.bytes_stream();
+            let mut decoder = SseDecoder::new();
+
+            // chunks are SSE `data:` events which may be split across network reads
+            'read: while let Some(chunk) = timeout(STREAM_IDLE_TIMEOUT, byte_stream.next())
+                .await
+                .map_err(|_| AiError::Timeout)?
+            {
+                for data in decoder.push(&chunk?) {
+                    if data == "[DONE]" {
+                        break 'read;
+                    }
+
+                    for item in decode_stream_event(&data) {
+                        yield item?;
+                    }
+                }
+            }
+        }
+        .boxed()
+    }
+}
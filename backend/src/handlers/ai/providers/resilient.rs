@@ -0,0 +1,164 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures_util::{StreamExt, stream::BoxStream};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::handlers::ai::{
+    error::AiError,
+    providers::{Completion, LlmProvider, LlmRequest, SharedLlmProvider, StreamItem},
+};
+
+/// how temporary failures (rate limits, timeouts, 5xx) are retried
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// first backoff delay, doubled on every further retry
+    pub base_delay: Duration,
+    /// longest we wait before a retry; a longer `Retry-After` fails the call instead
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(20),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// how long to wait before retry number `attempt` (counting from 0) after `error`,
+    /// `None` when the call should fail instead
+    fn delay(&self, attempt: u32, error: &AiError) -> Option<Duration> {
+        if !error.is_retryable() || attempt >= self.max_retries {
+            return None;
+        }
+
+        match error {
+            // the server knows best when it'll take us again
+            AiError::RateLimited {
+                retry_after: Some(delay),
+                ..
+            } => (*delay <= self.max_delay).then_some(*delay),
+            _ => Some(
+                self.base_delay
+                    .saturating_mul(2u32.saturating_pow(attempt))
+                    .min(self.max_delay),
+            ),
+        }
+    }
+}
+
+/// wraps a provider with retries and a limit on concurrent outbound calls, so a
+/// classroom of teachers doesn't trip the vendor's rate limits all at once
+pub struct ResilientProvider {
+    inner: SharedLlmProvider,
+    policy: RetryPolicy,
+    permits: Arc<Semaphore>,
+}
+
+impl ResilientProvider {
+    pub fn new(inner: SharedLlmProvider, policy: RetryPolicy, max_concurrent: usize) -> Self {
+        ResilientProvider {
+            inner,
+            policy,
+            permits: Arc::new(Semaphore::new(max_concurrent.max(1))),
+        }
+    }
+}
+
+// the semaphore lives as long as the provider and is never closed
+async fn acquire(permits: &Arc<Semaphore>) -> OwnedSemaphorePermit {
+    permits
+        .clone()
+        .acquire_owned()
+        .await
+        .expect("LLM concurrency limiter closed")
+}
+
+#[async_trait]
+impl LlmProvider for ResilientProvider {
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    async fn complete(&self, request: LlmRequest) -> Result<Completion, AiError> {
+        let mut attempt = 0;
+
+        loop {
+            let result = {
+                let _permit = acquire(&self.permits).await;
+                self.inner.complete(request.clone()).await
+            };
+
+            let error = match result {
+                Ok(completion) => return Ok(completion),
+                Err(e) => e,
+            };
+
+            match self.policy.delay(attempt, &error) {
+                Some(delay) => {
+                    tracing::warn!(
+                        "{} call failed ({}), retrying in {:?}",
+                        self.model(),
+                        error,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                None => return Err(error),
+            }
+        }
+    }
+
+    fn stream(&self, request: LlmRequest) -> BoxStream<'static, Result<StreamItem, AiError>> {
+        let inner = self.inner.clone();
+        let policy = self.policy;
+        let permits = self.permits.clone();
+
+        async_stream::stream! {
+            let mut attempt = 0;
+
+            'attempts: loop {
+                // held until the stream ends, a long reply counts as one ongoing call
+                let permit = acquire(&permits).await;
+                let mut upstream = inner.stream(request.clone());
+                let mut started = false;
+
+                while let Some(item) = upstream.next().await {
+                    match item {
+                        // text already sent to the teacher can't be taken back,
+                        // so only failures before the first item are retried
+                        Err(error) if !started => {
+                            if let Some(delay) = policy.delay(attempt, &error) {
+                                tracing::warn!(
+                                    "{} stream failed ({}), retrying in {:?}",
+                                    inner.model(),
+                                    error,
+                                    delay
+                                );
+                                drop(permit);
+                                tokio::time::sleep(delay).await;
+                                attempt += 1;
+                                continue 'attempts;
+                            }
+                            yield Err(error);
+                            return;
+                        }
+                        item => {
+                            started = true;
+                            yield item;
+                        }
+                    }
+                }
+
+                return;
+            }
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,551 @@
+use std::time::Instant;
+
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    citations::cite_excerpt,
+    database::{
+        messages::insert_exchange,
+        quiz_questions::{advance_quiz, create_quiz_questions, list_quiz_questions},
+    },
+    handlers::ai::{
+        client::{complete_and_record, message_usage, parse_json_reply},
+        concepts::sample_material,
+        context::{ContextBudget, estimate_tokens},
+        error::AiError,
+        prompt::{GRADE_QUIZ_ANSWER_PROMPT, QUIZ_QUESTIONS_PROMPT},
+        providers::{ChatRole, ChatTurn, LlmProvider, LlmRequest},
+    },
+    models::{
+        generation::GenerationSettings,
+        material::PageCitation,
+        message::{CreateMessage, Message},
+        quiz::{
+            MAX_QUESTION_SCORE, MIN_QUESTION_SCORE, QuestionKind, QuizGrade, QuizMessageMeta,
+            QuizQuestion, QuizQuestionDraft,
+        },
+        session::Session,
+        usage::{CallPurpose, MessageUsage},
+    },
+};
+
+// long enough to cover the material, short enough to finish in one sitting
+const QUIZ_LENGTH: usize = 8;
+// a question, four options, the answer and a quote for each
+const QUESTIONS_REPLY_TOKENS: u32 = 3072;
+// longer material is sampled evenly instead of sent whole
+const MAX_QUIZ_MATERIAL_TOKENS: usize = 12_000;
+// a score and a few sentences of feedback
+const GRADE_REPLY_TOKENS: u32 = 512;
+const OPTION_LETTERS: [char; 4] = ['A', 'B', 'C', 'D'];
+
+#[derive(Deserialize)]
+struct QuestionsReply {
+    #[serde(default)]
+    questions: Vec<QuizQuestionDraft>,
+}
+
+/// one exchange of a quiz: the grade of the user's answer, if the message answered
+/// a question, and Bodhi's reply with the next question
+pub struct QuizTurn {
+    pub reply: String,
+    pub answer: Option<QuizMessageMeta>, // what the user's message answered
+    pub question: Option<QuizMessageMeta>, // what the reply asks
+    pub grade: Option<QuizGrade>,
+    pub usage: Option<MessageUsage>, // of grading the answer, when a model did it
+    pub citations: Vec<PageCitation>, // pages of the graded and the next question's evidence
+}
+
+/// asks the model for the quiz questions of the session's material
+pub async fn generate_questions(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    session: &Session,
+) -> Result<Vec<QuizQuestionDraft>, AiError> {
+    let system_prompt = QUIZ_QUESTIONS_PROMPT.replace("{count}", &QUIZ_LENGTH.to_string());
+    let material_budget = ContextBudget::for_model(llm.model())
+        .prompt_tokens(QUESTIONS_REPLY_TOKENS as usize)
+        .saturating_sub(estimate_tokens(&system_prompt))
+        .min(MAX_QUIZ_MATERIAL_TOKENS);
+
+    let request = LlmRequest {
+        system_prompt,
+        turns: vec![ChatTurn {
+            role: ChatRole::User,
+            text: format!(
+                "Study material:\n{}",
+                sample_material(&session.material_text, material_budget)
+            ),
+        }],
+        settings: GenerationSettings {
+            temperature: Some(0.4),
+            max_output_tokens: Some(QUESTIONS_REPLY_TOKENS),
+            ..GenerationSettings::defaults()
+        },
+        response_schema: None,
+    };
+
+    let reply = complete_and_record(pool, llm, session.id, CallPurpose::QuizQuestions, request)
+        .await?
+        .text;
+    let parsed: QuestionsReply =
+        parse_json_reply(&reply).map_err(|e| AiError::Malformed(e.to_string()))?;
+
+    let mut questions: Vec<QuizQuestionDraft> = parsed
+        .questions
+        .into_iter()
+        .filter_map(normalize_question)
+        .collect();
+    questions.truncate(QUIZ_LENGTH);
+
+    if questions.is_empty() {
+        return Err(AiError::Malformed("No usable quiz questions".to_string()));
+    }
+    Ok(questions)
+}
+
+// drops unusable questions and turns a multiple choice answer into its option letter
+fn normalize_question(mut draft: QuizQuestionDraft) -> Option<QuizQuestionDraft> {
+    draft.question = draft.question.trim().to_string();
+    draft.answer = draft.answer.trim().to_string();
+    if draft.question.is_empty() || draft.answer.is_empty() {
+        return None;
+    }
+
+    if draft.kind != QuestionKind::MultipleChoice {
+        draft.options.clear();
+        return Some(draft);
+    }
+
+    draft.options.truncate(OPTION_LETTERS.len());
+    if draft.options.len() < 2 {
+        return None;
+    }
+    let index = option_index(&draft.answer, draft.options.len()).or_else(|| {
+        draft
+            .options
+            .iter()
+            .position(|option| option.trim().eq_ignore_ascii_case(&draft.answer))
+    })?;
+    draft.answer = OPTION_LETTERS[index].to_string();
+
+    Some(draft)
+}
+
+/// the option a letter like "b", "B)" or "(B)." stands for
+fn option_index(text: &str, options: usize) -> Option<usize> {
+    let letter = text
+        .trim()
+        .trim_matches(|c: char| matches!(c, '(' | ')' | '.' | ':'))
+        .to_ascii_uppercase();
+    let mut chars = letter.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+
+    OPTION_LETTERS[..options].iter().position(|&l| l == c)
+}
+
+/// the session's quiz questions, generated first when the quiz hasn't started. when two
+/// messages start it at once only the quiz stored first is kept
+pub async fn ensure_quiz(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    session: &Session,
+) -> Result<Vec<QuizQuestion>, anyhow::Error> {
+    let questions = list_quiz_questions(pool, session.id).await?;
+    if !questions.is_empty() {
+        return Ok(questions);
+    }
+
+    let drafts = generate_questions(pool, llm, session).await?;
+    match create_quiz_questions(pool, session.id, &drafts).await {
+        Ok(questions) => Ok(questions),
+        // the other message's questions took the positions first
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            Ok(list_quiz_questions(pool, session.id).await?)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// grades the user's `answer` against the expected one and its quote from the material
+pub async fn grade_answer(
+    llm: &dyn LlmProvider,
+    question: &QuizQuestion,
+    answer: &str,
+) -> Result<(QuizGrade, Option<MessageUsage>), AiError> {
+    // a bare option letter needs no model
+    if question.kind == QuestionKind::MultipleChoice {
+        if let Some(index) = option_index(answer, question.options.len()) {
+            return Ok((grade_choice(question, index), None));
+        }
+    }
+
+    let expected = match question.kind {
+        QuestionKind::MultipleChoice => option_text(question, &question.answer),
+        _ => question.answer.clone(),
+    };
+    let mut asked = question.question.clone();
+    for (letter, option) in OPTION_LETTERS.iter().zip(&question.options) {
+        asked.push_str(&format!("\n{}) {}", letter, option));
+    }
+
+    let request = LlmRequest {
+        system_prompt: GRADE_QUIZ_ANSWER_PROMPT
+            .replace("{kind}", question.kind.as_str())
+            .replace("{question}", &asked)
+            .replace("{answer}", &expected)
+            .replace("{evidence}", &question.evidence),
+        turns: vec![ChatTurn {
+            role: ChatRole::User,
+            text: format!("Learner's answer:\n{}", answer),
+        }],
+        settings: GenerationSettings {
+            temperature: Some(0.0),
+            max_output_tokens: Some(GRADE_REPLY_TOKENS),
+            ..GenerationSettings::defaults()
+        },
+        response_schema: None,
+    };
+
+    let started = Instant::now();
+    let completion = llm.complete(request).await?;
+    let usage = message_usage(
+        llm,
+        completion.usage,
+        completion.finish_reason.clone(),
+        started,
+    );
+    let mut grade: QuizGrade =
+        parse_json_reply(&completion.text).map_err(|e| AiError::Malformed(e.to_string()))?;
+    grade.score = grade.score.clamp(MIN_QUESTION_SCORE, MAX_QUESTION_SCORE);
+    grade.feedback = grade.feedback.trim().to_string();
+
+    Ok((grade, Some(usage)))
+}
+
+fn grade_choice(question: &QuizQuestion, chosen: usize) -> QuizGrade {
+    let correct = OPTION_LETTERS[chosen].to_string() == question.answer;
+    let mut feedback = format!("The answer is {}.", option_text(question, &question.answer));
+    if !question.evidence.is_empty() {
+        feedback.push_str(&format!(" The material says: \"{}\"", question.evidence));
+    }
+
+    QuizGrade {
+        score: if correct {
+            MAX_QUESTION_SCORE
+        } else {
+            MIN_QUESTION_SCORE
+        },
+        feedback,
+    }
+}
+
+// "B) the option" for a letter of the question's options
+fn option_text(question: &QuizQuestion, letter: &str) -> String {
+    option_index(letter, question.options.len())
+        .map(|i| format!("{}) {}", OPTION_LETTERS[i], question.options[i]))
+        .unwrap_or_else(|| letter.to_string())
+}
+
+/// the question as Bodhi asks it in the chat
+pub fn format_question(question: &QuizQuestion, total: usize) -> String {
+    let mut text = format!(
+        "Question {} of {} ({}): {}",
+        question.position,
+        total,
+        question.kind.label(),
+        question.question
+    );
+    for (letter, option) in OPTION_LETTERS.iter().zip(&question.options) {
+        text.push_str(&format!("\n{}) {}", letter, option));
+    }
+    text
+}
+
+/// grades the user's message if it answers the question Bodhi asked last and
+/// writes the reply that asks the next one. nothing is stored until `save_quiz_exchange`
+pub async fn quiz_turn(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    session: &Session,
+    text: &str,
+) -> Result<QuizTurn, anyhow::Error> {
+    let questions = ensure_quiz(pool, llm, session).await?;
+    let total = questions.len();
+    let meta = |question: &QuizQuestion, score| QuizMessageMeta {
+        question_id: question.id,
+        position: question.position,
+        total: total as i64,
+        score,
+    };
+
+    let mut reply = Vec::new();
+    let mut turn = QuizTurn {
+        reply: String::new(),
+        answer: None,
+        question: None,
+        grade: None,
+        usage: None,
+        citations: Vec::new(),
+    };
+
+    let pending = questions.iter().find(|q| q.asked && q.score.is_none());
+    let mut scores: Vec<i64> = questions.iter().filter_map(|q| q.score).collect();
+    match pending {
+        Some(question) => {
+            let (grade, usage) = grade_answer(llm, question, text).await?;
+            reply.push(format!(
+                "{} ({}/{}). {}",
+                verdict(grade.score),
+                grade.score,
+                MAX_QUESTION_SCORE,
+                grade.feedback
+            ));
+            scores.push(grade.score);
+            turn.answer = Some(meta(question, Some(grade.score)));
+            turn.citations.extend(cite_evidence(session, question));
+            turn.grade = Some(grade);
+            turn.usage = usage;
+        }
+        None if !questions.iter().any(|q| q.asked) => reply.push(format!(
+            "Let's start the quiz on \"{}\": {} questions, answer them one at a time.",
+            session.topic, total
+        )),
+        None => {}
+    }
+
+    match questions.iter().find(|q| !q.asked) {
+        Some(next) => {
+            reply.push(format_question(next, total));
+            turn.question = Some(meta(next, None));
+            if let Some(citation) = cite_evidence(session, next) {
+                if !turn.citations.contains(&citation) {
+                    turn.citations.push(citation);
+                }
+            }
+        }
+        None => reply.push(format!(
+            "{} You scored {} of {} points.",
+            if turn.answer.is_some() {
+                "That was the last question."
+            } else {
+                "The quiz is over."
+            },
+            scores.iter().sum::<i64>(),
+            total as i64 * MAX_QUESTION_SCORE
+        )),
+    }
+
+    turn.reply = reply.join("\n\n");
+    Ok(turn)
+}
+
+// the page the question's evidence was quoted from
+fn cite_evidence(session: &Session, question: &QuizQuestion) -> Option<PageCitation> {
+    cite_excerpt(
+        &session.material_text,
+        &session.material_pages,
+        &question.evidence,
+    )
+}
+
+fn verdict(score: i64) -> &'static str {
+    match score {
+        s if s >= 8 => "Correct",
+        s if s >= 4 => "Partly right",
+        _ => "Not quite",
+    }
+}
+
+/// saves the exchange of a quiz `turn` together with the grade and the newly asked
+/// question, so a failed save leaves the quiz where it was
+pub async fn save_quiz_exchange(
+    pool: &SqlitePool,
+    session_id: Uuid,
+    user_message: CreateMessage,
+    assistant_message: CreateMessage,
+    turn: &QuizTurn,
+) -> Result<(Message, Message), sqlx::Error> {
+    let graded = turn
+        .answer
+        .as_ref()
+        .zip(turn.grade.as_ref())
+        .map(|(answer, grade)| (answer.question_id, grade));
+    let asked = turn.question.as_ref().map(|question| question.question_id);
+
+    let mut tx = pool.begin().await?;
+    let exchange = insert_exchange(
+        &mut tx,
+        session_id,
+        user_message,
+        assistant_message,
+        false,
+        turn.usage.clone(),
+        None,
+    )
+    .await?;
+    advance_quiz(&mut tx, graded, asked).await?;
+    tx.commit().await?;
+
+    Ok(exchange)
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use futures_util::stream::BoxStream;
+    use serde_json::json;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::{
+        database::sessions::create_session,
+        handlers::ai::providers::{Completion, StreamItem},
+        models::{
+            message::MessageRole,
+            session::{CreateSession, SessionMode},
+        },
+    };
+
+    async fn test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!().run(&pool).await.unwrap();
+        pool
+    }
+
+    async fn quiz_session(pool: &SqlitePool) -> Session {
+        create_session(
+            pool,
+            CreateSession {
+                topic: "Cells".to_string(),
+                material_text: "The nucleus holds the DNA of the cell.".to_string(),
+                persona_id: None,
+                generation_settings: None,
+                mode: SessionMode::Quiz,
+                source: None,
+                upload: None,
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    fn draft(question: &str) -> QuizQuestionDraft {
+        QuizQuestionDraft {
+            kind: QuestionKind::ShortAnswer,
+            question: question.to_string(),
+            options: Vec::new(),
+            answer: "The nucleus".to_string(),
+            evidence: "The nucleus holds the DNA".to_string(),
+        }
+    }
+
+    /// writes the quiz, but another message stores its quiz while it does
+    struct RacedQuizModel {
+        pool: SqlitePool,
+        session_id: Uuid,
+    }
+
+    #[async_trait]
+    impl LlmProvider for RacedQuizModel {
+        fn model(&self) -> &str {
+            "raced"
+        }
+
+        async fn complete(&self, _request: LlmRequest) -> Result<Completion, AiError> {
+            create_quiz_questions(&self.pool, self.session_id, &[draft("Stored first?")])
+                .await
+                .unwrap();
+            let questions = json!({ "questions": [
+                { "kind": "short_answer", "question": "Stored second?", "answer": "No" },
+                { "kind": "short_answer", "question": "And this?", "answer": "No" }
+            ]});
+            Ok(Completion {
+                text: questions.to_string(),
+                usage: None,
+                finish_reason: Some("STOP".to_string()),
+            })
+        }
+
+        fn stream(&self, _request: LlmRequest) -> BoxStream<'static, Result<StreamItem, AiError>> {
+            unimplemented!("quizzes are written with complete")
+        }
+    }
+
+    #[tokio::test]
+    async fn a_quiz_started_twice_at_once_keeps_the_first() {
+        let pool = test_pool().await;
+        let session = quiz_session(&pool).await;
+        let llm = RacedQuizModel {
+            pool: pool.clone(),
+            session_id: session.id,
+        };
+
+        let questions = ensure_quiz(&pool, &llm, &session).await.unwrap();
+
+        let asked: Vec<&str> = questions.iter().map(|q| q.question.as_str()).collect();
+        assert_eq!(asked, ["Stored first?"]);
+        assert_eq!(
+            list_quiz_questions(&pool, session.id).await.unwrap().len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn saving_a_quiz_exchange_grades_the_answer_and_asks_the_next_question() {
+        let pool = test_pool().await;
+        let session = quiz_session(&pool).await;
+        let questions =
+            create_quiz_questions(&pool, session.id, &[draft("First?"), draft("Second?")])
+                .await
+                .unwrap();
+        let meta = |question: &QuizQuestion, score| QuizMessageMeta {
+            question_id: question.id,
+            position: question.position,
+            total: 2,
+            score,
+        };
+        let message = |role, content: &str| CreateMessage {
+            role,
+            content: content.to_string(),
+            quiz: None,
+            debate: None,
+            clarity: None,
+            citations: Vec::new(),
+        };
+        let turn = QuizTurn {
+            reply: "Correct (10/10). Question 2 of 2".to_string(),
+            answer: Some(meta(&questions[0], Some(10))),
+            question: Some(meta(&questions[1], None)),
+            grade: Some(QuizGrade {
+                score: 10,
+                feedback: "Yes.".to_string(),
+            }),
+            usage: None,
+            citations: Vec::new(),
+        };
+
+        save_quiz_exchange(
+            &pool,
+            session.id,
+            message(MessageRole::User, "The nucleus"),
+            message(MessageRole::Assistant, &turn.reply),
+            &turn,
+        )
+        .await
+        .unwrap();
+
+        let saved = list_quiz_questions(&pool, session.id).await.unwrap();
+        assert_eq!(saved[0].score, Some(10));
+        assert!(saved[1].asked);
+        assert_eq!(saved[1].score, None);
+    }
+}
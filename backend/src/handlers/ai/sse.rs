@@ -0,0 +1,111 @@
+/// incremental decoder for `text/event-stream` response bodies.
+///
+/// network chunks can end anywhere, even in the middle of a multi-byte character,
+/// so bytes are buffered until a full line is available and `data:` lines are
+/// collected until the blank line that terminates an event.
+#[derive(Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// feeds the next chunk of bytes and returns the data of every event completed by it
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut events = Vec::new();
+        while let Some(newline) = self.buffer.iter().position(|b| *b == b'\n') {
+            let mut line: Vec<u8> = self.buffer.drain(..=newline).collect();
+            line.pop(); // the '\n' itself
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+
+            if let Some(event) = self.process_line(&String::from_utf8_lossy(&line)) {
+                events.push(event);
+            }
+        }
+
+        events
+    }
+
+    /// flushes whatever is left once the body has ended without a trailing blank line
+    pub fn finish(&mut self) -> Option<String> {
+        if !self.buffer.is_empty() {
+            let line = std::mem::take(&mut self.buffer);
+            let line = String::from_utf8_lossy(&line)
+                .trim_end_matches('\r')
+                .to_string();
+            if let Some(event) = self.process_line(&line) {
+                return Some(event);
+            }
+        }
+
+        self.dispatch()
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<String> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        // comments (`: keep-alive`) and fields other than data are of no use to us
+        if let Some(value) = line.strip_prefix("data:") {
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            self.data.push(value.to_string());
+        } else if line == "data" {
+            self.data.push(String::new());
+        }
+
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<String> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        Some(std::mem::take(&mut self.data).join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_data_lines_and_skips_comments() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.push(b": keep-alive\nevent: message\ndata: one\ndata\ndata:two\n\n");
+
+        assert_eq!(events, ["one\n\ntwo"]);
+    }
+
+    #[test]
+    fn waits_for_the_blank_line() {
+        let mut decoder = SseDecoder::new();
+
+        assert!(decoder.push(b"data: first\r\n").is_empty());
+        assert_eq!(decoder.push(b"\r\ndata: sec"), ["first"]);
+        assert_eq!(decoder.finish().as_deref(), Some("sec"));
+    }
+
+    #[test]
+    fn decodes_the_same_wherever_the_body_is_split() {
+        let body = "data: café ☕\r\n\r\ndata: 🌱\r\n\r\n".as_bytes();
+
+        for split in 0..=body.len() {
+            let mut decoder = SseDecoder::new();
+            let mut events = decoder.push(&body[..split]);
+            events.extend(decoder.push(&body[split..]));
+            events.extend(decoder.finish());
+
+            assert_eq!(events, ["café ☕", "🌱"], "split at byte {}", split);
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use std::env;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    handlers::ai::{client::parse_json_reply, providers::ResponseSchema},
+    models::{
+        debate::{ArgumentVerdict, DebateTurn},
+        turn::{MAX_UNDERSTANDING, MIN_UNDERSTANDING, TurnSignals},
+    },
+};
+
+const BODHI_TURN: &str = "bodhi_turn";
+const DEBATE_TURN: &str = "debate_turn";
+
+/// whether Bodhi answers with structured JSON turns, from `STRUCTURED_TURNS` (on or off).
+/// on by default, the chat's understanding meter is fed by them
+pub fn structured_turns_enabled() -> bool {
+    match env::var("STRUCTURED_TURNS").as_deref() {
+        Ok("on") | Err(_) => true,
+        Ok("off") => false,
+        Ok(other) => {
+            tracing::warn!("Unknown STRUCTURED_TURNS '{}', using 'on'", other);
+            true
+        }
+    }
+}
+
+/// the shape of a structured Bodhi turn; `reply` comes first so it can be streamed
+/// while the rest is still being generated
+pub fn bodhi_turn_schema() -> ResponseSchema {
+    ResponseSchema {
+        name: BODHI_TURN,
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "reply": { "type": "string" },
+                "understanding": { "type": "integer" },
+                "confusions": { "type": "array", "items": { "type": "string" } },
+                "concept": { "type": "string" }
+            },
+            "required": ["reply", "understanding", "confusions", "concept"],
+            "additionalProperties": false
+        }),
+    }
+}
+
+/// the shape of a debate reply: what Bodhi says, the position it defends and what
+/// it made of each argument in the user's latest message
+pub fn debate_turn_schema() -> ResponseSchema {
+    ResponseSchema {
+        name: DEBATE_TURN,
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "reply": { "type": "string" },
+                "stance": { "type": "string" },
+                "arguments": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "argument": { "type": "string" },
+                            "outcome": { "type": "string", "enum": ["rebutted", "accepted", "open"] }
+                        },
+                        "required": ["argument", "outcome"],
+                        "additionalProperties": false
+                    }
+                },
+                "conceded": { "type": "boolean" }
+            },
+            "required": ["reply", "stance", "arguments", "conceded"],
+            "additionalProperties": false
+        }),
+    }
+}
+
+/// a reply split into the text shown to the user and what came with it
+pub struct DecodedReply {
+    pub content: String,
+    pub parsed: bool, // the whole structured reply was valid
+    pub signals: Option<TurnSignals>,
+    pub debate: Option<DebateTurn>,
+}
+
+/// decodes a reply generated for the response schema named `schema`, if any
+pub fn decode_reply(schema: Option<&str>, raw: String) -> DecodedReply {
+    let mut decoded = DecodedReply {
+        content: raw,
+        parsed: false,
+        signals: None,
+        debate: None,
+    };
+
+    match schema {
+        Some(BODHI_TURN) => {
+            let (content, signals) = decode_turn(&decoded.content);
+            decoded.parsed = signals.is_some();
+            decoded.content = content;
+            decoded.signals = signals;
+        }
+        Some(DEBATE_TURN) => {
+            let (content, debate) = decode_debate_turn(&decoded.content);
+            decoded.parsed = debate.is_some();
+            decoded.content = content;
+            decoded.debate = debate;
+        }
+        _ => {}
+    }
+
+    decoded
+}
+
+#[derive(Deserialize)]
+struct BodhiTurn {
+    reply: String,
+    #[serde(default)]
+    understanding: i64,
+    #[serde(default)]
+    confusions: Vec<String>,
+    #[serde(default)]
+    concept: String,
+}
+
+/// splits a structured reply into the text shown to the teacher and its signals.
+/// a reply that isn't valid JSON, e.g. one cut off mid-stream, keeps whatever reply
+/// text can be recovered and has no signals
+pub fn decode_turn(raw: &str) -> (String, Option<TurnSignals>) {
+    match parse_json_reply::<BodhiTurn>(raw) {
+        Ok(turn) => {
+            let concept = turn.concept.trim();
+            let signals = TurnSignals {
+                understanding: turn
+                    .understanding
+                    .clamp(MIN_UNDERSTANDING, MAX_UNDERSTANDING),
+                confusions: turn
+                    .confusions
+                    .into_iter()
+                    .map(|c| c.trim().to_string())
+                    .filter(|c| !c.is_empty())
+                    .collect(),
+                concept: (!concept.is_empty()).then(|| concept.to_string()),
+            };
+            (turn.reply, Some(signals))
+        }
+        Err(e) => {
+            tracing::warn!("Structured reply could not be parsed: {}", e);
+            (recover_reply(raw), None)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DebateReply {
+    reply: String,
+    #[serde(default)]
+    stance: String,
+    #[serde(default)]
+    arguments: Vec<ArgumentVerdict>,
+    #[serde(default)]
+    conceded: bool,
+}
+
+/// splits a debate reply into its text and what Bodhi made of the user's arguments
+pub fn decode_debate_turn(raw: &str) -> (String, Option<DebateTurn>) {
+    match parse_json_reply::<DebateReply>(raw) {
+        Ok(turn) => {
+            let debate = DebateTurn {
+                stance: turn.stance.trim().to_string(),
+                arguments: turn
+                    .arguments
+                    .into_iter()
+                    .map(|mut verdict| {
+                        verdict.argument = verdict.argument.trim().to_string();
+                        verdict
+                    })
+                    .filter(|verdict| !verdict.argument.is_empty())
+                    .collect(),
+                conceded: turn.conceded,
+            };
+            (turn.reply, Some(debate))
+        }
+        Err(e) => {
+            tracing::warn!("Debate reply could not be parsed: {}", e);
+            (recover_reply(raw), None)
+        }
+    }
+}
+
+// whatever reply text can be recovered from a structured reply that isn't valid JSON
+fn recover_reply(raw: &str) -> String {
+    let mut extractor = ReplyExtractor::new();
+    let reply = extractor.push(raw);
+    if reply.trim().is_empty() {
+        raw.to_string()
+    } else {
+        reply
+    }
+}
+
+/// pulls the `reply` string out of a structured turn while it is still streaming in,
+/// so the teacher sees text instead of JSON
+#[derive(Default)]
+pub struct ReplyExtractor {
+    raw: String,
+    /// byte offset in `raw` of the next undecoded character of the reply
+    cursor: Option<usize>,
+    done: bool,
+}
+
+impl ReplyExtractor {
+    pub fn new() -> Self {
+        ReplyExtractor::default()
+    }
+
+    /// everything received so far
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// adds the next chunk and returns the reply text it completed, if any
+    pub fn push(&mut self, chunk: &str) -> String {
+        self.raw.push_str(chunk);
+        if self.done {
+            return String::new();
+        }
+
+        if self.cursor.is_none() {
+            self.cursor = self.find_reply_start();
+        }
+
+        match self.cursor {
+            Some(cursor) => self.decode_from(cursor),
+            None => String::new(),
+        }
+    }
+
+    // just past the opening quote of the value of `"reply"`
+    fn find_reply_start(&self) -> Option<usize> {
+        let key = self.raw.find("\"reply\"")? + "\"reply\"".len();
+        let rest = self.raw[key..].trim_start();
+        let rest = rest.strip_prefix(':')?.trim_start();
+        rest.strip_prefix('"')?;
+
+        Some(self.raw.len() - rest.len() + 1)
+    }
+
+    // decodes as far as the received text allows, an escape split across chunks waits
+    fn decode_from(&mut self, cursor: usize) -> String {
+        let mut out = String::new();
+        let rest = &self.raw[cursor..];
+        let mut i = 0;
+
+        while let Some(c) = rest[i..].chars().next() {
+            match c {
+                '"' => {
+                    self.done = true;
+                    i += 1;
+                    break;
+                }
+                '\\' => match decode_escape(&rest[i..]) {
+                    Some((decoded, len)) => {
+                        out.push(decoded);
+                        i += len;
+                    }
+                    None => break,
+                },
+                c => {
+                    out.push(c);
+                    i += c.len_utf8();
+                }
+            }
+        }
+
+        self.cursor = Some(cursor + i);
+        out
+    }
+}
+
+/// decodes the JSON escape at the start of `text`, returning it and its length in bytes,
+/// or `None` when it isn't complete yet. a malformed `\u` escape decodes to U+FFFD so
+/// the rest of the reply isn't held back waiting for it
+fn decode_escape(text: &str) -> Option<(char, usize)> {
+    let escaped = text[1..].chars().next()?;
+    let decoded = match escaped {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        'b' => '\u{8}',
+        'f' => '\u{c}',
+        'u' => {
+            let high = match hex4(&text[2..])? {
+                Ok(high) => high,
+                Err(digits) => return Some(('\u{FFFD}', 2 + digits)),
+            };
+            if !(0xD800..0xDC00).contains(&high) {
+                return Some((char::from_u32(high).unwrap_or('\u{FFFD}'), 6));
+            }
+
+            // a surrogate pair, the low half follows as a second \u escape. without one the
+            // high half is replaced and whatever follows is decoded on its own
+            let next = &text[6..];
+            if next.len() < 2 && "\\u".starts_with(next) {
+                return None;
+            }
+            let low = match next.strip_prefix("\\u").map(hex4) {
+                Some(None) => return None,
+                Some(Some(Ok(low))) if (0xDC00..0xE000).contains(&low) => low,
+                _ => return Some(('\u{FFFD}', 6)),
+            };
+            let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+            return Some((char::from_u32(code).unwrap_or('\u{FFFD}'), 12));
+        }
+        other => other, // \" \\ \/
+    };
+
+    Some((decoded, 1 + escaped.len_utf8()))
+}
+
+/// the four hex digits of a `\u` escape, `None` while more may still arrive, or the
+/// number of digits read before one that isn't hex
+fn hex4(text: &str) -> Option<Result<u32, usize>> {
+    let mut code = 0;
+    for (i, c) in text.chars().take(4).enumerate() {
+        match c.to_digit(16) {
+            Some(digit) => code = code * 16 + digit,
+            None => return Some(Err(i)),
+        }
+    }
+
+    if text.len() < 4 {
+        return None;
+    }
+    Some(Ok(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(chunks: &[&str]) -> String {
+        let mut extractor = ReplyExtractor::new();
+        chunks.iter().map(|chunk| extractor.push(chunk)).collect()
+    }
+
+    #[test]
+    fn waits_for_an_escape_split_across_chunks() {
+        assert_eq!(
+            extract(&[r#"{"reply": "caf\u00"#, r#"e9 \ud83d"#, r#"\ude00"}"#]),
+            "café 😀"
+        );
+    }
+
+    #[test]
+    fn replaces_a_malformed_unicode_escape() {
+        assert_eq!(extract(&[r#"{"reply": "a\u12x4b"}"#]), "a\u{FFFD}x4b");
+        assert_eq!(extract(&[r#"{"reply": "a\u"}"#]), "a\u{FFFD}");
+    }
+
+    #[test]
+    fn replaces_a_lone_surrogate() {
+        assert_eq!(extract(&[r#"{"reply": "a\ud83d b"}"#]), "a\u{FFFD} b");
+        assert_eq!(extract(&[r#"{"reply": "a\ud83dA"}"#]), "a\u{FFFD}A");
+        assert_eq!(extract(&[r#"{"reply": "a\ude00"}"#]), "a\u{FFFD}");
+    }
+}
@@ -0,0 +1,42 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    clarity,
+    database::{messages::list_messages_for_session, sessions::get_session},
+};
+
+/// how the reading level, sentence length and jargon of the teacher's messages
+/// developed over the session
+pub async fn get_clarity_handler(
+    State(pool): State<SqlitePool>,
+    Path(session_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match get_session(&pool, session_id).await {
+        Ok(_) => {}
+        Err(sqlx::Error::RowNotFound) => {
+            return (StatusCode::NOT_FOUND, "Session not found").into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to get session for clarity: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    match list_messages_for_session(&pool, session_id).await {
+        Ok(messages) => (StatusCode::OK, Json(clarity::trend(&messages))).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to get messages for clarity: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to retrieve clarity",
+            )
+                .into_response()
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    database::{concepts::get_coverage, sessions::get_session},
+    handlers::ai::{concepts::ensure_concepts, error::AiError, providers::SharedLlmProvider},
+    models::concept::CoverageReport,
+};
+
+/// which of the material's key concepts the teacher has taught, partially taught or skipped
+pub async fn get_coverage_handler(
+    State(pool): State<SqlitePool>,
+    State(llm): State<SharedLlmProvider>,
+    Path(session_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let session = match get_session(&pool, session_id).await {
+        Ok(s) => s,
+        Err(sqlx::Error::RowNotFound) => {
+            return (StatusCode::NOT_FOUND, "Session not found").into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to get session for coverage: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    // sessions whose extraction failed or predates concepts get them now
+    if let Err(e) = ensure_concepts(&pool, llm.as_ref(), &session).await {
+        tracing::error!(
+            "Failed to extract concepts for session {}: {}",
+            session_id,
+            e
+        );
+        return match e.downcast::<AiError>() {
+            Ok(ai_error) => ai_error.into_response(),
+            Err(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to extract concepts",
+            )
+                .into_response(),
+        };
+    }
+
+    match get_coverage(&pool, session_id).await {
+        Ok(concepts) => (StatusCode::OK, Json(CoverageReport::new(concepts))).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to get coverage: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to retrieve coverage",
+            )
+                .into_response()
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        evaluations::{create_evaluation, get_latest_evaluation},
+        messages::list_messages_for_session,
+        sessions::get_session,
+    },
+    handlers::ai::{error::AiError, evaluation::evaluate_lesson, providers::SharedLlmProvider},
+    models::message::MessageRole,
+    retrieval::Retriever,
+};
+
+/// grades the lesson so far and stores the result
+pub async fn create_evaluation_handler(
+    State(pool): State<SqlitePool>,
+    State(llm): State<SharedLlmProvider>,
+    State(retriever): State<Retriever>,
+    Path(session_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let session = match get_session(&pool, session_id).await {
+        Ok(s) => s,
+        Err(sqlx::Error::RowNotFound) => {
+            return (StatusCode::NOT_FOUND, "Session not found").into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to get session for evaluation: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let history = match list_messages_for_session(&pool, session_id).await {
+        Ok(h) => h,
+        Err(e) => {
+            tracing::error!("Failed to get history for evaluation: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    // without a single explanation there is nothing to grade
+    if !history
+        .iter()
+        .any(|msg| matches!(msg.role, MessageRole::User))
+    {
+        return (StatusCode::BAD_REQUEST, "Nothing to evaluate yet").into_response();
+    }
+
+    let draft = match evaluate_lesson(&pool, llm.as_ref(), &retriever, &session, history).await {
+        Ok(draft) => draft,
+        Err(e) => {
+            tracing::error!("{} evaluation failed: {}", llm.model(), e);
+            // model failures tell the frontend what went wrong, anything else is on us
+            return match e.downcast::<AiError>() {
+                Ok(ai_error) => ai_error.into_response(),
+                Err(_) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to evaluate the lesson",
+                )
+                    .into_response(),
+            };
+        }
+    };
+
+    match create_evaluation(&pool, session_id, draft, llm.model()).await {
+        Ok(evaluation) => (StatusCode::CREATED, Json(evaluation)).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to save evaluation: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// the most recent evaluation of the session
+pub async fn get_evaluation_handler(
+    State(pool): State<SqlitePool>,
+    Path(session_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match get_latest_evaluation(&pool, session_id).await {
+        Ok(evaluation) => (StatusCode::OK, Json(evaluation)).into_response(),
+        Err(sqlx::Error::RowNotFound) => {
+            (StatusCode::NOT_FOUND, "No evaluation yet").into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to get evaluation: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to retrieve evaluation",
+            )
+                .into_response()
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    database::{ingestion_jobs::get_job, sessions::get_session},
+    models::ingestion::IngestionProgress,
+};
+
+/// the session's status and how far its upload got, polled while it's processing
+pub async fn get_ingestion_handler(
+    State(pool): State<SqlitePool>,
+    Path(session_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let session = match get_session(&pool, session_id).await {
+        Ok(session) => session,
+        Err(sqlx::Error::RowNotFound) => {
+            return (StatusCode::NOT_FOUND, "Session not found").into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to get session for ingestion progress: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let job = match get_job(&pool, session_id).await {
+        Ok(job) => Some(job),
+        Err(sqlx::Error::RowNotFound) => None,
+        Err(e) => {
+            tracing::error!("Failed to get ingestion job: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let progress = IngestionProgress {
+        status: session.status,
+        job,
+    };
+    (StatusCode::OK, Json(progress)).into_response()
+}
@@ -0,0 +1,400 @@
+use axum::{
+    body::Bytes,
+    extract::{Multipart, Path, State, multipart::Field},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        materials::{add_material, delete_material, list_materials},
+        sessions::{get_session, update_material},
+    },
+    handlers::{
+        ai::{client::assemble_material, providers::SharedLlmProvider},
+        session_handlers::index_material,
+    },
+    ingestion::{
+        IngestError, cleanup::Cleanup, detect_format, extract_blocking, extract_material,
+        extract_upload, pages::PageSelection,
+    },
+    models::{
+        ingestion::CreateIngestionJob,
+        material::{CreateMaterial, MaterialPreview},
+        session::Session,
+    },
+    retrieval::Retriever,
+};
+
+/// an uploaded file field, read but not extracted yet since the page selection may come
+/// later in the form
+pub struct UploadedFile {
+    name: Option<String>,
+    content_type: Option<String>,
+    bytes: Bytes,
+}
+
+impl UploadedFile {
+    /// reads a file field, failures come back as the response to send
+    pub async fn read(field: Field<'_>) -> Result<Self, Response> {
+        // the name and type go with the field once its bytes are read
+        let name = field.file_name().map(str::to_string);
+        let content_type = field.content_type().map(str::to_string);
+        match field.bytes().await {
+            Ok(bytes) => Ok(UploadedFile {
+                name,
+                content_type,
+                bytes,
+            }),
+            // e.g. a file over the upload limit
+            Err(e) => {
+                tracing::error!("Failed to read uploaded file: {}", e);
+                Err((e.status(), e.body_text()).into_response())
+            }
+        }
+    }
+
+    /// the file as an upload to extract, keeping only the `selection` of a PDF's pages.
+    /// types we have no extractor for are turned away here, damaged files only show
+    /// when they're extracted
+    pub fn into_upload(
+        self,
+        selection: Option<PageSelection>,
+    ) -> Result<CreateIngestionJob, IngestError> {
+        detect_format(
+            self.name.as_deref(),
+            self.content_type.as_deref(),
+            &self.bytes,
+        )
+        .inspect_err(|e| tracing::error!("Rejected upload: {}", e))?;
+
+        Ok(CreateIngestionJob {
+            file_name: self.name,
+            content_type: self.content_type,
+            pages: selection,
+            data: self.bytes.to_vec(),
+        })
+    }
+}
+
+/// the next field of an upload form, failures like a body over the upload limit come
+/// back as the response to send
+pub async fn next_field(multipart: &mut Multipart) -> Result<Option<Field<'_>>, Response> {
+    multipart.next_field().await.map_err(|e| {
+        tracing::error!("Failed to read upload form: {}", e);
+        (e.status(), e.body_text()).into_response()
+    })
+}
+
+/// the `pages` field of an upload, an empty one selects every page
+pub fn parse_page_selection(text: &str) -> Result<Option<PageSelection>, String> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    text.parse().map(Some)
+}
+
+// the session the material routes are nested under, or the response for a missing one
+async fn find_session(pool: &SqlitePool, session_id: Uuid) -> Result<Session, Response> {
+    match get_session(pool, session_id).await {
+        Ok(session) => Ok(session),
+        Err(sqlx::Error::RowNotFound) => {
+            Err((StatusCode::NOT_FOUND, "Session not found").into_response())
+        }
+        Err(e) => {
+            tracing::error!("Failed to get session for materials: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+// the session for a change of its materials, which waits until its upload is processed
+async fn find_editable_session(pool: &SqlitePool, session_id: Uuid) -> Result<Session, Response> {
+    let session = find_session(pool, session_id).await?;
+    if !session.status.has_material() {
+        let reason = session.status.closed_reason().unwrap_or_default();
+        return Err((StatusCode::CONFLICT, reason).into_response());
+    }
+    Ok(session)
+}
+
+/// assembles the session's text from its materials again and re-indexes it
+async fn reassemble_material(
+    pool: &SqlitePool,
+    llm: &SharedLlmProvider,
+    retriever: &Retriever,
+    session_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    let materials = list_materials(pool, session_id).await?;
+    let assembled = assemble_material(&materials);
+    let session = update_material(
+        pool,
+        session_id,
+        &assembled.text,
+        &assembled.sections,
+        &assembled.pages,
+    )
+    .await?;
+
+    index_material(pool, llm, retriever, &session).await;
+    Ok(())
+}
+
+/// the session's materials in the order Bodhi reads them
+pub async fn list_materials_handler(
+    State(pool): State<SqlitePool>,
+    Path(session_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if let Err(response) = find_session(&pool, session_id).await {
+        return response;
+    }
+
+    match list_materials(&pool, session_id).await {
+        Ok(materials) => (StatusCode::OK, Json(materials)).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to list materials: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to retrieve materials",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// adds a `file`, or pasted `text` with an optional `name`, after the session's other materials
+pub async fn add_material_handler(
+    State(pool): State<SqlitePool>,
+    State(llm): State<SharedLlmProvider>,
+    State(retriever): State<Retriever>,
+    Path(session_id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    if let Err(response) = find_editable_session(&pool, session_id).await {
+        return response;
+    }
+
+    let mut file: Option<UploadedFile> = None;
+    let mut text: Option<String> = None;
+    let mut name: Option<String> = None;
+    let mut selection: Option<PageSelection> = None;
+
+    loop {
+        let field = match next_field(&mut multipart).await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(response) => return response,
+        };
+        match field.name() {
+            Some("file") => match UploadedFile::read(field).await {
+                Ok(uploaded) => file = Some(uploaded),
+                Err(response) => return response,
+            },
+            Some("text") => {
+                if let Ok(data) = field.text().await {
+                    text = Some(data).filter(|text| !text.trim().is_empty());
+                }
+            }
+            Some("name") => {
+                if let Ok(data) = field.text().await {
+                    name = Some(data.trim().to_string()).filter(|name| !name.is_empty());
+                }
+            }
+            Some("pages") => {
+                if let Ok(data) = field.text().await {
+                    match parse_page_selection(&data) {
+                        Ok(parsed) => selection = parsed,
+                        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+                    }
+                }
+            }
+            _ => {} // ignore other fields
+        }
+    }
+
+    let material = match (file, text) {
+        (Some(file), _) => {
+            let extracted = match file.into_upload(selection) {
+                Ok(upload) => extract_blocking(move || extract_upload(&upload)).await,
+                Err(e) => Err(e),
+            };
+            match extracted {
+                Ok(material) => material,
+                Err(e) => return e.into_response(),
+            }
+        }
+        // only pasted text is named by the teacher, files keep their own name
+        (None, Some(text)) => {
+            let mut material = CreateMaterial::pasted(text);
+            if let Some(name) = name {
+                material.source_name = name;
+            }
+            material
+        }
+        (None, None) => {
+            return (StatusCode::BAD_REQUEST, "Missing 'file' or 'text'").into_response();
+        }
+    };
+
+    let material = match add_material(&pool, session_id, &material).await {
+        Ok(material) => material,
+        Err(e) => {
+            tracing::error!("Failed to add material: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to add material").into_response();
+        }
+    };
+
+    match reassemble_material(&pool, &llm, &retriever, session_id).await {
+        Ok(()) => (StatusCode::CREATED, Json(material)).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to update session material: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update session material",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// removes a material, a session keeps at least one
+pub async fn delete_material_handler(
+    State(pool): State<SqlitePool>,
+    State(llm): State<SharedLlmProvider>,
+    State(retriever): State<Retriever>,
+    Path((session_id, material_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    if let Err(response) = find_editable_session(&pool, session_id).await {
+        return response;
+    }
+
+    let materials = match list_materials(&pool, session_id).await {
+        Ok(materials) => materials,
+        Err(e) => {
+            tracing::error!("Failed to list materials: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if !materials.iter().any(|material| material.id == material_id) {
+        return (StatusCode::NOT_FOUND, "Material not found").into_response();
+    }
+    if materials.len() == 1 {
+        return (
+            StatusCode::CONFLICT,
+            "A session needs at least one material",
+        )
+            .into_response();
+    }
+
+    match delete_material(&pool, session_id, material_id).await {
+        Ok(()) => {}
+        Err(sqlx::Error::RowNotFound) => {
+            return (StatusCode::NOT_FOUND, "Material not found").into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete material: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to delete material",
+            )
+                .into_response();
+        }
+    }
+
+    match reassemble_material(&pool, &llm, &retriever, session_id).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => {
+            tracing::error!("Failed to update session material: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update session material",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// extracts a `file` as is and cleaned up, to see what the cleanup does to it before
+/// uploading. `cleanup` and `captions` try other settings than the server's, in the form
+/// of `TEXT_CLEANUP` and `CAPTIONS`
+pub async fn preview_material_handler(mut multipart: Multipart) -> impl IntoResponse {
+    let mut file: Option<UploadedFile> = None;
+    let mut selection: Option<PageSelection> = None;
+    let mut cleanup = Cleanup::from_env();
+
+    loop {
+        let field = match next_field(&mut multipart).await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(response) => return response,
+        };
+        match field.name() {
+            Some("file") => match UploadedFile::read(field).await {
+                Ok(uploaded) => file = Some(uploaded),
+                Err(response) => return response,
+            },
+            Some("pages") => {
+                if let Ok(data) = field.text().await {
+                    match parse_page_selection(&data) {
+                        Ok(parsed) => selection = parsed,
+                        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+                    }
+                }
+            }
+            Some("cleanup") => {
+                if let Ok(data) = field.text().await {
+                    match Cleanup::parse_steps(&data) {
+                        Ok(steps) => cleanup.steps = steps,
+                        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+                    }
+                }
+            }
+            Some("captions") => {
+                if let Ok(data) = field.text().await {
+                    match data.trim().parse() {
+                        Ok(mode) => cleanup.captions = mode,
+                        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+                    }
+                }
+            }
+            _ => {} // ignore other fields
+        }
+    }
+
+    let Some(file) = file else {
+        return (StatusCode::BAD_REQUEST, "Missing 'file'").into_response();
+    };
+    let upload = match file.into_upload(selection) {
+        Ok(upload) => upload,
+        Err(e) => return e.into_response(),
+    };
+
+    // the file is extracted twice, without and with the cleanup, to compare them
+    let extracted = extract_blocking(move || {
+        let extract = |cleanup: &Cleanup| {
+            extract_material(
+                upload.file_name.as_deref(),
+                upload.content_type.as_deref(),
+                &upload.data,
+                upload.pages.as_ref(),
+                cleanup,
+            )
+        };
+        Ok((extract(&Cleanup::none())?, extract(&cleanup)?))
+    })
+    .await;
+    match extracted {
+        Ok((before, after)) => {
+            let preview = MaterialPreview {
+                format: after.format,
+                before: before.text,
+                after: after.text,
+                cleanup: after.cleanup,
+            };
+            (StatusCode::OK, Json(preview)).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
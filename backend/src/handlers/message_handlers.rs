@@ -1,94 +1,169 @@
 use axum::{
     Json,
     extract::{Path, State},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
 use reqwest::StatusCode;
 use sqlx::SqlitePool;
+use std::time::Instant;
 use uuid::Uuid;
 
 use crate::{
+    citations::cite_reply,
+    clarity,
+    database::{
+        messages::{create_exchange, list_messages_for_session},
+        personas::get_persona,
+        sessions::get_session,
+    },
     handlers::ai::{
-        client::call_gemini_api,
-        model::{Content, Part},
+        clarity::ask_about_jargon,
+        client::{message_usage, prepare_bodhi_request},
+        concepts::{record_coverage, start_coverage},
+        error::AiError,
+        misconceptions::{record_check, start_check},
+        providers::SharedLlmProvider,
+        quiz::{quiz_turn, save_quiz_exchange},
+        turn::decode_reply,
     },
-    database::sessions::get_session,
     models::{
         message::{CreateMessage, MessageRole},
-        session::Session,
+        session::{Session, SessionMode},
     },
+    retrieval::Retriever,
 };
 
 pub async fn create_message_handler(
     State(pool): State<SqlitePool>,
+    State(llm): State<SharedLlmProvider>,
+    State(retriever): State<Retriever>,
     Path(session_id): Path<Uuid>,
-    Json(payload): Json<CreateMessage>,
+    Json(mut payload): Json<CreateMessage>,
 ) -> impl IntoResponse {
-    // save the user's message
-    let user_message =
-        match crate::database::messages::create_message(&pool, session_id, payload).await {
-            Ok(msg) => msg,
-            Err(e) => {
-                tracing::error!("Failed to save user message: {}", e);
-                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-            }
-        };
-
     // fetch the full session context (material + history)
     let session: Session = match get_session(&pool, session_id).await {
         Ok(s) => s,
+        Err(sqlx::Error::RowNotFound) => {
+            return (StatusCode::NOT_FOUND, "Session not found").into_response();
+        }
         Err(e) => {
             tracing::error!("Failed to get session for AI call: {}", e);
             return StatusCode::INTERNAL_SERVER_ERROR.into_response();
         }
     };
 
-    let history =
-        match crate::database::messages::list_messages_for_session(&pool, session_id).await {
-            Ok(h) => h,
-            Err(e) => {
-                tracing::error!("Failed to get history for AI call: {}", e);
-                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-            }
-        };
-
-    // call the Gemini API
-    // convert our internal Message structs to the external AI model structs
-    let conversation_history: Vec<Content> = history
-        .into_iter()
-        .map(|msg| Content {
-            role: match msg.role {
-                MessageRole::User => "user".to_string(),
-                MessageRole::Assistant => "model".to_string(),
-            },
-            parts: vec![Part { text: msg.content }],
-        })
-        .collect();
-
-    let ai_response_text = match call_gemini_api(session.material_text, conversation_history).await
+    // the session only becomes active once the exchange is saved
+    if let Some(reason) = session.status.closed_reason() {
+        return (StatusCode::CONFLICT, reason).into_response();
+    }
+
+    if session.mode == SessionMode::Quiz {
+        return create_quiz_message(&pool, &llm, &session, payload).await;
+    }
+
+    let persona = match get_persona(&pool, &session.persona_id).await {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("Failed to get persona for AI call: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let history = match list_messages_for_session(&pool, session_id).await {
+        Ok(h) => h,
+        Err(e) => {
+            tracing::error!("Failed to get history for AI call: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    // how plainly the teacher wrote, against what they explained before
+    let clarity = clarity::assess(&session.material_text, &history, &payload.content);
+
+    // ask the configured model for Bodhi's reply, the teacher's message is only
+    // saved together with it so a failed call leaves no unanswered message behind
+    let mut request = match prepare_bodhi_request(
+        &pool,
+        llm.as_ref(),
+        &retriever,
+        &session,
+        &persona,
+        history,
+        Some(&payload.content),
+    )
+    .await
     {
-        Ok(text) => text,
+        Ok(request) => request,
         Err(e) => {
-            tracing::error!("Gemini API call failed: {}", e);
+            tracing::error!("Failed to prepare AI request: {}", e);
             return StatusCode::INTERNAL_SERVER_ERROR.into_response();
         }
     };
+    ask_about_jargon(&mut request, &session, &clarity);
+    payload.clarity = Some(clarity);
+
+    // check the teacher's explanation against the material and map it to the
+    // material's concepts while Bodhi thinks
+    let coverage = start_coverage(&pool, &llm, &session, &payload.content);
+    let material_pages = session.material_pages.clone();
+    let check = start_check(
+        &pool,
+        &llm,
+        &retriever,
+        session,
+        &payload.content,
+        &mut request,
+    )
+    .await;
 
-    // save the AI's response
+    let schema = request.response_schema.as_ref().map(|schema| schema.name);
+    let started = Instant::now();
+    let completion = match llm.complete(request).await {
+        Ok(completion) => completion,
+        Err(e) => {
+            tracing::error!("{} call failed: {}", llm.model(), e);
+            return e.into_response();
+        }
+    };
+
+    let usage = message_usage(
+        llm.as_ref(),
+        completion.usage,
+        completion.finish_reason,
+        started,
+    );
+    let reply = decode_reply(schema, completion.text);
     let assistant_payload = CreateMessage {
         role: MessageRole::Assistant,
-        content: ai_response_text,
+        citations: cite_reply(&reply.content, &material_pages),
+        content: reply.content,
+        quiz: None,
+        debate: reply.debate,
+        clarity: None,
+    };
+
+    let (mut user_message, assistant_message) = match create_exchange(
+        &pool,
+        session_id,
+        payload,
+        assistant_payload,
+        false,
+        Some(usage),
+        reply.signals,
+    )
+    .await
+    {
+        Ok(saved) => saved,
+        Err(e) => {
+            tracing::error!("Failed to save exchange: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
     };
 
-    let assistant_message =
-        match crate::database::messages::create_message(&pool, session_id, assistant_payload).await
-        {
-            Ok(msg) => msg,
-            Err(e) => {
-                tracing::error!("Failed to save assistant message: {}", e);
-                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-            }
-        };
+    if let Some(check) = check {
+        record_check(&pool, &mut user_message, check).await;
+    }
+    record_coverage(&pool, user_message.id, coverage).await;
 
     // return both the user's and the assistant's messages
     (
@@ -98,11 +173,52 @@ pub async fn create_message_handler(
         .into_response()
 }
 
+/// grades the user's answer to the question Bodhi asked last and replies with the next one
+async fn create_quiz_message(
+    pool: &SqlitePool,
+    llm: &SharedLlmProvider,
+    session: &Session,
+    mut payload: CreateMessage,
+) -> Response {
+    let turn = match quiz_turn(pool, llm.as_ref(), session, &payload.content).await {
+        Ok(turn) => turn,
+        Err(e) => {
+            tracing::error!("{} quiz turn failed: {}", llm.model(), e);
+            return match e.downcast::<AiError>() {
+                Ok(ai_error) => ai_error.into_response(),
+                Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            };
+        }
+    };
+
+    payload.quiz = turn.answer.clone();
+    let assistant_payload = CreateMessage {
+        role: MessageRole::Assistant,
+        content: turn.reply.clone(),
+        quiz: turn.question.clone(),
+        debate: None,
+        clarity: None,
+        citations: turn.citations.clone(),
+    };
+
+    match save_quiz_exchange(pool, session.id, payload, assistant_payload, &turn).await {
+        Ok((user_message, assistant_message)) => (
+            StatusCode::CREATED,
+            Json(vec![user_message, assistant_message]),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to save quiz exchange: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
 pub async fn list_messages_handler(
     State(pool): State<SqlitePool>,
     Path(session_id): Path<Uuid>,
 ) -> impl IntoResponse {
-    match crate::database::messages::list_messages_for_session(&pool, session_id).await {
+    match list_messages_for_session(&pool, session_id).await {
         Ok(messages) => (StatusCode::OK, Json(messages)).into_response(),
         Err(e) => {
             tracing::error!("Failed to list messages: {}", e);
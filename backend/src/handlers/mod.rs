@@ -1,4 +1,12 @@
 pub mod ai;
+pub mod clarity_handlers;
+pub mod coverage_handlers;
+pub mod evaluation_handlers;
+pub mod ingestion_handlers;
+pub mod material_handlers;
 pub mod message_handlers;
+pub mod persona_handlers;
 pub mod session_handlers;
 pub mod stream_handlers;
+pub mod summary_handlers;
+pub mod usage_handlers;
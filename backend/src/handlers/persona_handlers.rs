@@ -0,0 +1,20 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use sqlx::SqlitePool;
+
+pub async fn list_personas_handler(State(pool): State<SqlitePool>) -> impl IntoResponse {
+    match crate::database::personas::list_personas(&pool).await {
+        Ok(personas) => (StatusCode::OK, Json(personas)).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to list personas: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to retrieve personas",
+            )
+                .into_response()
+        }
+    }
+}
@@ -1,18 +1,107 @@
-use crate::{database::sessions::create_session, models::session::CreateSession};
+use crate::{
+    database::{
+        personas::get_persona,
+        sessions::{StatusError, create_session, update_status},
+    },
+    handlers::{
+        ai::{concepts::index_concepts, providers::SharedLlmProvider},
+        material_handlers::{UploadedFile, next_field, parse_page_selection},
+    },
+    ingestion::{pages::PageSelection, worker::IngestionQueue},
+    models::{
+        generation::GenerationSettings,
+        session::{CreateSession, Session, SessionMode, SessionStatus, UpdateSessionStatus},
+    },
+    retrieval::Retriever,
+};
 use axum::{
     extract::{Multipart, Path, State},
     http::StatusCode,
-    response::{IntoResponse, Json},
+    response::{IntoResponse, Json, Response},
 };
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
+/// rejects a session that asks for a persona we don't know about
+async fn validate_persona(pool: &SqlitePool, persona_id: Option<&str>) -> Result<(), Response> {
+    let Some(persona_id) = persona_id else {
+        return Ok(());
+    };
+
+    match get_persona(pool, persona_id).await {
+        Ok(_) => Ok(()),
+        Err(sqlx::Error::RowNotFound) => {
+            Err((StatusCode::BAD_REQUEST, "Unknown persona").into_response())
+        }
+        Err(e) => {
+            tracing::error!("Failed to get persona: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+/// chunks and indexes the session's material and, unless it's a quiz, starts
+/// extracting its key concepts in the background. failures aren't
+/// fatal: both are done again the first time they're needed.
+pub async fn index_material(
+    pool: &SqlitePool,
+    llm: &SharedLlmProvider,
+    retriever: &Retriever,
+    session: &Session,
+) {
+    match retriever
+        .index(pool, session.id, &session.material_text)
+        .await
+    {
+        Ok(count) => tracing::debug!("Indexed {} chunks for session {}", count, session.id),
+        Err(e) => tracing::warn!("Failed to index material for session {}: {}", session.id, e),
+    }
+
+    if session.mode == SessionMode::Quiz {
+        return;
+    }
+
+    let (pool, llm, session) = (pool.clone(), llm.clone(), session.clone());
+    tokio::spawn(async move {
+        match index_concepts(&pool, llm.as_ref(), &session).await {
+            Ok(concepts) => tracing::debug!(
+                "Extracted {} concepts for session {}",
+                concepts.len(),
+                session.id
+            ),
+            Err(e) => tracing::warn!(
+                "Failed to extract concepts for session {}: {}",
+                session.id,
+                e
+            ),
+        }
+    });
+}
+
+// the response for a status change the session's current status doesn't allow
+fn status_conflict(from: SessionStatus, to: SessionStatus) -> Response {
+    let message = match (from.closed_reason(), to) {
+        (Some(reason), SessionStatus::Active) => reason.to_string(),
+        _ => format!("A session can't go from {} to {}", from, to),
+    };
+    (StatusCode::CONFLICT, message).into_response()
+}
+
 pub async fn create_session_handler(
     State(pool): State<SqlitePool>,
+    State(llm): State<SharedLlmProvider>,
+    State(retriever): State<Retriever>,
     Json(payload): Json<CreateSession>,
 ) -> impl IntoResponse {
+    if let Err(response) = validate_persona(&pool, payload.persona_id.as_deref()).await {
+        return response;
+    }
+
     match create_session(&pool, payload).await {
-        Ok(session) => (StatusCode::CREATED, Json(session)).into_response(),
+        Ok(session) => {
+            index_material(&pool, &llm, &retriever, &session).await;
+            (StatusCode::CREATED, Json(session)).into_response()
+        }
         Err(e) => {
             tracing::error!("Failed to create session: {}", e);
             (
@@ -72,15 +161,85 @@ pub async fn delete_session_handler(
     }
 }
 
+pub async fn update_generation_settings_handler(
+    State(pool): State<SqlitePool>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<GenerationSettings>,
+) -> impl IntoResponse {
+    // an empty object clears the overrides so the app defaults apply again
+    let settings = Some(payload).filter(|settings| *settings != GenerationSettings::default());
+
+    match crate::database::sessions::update_generation_settings(&pool, id, settings).await {
+        Ok(session) => (StatusCode::OK, Json(session)).into_response(),
+        Err(sqlx::Error::RowNotFound) => {
+            (StatusCode::NOT_FOUND, "Session not found").into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to update generation settings: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update generation settings",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// completes, reopens or archives a session; the other statuses follow the processing
+/// of its upload
+pub async fn update_status_handler(
+    State(pool): State<SqlitePool>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateSessionStatus>,
+) -> impl IntoResponse {
+    if !matches!(
+        payload.status,
+        SessionStatus::Active | SessionStatus::Completed | SessionStatus::Archived
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Only 'active', 'completed' and 'archived' can be set",
+        )
+            .into_response();
+    }
+
+    match update_status(&pool, id, payload.status).await {
+        Ok(session) => (StatusCode::OK, Json(session)).into_response(),
+        Err(StatusError::InvalidTransition { from, to }) => status_conflict(from, to),
+        Err(StatusError::Database(sqlx::Error::RowNotFound)) => {
+            (StatusCode::NOT_FOUND, "Session not found").into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to update session status: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update session status",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// stores the upload with a new processing session and leaves the extraction to the
+/// background worker, the session's `/ingestion` route tells how far it got
 pub async fn upload_session_handler(
     State(pool): State<SqlitePool>,
+    State(ingestion): State<IngestionQueue>,
     mut multipart: Multipart,
 ) -> impl IntoResponse {
     let mut topic: Option<String> = None;
-    let mut material_text: Option<String> = None;
+    let mut file: Option<UploadedFile> = None;
+    let mut selection: Option<PageSelection> = None;
+    let mut persona_id: Option<String> = None;
+    let mut mode = SessionMode::default();
 
-    // loop through all fields to find topic and the PDF file
-    while let Ok(Some(field)) = multipart.next_field().await {
+    // loop through all fields to find topic and the material file
+    loop {
+        let field = match next_field(&mut multipart).await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(response) => return response,
+        };
         if let Some(name) = field.name() {
             match name {
                 "topic" => {
@@ -88,18 +247,28 @@ pub async fn upload_session_handler(
                         topic = Some(data);
                     }
                 }
-                "pdf_file" => {
-                    if let Ok(data) = field.bytes().await {
-                        match pdf_extract::extract_text_from_mem(&data) {
-                            Ok(text) => material_text = Some(text),
-                            Err(e) => {
-                                tracing::error!("PDF extraction failed: {}", e);
-                                return (
-                                    StatusCode::UNPROCESSABLE_ENTITY,
-                                    "Invalid or corrupted PDF",
-                                )
-                                    .into_response();
-                            }
+                "persona_id" => {
+                    if let Ok(data) = field.text().await {
+                        persona_id = Some(data).filter(|id| !id.is_empty());
+                    }
+                }
+                "mode" => {
+                    if let Ok(data) = field.text().await {
+                        match data.parse() {
+                            Ok(parsed) => mode = parsed,
+                            Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+                        }
+                    }
+                }
+                "file" | "pdf_file" => match UploadedFile::read(field).await {
+                    Ok(uploaded) => file = Some(uploaded),
+                    Err(response) => return response,
+                },
+                "pages" => {
+                    if let Ok(data) = field.text().await {
+                        match parse_page_selection(&data) {
+                            Ok(parsed) => selection = parsed,
+                            Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
                         }
                     }
                 }
@@ -109,21 +278,37 @@ pub async fn upload_session_handler(
     }
 
     // validate that we have both a topic and extracted text
-    if let (Some(topic), Some(material_text)) = (topic, material_text) {
+    if let (Some(topic), Some(file)) = (topic, file) {
+        if let Err(response) = validate_persona(&pool, persona_id.as_deref()).await {
+            return response;
+        }
+        let upload = match file.into_upload(selection) {
+            Ok(upload) => upload,
+            Err(e) => return e.into_response(),
+        };
+
+        // the material text is filled in once the upload is extracted
         let payload = CreateSession {
             topic,
-            material_text,
+            material_text: String::new(),
+            persona_id,
+            generation_settings: None,
+            mode,
+            source: None,
+            upload: Some(upload),
         };
 
-        // call the existing create_session database function
-        match crate::database::sessions::create_session(&pool, payload).await {
-            Ok(session) => (StatusCode::CREATED, Json(session)).into_response(),
+        match create_session(&pool, payload).await {
+            Ok(session) => {
+                ingestion.enqueue(session.id);
+                (StatusCode::ACCEPTED, Json(session)).into_response()
+            }
             Err(e) => {
                 tracing::error!("Failed to create session from upload: {}", e);
                 StatusCode::INTERNAL_SERVER_ERROR.into_response()
             }
         }
     } else {
-        (StatusCode::BAD_REQUEST, "Missing 'topic' or 'pdf_file'").into_response()
+        (StatusCode::BAD_REQUEST, "Missing 'topic' or 'file'").into_response()
     }
 }
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::sse::{Event, KeepAlive, Sse},
 };
 use futures_util::{
@@ -7,66 +7,61 @@ use futures_util::{
     stream::{self, Stream},
 };
 use sqlx::SqlitePool;
-use std::convert::Infallible;
+use std::{convert::Infallible, time::Instant};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 
 use crate::{
+    citations::cite_reply,
+    clarity,
+    database::{
+        messages, personas,
+        sessions::{self, StatusError},
+    },
     handlers::ai::{
-        client::stream_gemini_api,
-        model::{Content, Part},
+        clarity::ask_about_jargon,
+        client::{message_usage, prepare_bodhi_request},
+        concepts::{CoverageCheck, record_coverage, start_coverage},
+        error::AiError,
+        misconceptions::{MisconceptionCheck, record_check, start_check},
+        providers::{LlmProvider, LlmRequest, SharedLlmProvider, StreamItem},
+        quiz::{quiz_turn, save_quiz_exchange},
+        turn::{ReplyExtractor, decode_reply},
+    },
+    models::{
+        message::{CreateMessage, MessageRole, StreamMessage},
+        session::{Session, SessionMode, SessionStatus},
     },
-    database::{messages, sessions},
-    models::message::MessageRole,
+    retrieval::Retriever,
 };
 
 pub async fn sse_handler(
     State(pool): State<SqlitePool>,
+    State(llm): State<SharedLlmProvider>,
+    State(retriever): State<Retriever>,
     Path(session_id): Path<Uuid>,
+    Query(payload): Query<StreamMessage>,
 ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
-    // fetch the session first and handle the result properly
-    let session_result = sessions::get_session(&pool, session_id).await;
-
-    let stream = match session_result {
-        // if we found the session, proceed to create the AI stream
-        Ok(session) => {
-            let history = messages::list_messages_for_session(&pool, session_id)
-                .await
-                .unwrap_or_else(|_| vec![]);
-
-            let conversation_history: Vec<Content> = history
-                .into_iter()
-                .map(|msg| Content {
-                    role: match msg.role {
-                        MessageRole::User => "user".to_string(),
-                        MessageRole::Assistant => "model".to_string(),
-                    },
-                    parts: vec![Part { text: msg.content }],
-                })
-                .collect();
-
-            let ai_stream = stream_gemini_api(session.material_text, conversation_history);
-
-            // map the AI stream results into SSE Events
-            ai_stream
-                .map(|result| match result {
-                    Ok(text) => Event::default().data(text),
-                    Err(e) => {
-                        tracing::error!("Stream error: {}", e);
-                        Event::default()
-                            .event("error")
-                            .data("An error occurred during the stream.")
-                    }
-                })
-                // add this crucial line to wrap the Event in a Result
-                .map(Ok)
-                .left_stream()
-        }
+    // fetch the session, its persona and the history first and handle the result properly
+    let stream = match start_reply(pool, llm, retriever, session_id, payload).await {
+        Ok(rx) => ReceiverStream::new(rx).map(Ok).left_stream(),
         // if the session was not found, create a stream with a single error event
         Err(e) => {
-            tracing::error!("Initial SSE connection failed, session not found: {}", e);
-            let error_message = format!("Session with ID {} not found.", session_id);
-            let error_stream =
-                stream::once(async { Ok(Event::default().event("error").data(error_message)) });
+            tracing::error!("Initial SSE connection failed: {}", e);
+            let event = match (e.downcast_ref::<sqlx::Error>(), e.downcast_ref()) {
+                (Some(sqlx::Error::RowNotFound), _) => error_event(
+                    "not_found",
+                    &format!("Session with ID {} not found.", session_id),
+                ),
+                (_, Some(StatusError::InvalidTransition { from, .. })) => error_event(
+                    "session_closed",
+                    from.closed_reason()
+                        .unwrap_or("This session doesn't take messages."),
+                ),
+                _ => error_event("internal", "Failed to start the stream."),
+            };
+            let error_stream = stream::once(async { Ok(event) });
             error_stream.right_stream()
         }
     };
@@ -77,3 +72,294 @@ pub async fn sse_handler(
             .text("keep-alive-text"),
     )
 }
+
+/// starts the task that produces the reply's events, depending on the session's mode
+async fn start_reply(
+    pool: SqlitePool,
+    llm: SharedLlmProvider,
+    retriever: Retriever,
+    session_id: Uuid,
+    payload: StreamMessage,
+) -> Result<mpsc::Receiver<Event>, anyhow::Error> {
+    let session = sessions::get_session(&pool, session_id).await?;
+    // the session only becomes active once the exchange is saved
+    if session.status.closed_reason().is_some() {
+        return Err(StatusError::InvalidTransition {
+            from: session.status,
+            to: SessionStatus::Active,
+        }
+        .into());
+    }
+    let mut user_message = CreateMessage {
+        role: MessageRole::User,
+        content: payload.content,
+        quiz: None,
+        debate: None,
+        clarity: None,
+        citations: Vec::new(),
+    };
+
+    // the relay runs on its own task so it can still save the reply
+    // after the client has gone away
+    let (tx, rx) = mpsc::channel(32);
+    if session.mode == SessionMode::Quiz {
+        tokio::spawn(relay_quiz_turn(pool, llm, session, user_message, tx));
+        return Ok(rx);
+    }
+
+    let mut request =
+        load_bodhi_request(&pool, llm.as_ref(), &retriever, &session, &mut user_message).await?;
+    let coverage = start_coverage(&pool, &llm, &session, &user_message.content);
+    let misconceptions = start_check(
+        &pool,
+        &llm,
+        &retriever,
+        session.clone(),
+        &user_message.content,
+        &mut request,
+    )
+    .await;
+
+    tokio::spawn(relay_reply(
+        pool,
+        llm,
+        session,
+        user_message,
+        request,
+        MessageChecks {
+            misconceptions,
+            coverage,
+        },
+        tx,
+    ));
+
+    Ok(rx)
+}
+
+/// builds Bodhi's request with the teacher's not yet saved message as the last turn
+/// and rates how plainly that message is written
+async fn load_bodhi_request(
+    pool: &SqlitePool,
+    llm: &dyn LlmProvider,
+    retriever: &Retriever,
+    session: &Session,
+    user_message: &mut CreateMessage,
+) -> Result<LlmRequest, anyhow::Error> {
+    let persona = personas::get_persona(pool, &session.persona_id).await?;
+    let history = messages::list_messages_for_session(pool, session.id).await?;
+    let clarity = clarity::assess(&session.material_text, &history, &user_message.content);
+
+    let mut request = prepare_bodhi_request(
+        pool,
+        llm,
+        retriever,
+        session,
+        &persona,
+        history,
+        Some(&user_message.content),
+    )
+    .await?;
+    ask_about_jargon(&mut request, session, &clarity);
+    user_message.clarity = Some(clarity);
+
+    Ok(request)
+}
+
+/// background checks of the teacher's message, stored once it is saved
+struct MessageChecks {
+    misconceptions: Option<MisconceptionCheck>,
+    coverage: CoverageCheck,
+}
+
+/// forwards the model stream to the client as SSE events and, once it ends,
+/// saves the teacher's message together with whatever Bodhi replied, the pages
+/// it cites and the results of the `checks`. the stream always closes with a
+/// single `done` or `error` event, sent after the exchange is saved.
+async fn relay_reply(
+    pool: SqlitePool,
+    llm: SharedLlmProvider,
+    session: Session,
+    user_message: CreateMessage,
+    request: LlmRequest,
+    checks: MessageChecks,
+    tx: mpsc::Sender<Event>,
+) {
+    // structured replies stream as JSON, only their reply text goes to the client
+    let schema = request.response_schema.as_ref().map(|schema| schema.name);
+    let mut extractor = schema.is_some().then(ReplyExtractor::new);
+    let started = Instant::now();
+    let mut ai_stream = llm.stream(request);
+    let mut reply = String::new();
+    let mut usage = None;
+    let mut finish_reason = None;
+    let mut failure = None;
+
+    while let Some(result) = ai_stream.next().await {
+        // map the AI stream results into SSE Events
+        let event = match result {
+            Ok(StreamItem::Text(text)) => {
+                let text = match extractor.as_mut() {
+                    Some(extractor) => extractor.push(&text),
+                    None => text,
+                };
+                if text.is_empty() {
+                    continue;
+                }
+                reply.push_str(&text);
+                Event::default().data(text)
+            }
+            Ok(StreamItem::Usage(latest)) => {
+                usage = Some(latest);
+                continue;
+            }
+            // keep reading, some APIs send the usage after the finish reason
+            Ok(StreamItem::Finished { reason }) => {
+                tracing::debug!("Stream finished: {}", reason);
+                finish_reason = Some(reason);
+                continue;
+            }
+            Err(e) => {
+                tracing::error!("{} stream failed: {}", llm.model(), e);
+                // the stream ends after an error, whatever arrived so far is kept as partial
+                failure = Some(e);
+                break;
+            }
+        };
+
+        if tx.send(event).await.is_err() {
+            tracing::info!("Client disconnected mid-stream for session {}", session.id);
+            break;
+        }
+    }
+
+    let (signals, debate) = match &extractor {
+        Some(extractor) => {
+            let decoded = decode_reply(schema, extractor.raw().to_string());
+            // the whole reply is authoritative once it parsed
+            if decoded.parsed {
+                reply = decoded.content;
+            }
+            (decoded.signals, decoded.debate)
+        }
+        None => (None, None),
+    };
+
+    // nothing worth keeping, let the teacher send the message again
+    if reply.trim().is_empty() {
+        if let Some(e) = failure {
+            let _ = tx
+                .send(
+                    Event::default()
+                        .event("error")
+                        .data(e.to_json().to_string()),
+                )
+                .await;
+        }
+        return;
+    }
+
+    let assistant_message = CreateMessage {
+        role: MessageRole::Assistant,
+        citations: cite_reply(&reply, &session.material_pages),
+        content: reply,
+        quiz: None,
+        debate,
+        clarity: None,
+    };
+    let is_partial = finish_reason.is_none();
+    let usage = message_usage(llm.as_ref(), usage, finish_reason, started);
+
+    match messages::create_exchange(
+        &pool,
+        session.id,
+        user_message,
+        assistant_message,
+        is_partial,
+        Some(usage),
+        signals,
+    )
+    .await
+    {
+        Ok((mut user, assistant)) => {
+            if let Some(check) = checks.misconceptions {
+                record_check(&pool, &mut user, check).await;
+            }
+            record_coverage(&pool, user.id, checks.coverage).await;
+
+            // tell the client which messages to reconcile its list with, the client
+            // closes the stream on the first `done` or `error` so only one is sent
+            let saved = serde_json::to_value([user, assistant]).unwrap_or_default();
+            let event = match failure {
+                Some(e) => {
+                    let mut data = e.to_json();
+                    data["messages"] = saved;
+                    Event::default().event("error").data(data.to_string())
+                }
+                None => Event::default().event("done").data(saved.to_string()),
+            };
+            let _ = tx.send(event).await;
+        }
+        Err(e) => {
+            tracing::error!("Failed to save streamed exchange: {}", e);
+            let _ = tx
+                .send(error_event("internal", "Failed to save the conversation."))
+                .await;
+        }
+    }
+}
+
+/// grades a quiz answer and sends the reply with the next question as a single
+/// chunk, there is nothing to stream while the answer is graded
+async fn relay_quiz_turn(
+    pool: SqlitePool,
+    llm: SharedLlmProvider,
+    session: Session,
+    mut user_message: CreateMessage,
+    tx: mpsc::Sender<Event>,
+) {
+    let turn = match quiz_turn(&pool, llm.as_ref(), &session, &user_message.content).await {
+        Ok(turn) => turn,
+        Err(e) => {
+            tracing::error!("{} quiz turn failed: {}", llm.model(), e);
+            let event = match e.downcast_ref::<AiError>() {
+                Some(ai_error) => Event::default()
+                    .event("error")
+                    .data(ai_error.to_json().to_string()),
+                None => error_event("internal", "Failed to grade the answer."),
+            };
+            let _ = tx.send(event).await;
+            return;
+        }
+    };
+
+    let _ = tx.send(Event::default().data(turn.reply.clone())).await;
+
+    user_message.quiz = turn.answer.clone();
+    let assistant_message = CreateMessage {
+        role: MessageRole::Assistant,
+        content: turn.reply.clone(),
+        quiz: turn.question.clone(),
+        debate: None,
+        clarity: None,
+        citations: turn.citations.clone(),
+    };
+
+    match save_quiz_exchange(&pool, session.id, user_message, assistant_message, &turn).await {
+        Ok((user, assistant)) => {
+            let data = serde_json::to_string(&[user, assistant]).unwrap_or_default();
+            let _ = tx.send(Event::default().event("done").data(data)).await;
+        }
+        Err(e) => {
+            tracing::error!("Failed to save quiz exchange: {}", e);
+            let _ = tx
+                .send(error_event("internal", "Failed to save the conversation."))
+                .await;
+        }
+    }
+}
+
+/// an `error` event with the same JSON shape as `AiError` responses
+fn error_event(kind: &str, message: &str) -> Event {
+    let data = serde_json::json!({ "error": kind, "message": message });
+    Event::default().event("error").data(data.to_string())
+}
@@ -0,0 +1,88 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        lesson_summaries::{get_lesson_summary, upsert_lesson_summary},
+        messages::list_messages_for_session,
+        sessions::get_session,
+    },
+    handlers::ai::{
+        error::AiError, lesson_summary::summarize_lesson, providers::SharedLlmProvider,
+    },
+    models::message::MessageRole,
+};
+
+/// the lesson notes of the session, written again only when messages were added since
+pub async fn create_summary_handler(
+    State(pool): State<SqlitePool>,
+    State(llm): State<SharedLlmProvider>,
+    Path(session_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let session = match get_session(&pool, session_id).await {
+        Ok(s) => s,
+        Err(sqlx::Error::RowNotFound) => {
+            return (StatusCode::NOT_FOUND, "Session not found").into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to get session for summary: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let history = match list_messages_for_session(&pool, session_id).await {
+        Ok(h) => h,
+        Err(e) => {
+            tracing::error!("Failed to get history for summary: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    if !history
+        .iter()
+        .any(|msg| matches!(msg.role, MessageRole::User))
+    {
+        return (StatusCode::BAD_REQUEST, "Nothing to summarize yet").into_response();
+    }
+
+    // messages are never removed from a session, so a new one changes the count
+    let message_count = history.len() as i64;
+    match get_lesson_summary(&pool, session_id).await {
+        Ok(Some(cached)) if cached.message_count == message_count => {
+            return (StatusCode::OK, Json(cached)).into_response();
+        }
+        Ok(_) => {}
+        Err(e) => {
+            tracing::error!("Failed to get cached summary: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    let draft = match summarize_lesson(&pool, llm.as_ref(), &session, history).await {
+        Ok(draft) => draft,
+        Err(e) => {
+            tracing::error!("{} summary failed: {}", llm.model(), e);
+            return match e.downcast::<AiError>() {
+                Ok(ai_error) => ai_error.into_response(),
+                Err(_) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to summarize the lesson",
+                )
+                    .into_response(),
+            };
+        }
+    };
+
+    match upsert_lesson_summary(&pool, session_id, draft, message_count, llm.model()).await {
+        Ok(summary) => (StatusCode::CREATED, Json(summary)).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to save summary: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
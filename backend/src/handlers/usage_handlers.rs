@@ -0,0 +1,45 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use sqlx::SqlitePool;
+
+use crate::{
+    database::usage::{usage_by_day, usage_by_purpose, usage_by_session},
+    models::usage::UsageReport,
+};
+
+/// token usage and latency totals per session, per day and model, and per purpose
+pub async fn usage_report_handler(State(pool): State<SqlitePool>) -> impl IntoResponse {
+    let by_session = match usage_by_session(&pool).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!("Failed to aggregate usage by session: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let by_day = match usage_by_day(&pool).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!("Failed to aggregate usage by day: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let by_purpose = match usage_by_purpose(&pool).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!("Failed to aggregate usage by purpose: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    Json(UsageReport {
+        by_session,
+        by_day,
+        by_purpose,
+    })
+    .into_response()
+}
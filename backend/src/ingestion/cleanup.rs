@@ -0,0 +1,533 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    str::FromStr,
+};
+
+use serde::Serialize;
+use unicode_normalization::UnicodeNormalization;
+
+// running headers and footers are looked for among the first and last lines of a page
+const EDGE_LINES: usize = 2;
+// longer lines are text, not a running head
+const MAX_RUNNING_LINE_CHARS: usize = 120;
+// words that start a figure or table caption, lowercase
+const CAPTION_LABELS: [&str; 6] = ["figure", "fig.", "table", "chart", "diagram", "exhibit"];
+
+/// a step of the cleanup extracted text goes through before it's stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanupStep {
+    /// expands ligatures and other compatibility characters, drops invisible ones and
+    /// collapses runs of spaces
+    Unicode,
+    /// removes page numbers and the running headers and footers repeated across pages
+    HeadersFooters,
+    /// removes copyright notices, "intentionally left blank" and bare links
+    Boilerplate,
+    /// joins words hyphenated across a line break
+    Dehyphenate,
+}
+
+impl CleanupStep {
+    pub const ALL: [CleanupStep; 4] = [
+        CleanupStep::Unicode,
+        CleanupStep::HeadersFooters,
+        CleanupStep::Boilerplate,
+        CleanupStep::Dehyphenate,
+    ];
+}
+
+impl FromStr for CleanupStep {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unicode" => Ok(CleanupStep::Unicode),
+            "headers" => Ok(CleanupStep::HeadersFooters),
+            "boilerplate" => Ok(CleanupStep::Boilerplate),
+            "dehyphenate" => Ok(CleanupStep::Dehyphenate),
+            _ => Err(format!("Invalid cleanup step: {}", s)),
+        }
+    }
+}
+
+/// what happens to figure and table captions, which otherwise run into the text around them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptionMode {
+    Keep,
+    /// each caption becomes its own bracketed paragraph, e.g. "[Figure 3: The Calvin cycle]"
+    Separate,
+    Drop,
+}
+
+impl FromStr for CaptionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(CaptionMode::Keep),
+            "separate" => Ok(CaptionMode::Separate),
+            "drop" => Ok(CaptionMode::Drop),
+            _ => Err(format!("Invalid caption mode: {}", s)),
+        }
+    }
+}
+
+/// how extracted text is cleaned up, from `TEXT_CLEANUP` and `CAPTIONS`. everything is
+/// on by default and captions are set apart
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cleanup {
+    pub steps: Vec<CleanupStep>,
+    pub captions: CaptionMode,
+}
+
+impl Default for Cleanup {
+    fn default() -> Self {
+        Cleanup {
+            steps: CleanupStep::ALL.to_vec(),
+            captions: CaptionMode::Separate,
+        }
+    }
+}
+
+/// what the cleanup changed, to judge it in a preview
+#[derive(Debug, Default, Serialize)]
+pub struct CleanupReport {
+    pub characters_fixed: usize, // ligatures, odd spaces and invisible characters
+    pub page_numbers_removed: usize,
+    pub running_lines_removed: Vec<String>, // one example of each header or footer
+    pub boilerplate_removed: Vec<String>,
+    pub captions: usize,
+    pub hyphenations_joined: usize,
+}
+
+impl Cleanup {
+    /// the text exactly as the extractor produced it
+    pub fn none() -> Self {
+        Cleanup {
+            steps: Vec::new(),
+            captions: CaptionMode::Keep,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let mut cleanup = Cleanup::default();
+        if let Ok(steps) = env::var("TEXT_CLEANUP") {
+            match Cleanup::parse_steps(&steps) {
+                Ok(steps) => cleanup.steps = steps,
+                Err(e) => tracing::warn!("{} in TEXT_CLEANUP, using 'on'", e),
+            }
+        }
+        if let Ok(captions) = env::var("CAPTIONS") {
+            match captions.parse() {
+                Ok(mode) => cleanup.captions = mode,
+                Err(e) => tracing::warn!("{} in CAPTIONS, using 'separate'", e),
+            }
+        }
+        cleanup
+    }
+
+    /// a comma separated list of steps like "unicode, headers", or "on" for all of
+    /// them and "off" for none
+    pub fn parse_steps(text: &str) -> Result<Vec<CleanupStep>, String> {
+        match text.trim() {
+            "on" => Ok(CleanupStep::ALL.to_vec()),
+            "off" | "" => Ok(Vec::new()),
+            list => list
+                .split(',')
+                .map(str::trim)
+                .filter(|step| !step.is_empty())
+                .map(str::parse)
+                .collect(),
+        }
+    }
+
+    fn has(&self, step: CleanupStep) -> bool {
+        self.steps.contains(&step)
+    }
+
+    /// cleans the text of a document's pages, a document without pages is one page.
+    /// the steps always run in the same order: characters first so lines compare equal,
+    /// then whole lines are removed, and words are joined last across the lines left
+    pub fn clean_pages(&self, pages: &[String]) -> (Vec<String>, CleanupReport) {
+        let mut report = CleanupReport::default();
+        let mut pages: Vec<Vec<String>> = pages
+            .iter()
+            .map(|page| {
+                page.replace("\r\n", "\n")
+                    .replace('\r', "\n")
+                    .lines()
+                    .map(str::to_string)
+                    .collect()
+            })
+            .collect();
+
+        if self.has(CleanupStep::Unicode) {
+            for line in pages.iter_mut().flatten() {
+                *line = normalise_unicode(line, &mut report.characters_fixed);
+            }
+        }
+        if self.has(CleanupStep::HeadersFooters) && pages.len() > 1 {
+            remove_running_lines(&mut pages, &mut report);
+        }
+
+        for lines in pages.iter_mut() {
+            if self.has(CleanupStep::Boilerplate) {
+                lines.retain(|line| {
+                    if !is_boilerplate(line) {
+                        return true;
+                    }
+                    let line = line.trim().to_string();
+                    if !report.boilerplate_removed.contains(&line) {
+                        report.boilerplate_removed.push(line);
+                    }
+                    false
+                });
+            }
+            if self.captions != CaptionMode::Keep {
+                report.captions += handle_captions(lines, self.captions);
+            }
+            if self.has(CleanupStep::Dehyphenate) {
+                report.hyphenations_joined += dehyphenate(lines);
+            }
+        }
+
+        let pages = pages.into_iter().map(|lines| lines.join("\n")).collect();
+        (pages, report)
+    }
+}
+
+/// the line in NFKC with invisible characters dropped and inner runs of spaces collapsed,
+/// its indentation is kept
+fn normalise_unicode(line: &str, fixed: &mut usize) -> String {
+    let mut cleaned = String::with_capacity(line.len());
+    for (i, c) in line.char_indices() {
+        match c {
+            // a soft hyphen ending a line marks a hyphenated word, elsewhere it's invisible
+            '\u{00AD}' if line[i + c.len_utf8()..].trim().is_empty() => cleaned.push('-'),
+            '\u{00AD}' | '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' => {}
+            _ => {
+                if !c.is_ascii() && !c.nfkc().eq(std::iter::once(c)) {
+                    *fixed += 1;
+                }
+                cleaned.push(c);
+                continue;
+            }
+        }
+        *fixed += 1;
+    }
+
+    let normalised: String = cleaned.nfkc().collect();
+    let words = normalised.trim_start();
+    let indent = &normalised[..normalised.len() - words.len()];
+    format!(
+        "{}{}",
+        indent,
+        words.split_whitespace().collect::<Vec<_>>().join(" ")
+    )
+}
+
+/// which end of a page a line is at, and how many non-blank lines from it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Edge {
+    Top(usize),
+    Bottom(usize),
+}
+
+/// the non-blank lines at the top and bottom of a page, by their index
+fn edge_lines(lines: &[String]) -> Vec<(Edge, usize)> {
+    let filled: Vec<usize> = (0..lines.len())
+        .filter(|&i| !lines[i].trim().is_empty())
+        .collect();
+    let mut edges: Vec<(Edge, usize)> = Vec::new();
+    for (k, &i) in filled.iter().take(EDGE_LINES).enumerate() {
+        edges.push((Edge::Top(k), i));
+    }
+    for (k, &i) in filled.iter().rev().take(EDGE_LINES).enumerate() {
+        if !edges.iter().any(|&(_, seen)| seen == i) {
+            edges.push((Edge::Bottom(k), i));
+        }
+    }
+    edges
+}
+
+// lowercase with runs of digits as "#" so "Chapter 2 · 14" and "Chapter 2 · 15" match
+fn line_key(line: &str) -> String {
+    let mut key = String::new();
+    let mut in_number = false;
+    for c in line.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_digit() {
+            if !in_number {
+                key.push('#');
+            }
+            in_number = true;
+            continue;
+        }
+        in_number = false;
+        if !c.is_whitespace() {
+            key.push(c);
+        } else if !key.ends_with(' ') {
+            key.push(' ');
+        }
+    }
+    key
+}
+
+// "12", "- 12 -", "Page 12 of 30" or a small roman numeral like "xiv"
+fn is_page_number(key: &str) -> bool {
+    let key = key.trim_matches(|c: char| "-–—|[]() ".contains(c));
+    matches!(
+        key,
+        "#" | "page #" | "p. #" | "# of #" | "page # of #" | "# / #"
+    ) || (!key.is_empty() && key.len() <= 5 && key.chars().all(|c| "ivx".contains(c)))
+}
+
+/// removes page numbers at the edges of pages, and the lines that come back at the same
+/// edge on a good part of the pages
+fn remove_running_lines(pages: &mut [Vec<String>], report: &mut CleanupReport) {
+    let filled = pages
+        .iter()
+        .filter(|lines| lines.iter().any(|line| !line.trim().is_empty()))
+        .count();
+    // alternating left and right page headers each come back on half the pages
+    let threshold = (filled * 2).div_ceil(5).max(2);
+
+    let mut counts: HashMap<(Edge, String), usize> = HashMap::new();
+    for lines in pages.iter() {
+        for (edge, i) in edge_lines(lines) {
+            *counts.entry((edge, line_key(&lines[i]))).or_default() += 1;
+        }
+    }
+
+    let mut reported: HashSet<String> = HashSet::new();
+    for lines in pages.iter_mut() {
+        let mut removed: HashSet<usize> = HashSet::new();
+        for (edge, i) in edge_lines(lines) {
+            if lines[i].trim().chars().count() > MAX_RUNNING_LINE_CHARS {
+                continue;
+            }
+            let key = line_key(&lines[i]);
+            if is_page_number(&key) {
+                report.page_numbers_removed += 1;
+            } else if counts[&(edge, key.clone())] >= threshold {
+                if reported.insert(key) {
+                    report
+                        .running_lines_removed
+                        .push(lines[i].trim().to_string());
+                }
+            } else {
+                continue;
+            }
+            removed.insert(i);
+        }
+
+        let mut index = 0;
+        lines.retain(|_| {
+            index += 1;
+            !removed.contains(&(index - 1))
+        });
+    }
+}
+
+fn is_boilerplate(line: &str) -> bool {
+    let lower = line.trim().to_lowercase();
+    let bare_link = !lower.contains(' ')
+        && ["http://", "https://", "www.", "doi:"]
+            .iter()
+            .any(|prefix| lower.starts_with(prefix));
+
+    lower.contains("all rights reserved")
+        || lower.starts_with('©')
+        || (lower.starts_with("copyright ") && lower.chars().any(|c| c.is_ascii_digit()))
+        || lower.contains("intentionally left blank")
+        || lower.starts_with("downloaded from ")
+        || lower.starts_with("downloaded by ")
+        || bare_link
+}
+
+// "Figure 3: ...", "Fig. 2.1 - ..." or "Table 4. ...", but not "Table 4 shows ..."
+fn is_caption(line: &str) -> bool {
+    let lower = line.trim_start().to_lowercase();
+    let Some(rest) = CAPTION_LABELS
+        .iter()
+        .find_map(|label| lower.strip_prefix(label))
+    else {
+        return false;
+    };
+
+    let rest = rest.trim_start();
+    let number_len = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.'))
+        .unwrap_or(rest.len());
+    let (number, after) = rest.split_at(number_len);
+    let after = after.trim_start();
+
+    number.starts_with(|c: char| c.is_ascii_digit())
+        && (number.ends_with('.') || after.starts_with([':', '.', '-', '–', '—', '|']))
+}
+
+/// sets captions apart or drops them, returns how many were found
+fn handle_captions(lines: &mut Vec<String>, mode: CaptionMode) -> usize {
+    let mut found = 0;
+    let mut handled = Vec::with_capacity(lines.len());
+    for line in lines.drain(..) {
+        if !is_caption(&line) {
+            handled.push(line);
+            continue;
+        }
+
+        found += 1;
+        if mode == CaptionMode::Separate {
+            handled.push(String::new());
+            handled.push(format!("[{}]", line.trim()));
+            handled.push(String::new());
+        }
+    }
+    *lines = handled;
+    found
+}
+
+/// joins "photo-" and "synthesis" on the next line into "photosynthesis", when the next
+/// line goes on in lowercase; returns how many words were joined
+fn dehyphenate(lines: &mut Vec<String>) -> usize {
+    let mut joined = 0;
+    let mut i = 0;
+    while i + 1 < lines.len() {
+        let line = lines[i].trim_end();
+        let next = lines[i + 1].trim_start();
+        let stem = line
+            .strip_suffix(['-', '\u{2010}'])
+            .filter(|stem| stem.chars().last().is_some_and(char::is_alphabetic))
+            .filter(|_| next.starts_with(char::is_lowercase));
+
+        if let Some(stem) = stem {
+            let (word, rest) = next.split_at(next.find(char::is_whitespace).unwrap_or(next.len()));
+            let (line, rest) = (format!("{}{}", stem, word), rest.trim_start().to_string());
+            lines[i] = line;
+            joined += 1;
+            // the line may have held just the word's end, then the joined word may
+            // itself be hyphenated with the line after
+            if rest.is_empty() {
+                lines.remove(i + 1);
+            } else {
+                lines[i + 1] = rest;
+                i += 1;
+            }
+            continue;
+        }
+        i += 1;
+    }
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/cleanup");
+
+    fn read_fixture(name: &str) -> String {
+        let path = format!("{}/{}", FIXTURES, name);
+        std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path, e))
+    }
+
+    // a fixture's pages are split by a line holding just a form feed, as pdftotext does
+    fn read_pages(name: &str) -> Vec<String> {
+        read_fixture(name)
+            .trim_end_matches('\n')
+            .split("\n\u{c}\n")
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// cleans `input`.txt and compares the pages and the report with `expected`.expected.txt
+    /// and `expected`.report.json
+    fn golden(input: &str, expected: &str, cleanup: Cleanup) {
+        let (pages, report) = cleanup.clean_pages(&read_pages(&format!("{}.txt", input)));
+
+        assert_eq!(
+            pages,
+            read_pages(&format!("{}.expected.txt", expected)),
+            "cleaned pages of {}",
+            expected
+        );
+        let expected_report: serde_json::Value =
+            serde_json::from_str(&read_fixture(&format!("{}.report.json", expected))).unwrap();
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            expected_report,
+            "report of {}",
+            expected
+        );
+    }
+
+    fn only(step: CleanupStep) -> Cleanup {
+        Cleanup {
+            steps: vec![step],
+            captions: CaptionMode::Keep,
+        }
+    }
+
+    fn captions(mode: CaptionMode) -> Cleanup {
+        Cleanup {
+            captions: mode,
+            ..Cleanup::none()
+        }
+    }
+
+    #[test]
+    fn removes_running_headers_and_footers() {
+        golden(
+            "headers_footers",
+            "headers_footers",
+            only(CleanupStep::HeadersFooters),
+        );
+    }
+
+    #[test]
+    fn removes_page_numbers() {
+        golden(
+            "page_numbers",
+            "page_numbers",
+            only(CleanupStep::HeadersFooters),
+        );
+    }
+
+    #[test]
+    fn normalises_ligatures_and_invisible_characters() {
+        golden("unicode", "unicode", only(CleanupStep::Unicode));
+    }
+
+    #[test]
+    fn joins_hyphenated_words() {
+        golden(
+            "dehyphenation",
+            "dehyphenation",
+            only(CleanupStep::Dehyphenate),
+        );
+    }
+
+    #[test]
+    fn removes_boilerplate() {
+        golden("boilerplate", "boilerplate", only(CleanupStep::Boilerplate));
+    }
+
+    #[test]
+    fn keeps_captions() {
+        golden("captions", "captions_keep", captions(CaptionMode::Keep));
+    }
+
+    #[test]
+    fn separates_captions() {
+        golden(
+            "captions",
+            "captions_separate",
+            captions(CaptionMode::Separate),
+        );
+    }
+
+    #[test]
+    fn drops_captions() {
+        golden("captions", "captions_drop", captions(CaptionMode::Drop));
+    }
+}
@@ -0,0 +1,71 @@
+use quick_xml::{Reader, events::Event};
+
+use crate::ingestion::{TextBuilder, open_zip, read_zip_entry};
+
+const DOCUMENT_PATH: &str = "word/document.xml";
+
+/// whether a zip archive is a Word document
+pub fn looks_like_docx(bytes: &[u8]) -> bool {
+    open_zip(bytes).is_ok_and(|archive| archive.index_for_name(DOCUMENT_PATH).is_some())
+}
+
+/// the paragraphs of a Word document, with its "Title" and "Heading N" paragraphs as sections
+pub fn extract(bytes: &[u8]) -> Result<TextBuilder, anyhow::Error> {
+    let mut archive = open_zip(bytes)?;
+    let xml = read_zip_entry(&mut archive, DOCUMENT_PATH)?;
+
+    let mut reader = Reader::from_str(&xml);
+    let mut builder = TextBuilder::new();
+    let mut paragraph = String::new();
+    let mut heading: Option<u8> = None;
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"w:p" => {
+                    paragraph.clear();
+                    heading = None;
+                }
+                b"w:t" => in_text = true,
+                _ => {}
+            },
+            Event::Empty(e) => match e.name().as_ref() {
+                b"w:pStyle" => {
+                    if let Some(style) = e.try_get_attribute("w:val")? {
+                        heading = heading_level(&style.unescape_value()?);
+                    }
+                }
+                b"w:tab" | b"w:br" | b"w:cr" => paragraph.push(' '),
+                _ => {}
+            },
+            Event::Text(text) if in_text => paragraph.push_str(&text.unescape()?),
+            Event::End(e) => match e.name().as_ref() {
+                b"w:t" => in_text = false,
+                b"w:p" => match heading {
+                    Some(level) => builder.push_heading(&paragraph, level),
+                    None => builder.push_paragraph(&paragraph),
+                },
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(builder)
+}
+
+// "Title" is the top level, "Heading1" or "heading 2" count from there
+fn heading_level(style: &str) -> Option<u8> {
+    let style = style.to_ascii_lowercase();
+    if style == "title" {
+        return Some(1);
+    }
+
+    let level = style.strip_prefix("heading")?.trim();
+    level
+        .parse::<u8>()
+        .ok()
+        .filter(|level| (1..=9).contains(level))
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use quick_xml::{Reader, events::Event};
+
+use crate::ingestion::{TextBuilder, html, open_zip, read_zip_entry};
+
+const CONTAINER_PATH: &str = "META-INF/container.xml";
+
+/// whether a zip archive is an EPUB book
+pub fn looks_like_epub(bytes: &[u8]) -> bool {
+    open_zip(bytes).is_ok_and(|archive| archive.index_for_name(CONTAINER_PATH).is_some())
+}
+
+/// the chapters of an EPUB book in reading order, their headings as sections
+pub fn extract(bytes: &[u8]) -> Result<TextBuilder, anyhow::Error> {
+    let mut archive = open_zip(bytes)?;
+
+    // the container points at the package document, which lists the chapters
+    let container = read_zip_entry(&mut archive, CONTAINER_PATH)?;
+    let package_path = find_attribute(&container, b"rootfile", "full-path")?
+        .ok_or_else(|| anyhow!("{} names no package document", CONTAINER_PATH))?;
+    let package = read_zip_entry(&mut archive, &package_path)?;
+    let base = package_path
+        .rsplit_once('/')
+        .map(|(dir, _)| dir)
+        .unwrap_or_default();
+
+    let mut builder = TextBuilder::new();
+    for href in reading_order(&package)? {
+        let path = resolve_path(base, &href);
+        match read_zip_entry(&mut archive, &path) {
+            Ok(chapter) => builder.append(html::extract(&chapter)),
+            // a broken link costs one chapter, not the book
+            Err(e) => tracing::warn!("Skipping EPUB chapter {}: {}", path, e),
+        }
+    }
+
+    Ok(builder)
+}
+
+// the hrefs of the spine's documents, in reading order
+fn reading_order(package: &str) -> Result<Vec<String>, anyhow::Error> {
+    let mut reader = Reader::from_str(package);
+    let mut manifest: HashMap<String, String> = HashMap::new();
+    let mut spine: Vec<String> = Vec::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                b"item" => {
+                    let id = e.try_get_attribute("id")?;
+                    let href = e.try_get_attribute("href")?;
+                    if let (Some(id), Some(href)) = (id, href) {
+                        manifest.insert(
+                            id.unescape_value()?.into_owned(),
+                            href.unescape_value()?.into_owned(),
+                        );
+                    }
+                }
+                b"itemref" => {
+                    if let Some(idref) = e.try_get_attribute("idref")? {
+                        spine.push(idref.unescape_value()?.into_owned());
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(spine
+        .iter()
+        .filter_map(|idref| manifest.get(idref).cloned())
+        .collect())
+}
+
+// the value of `attribute` on the first `element` of `xml`
+fn find_attribute(
+    xml: &str,
+    element: &[u8],
+    attribute: &str,
+) -> Result<Option<String>, anyhow::Error> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == element => {
+                return Ok(match e.try_get_attribute(attribute)? {
+                    Some(value) => Some(value.unescape_value()?.into_owned()),
+                    None => None,
+                });
+            }
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+// an href of the package document as a path inside the archive
+fn resolve_path(base: &str, href: &str) -> String {
+    let href = href
+        .split('#')
+        .next()
+        .unwrap_or_default()
+        .replace("%20", " ");
+    let mut parts: Vec<&str> = base.split('/').filter(|part| !part.is_empty()).collect();
+
+    for part in href.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+
+    parts.join("/")
+}
@@ -0,0 +1,206 @@
+use crate::ingestion::TextBuilder;
+
+// elements whose content is never study material
+const SKIPPED_TAGS: &str = "script style head noscript template svg nav";
+// elements that end the paragraph before them
+const BLOCK_TAGS: &str = "p div br hr li ul ol dl dt dd tr table thead tbody section article
+header footer main aside blockquote figure figcaption body html";
+
+/// the paragraphs of an HTML page, its h1 to h6 headings as sections
+pub fn extract(html: &str) -> TextBuilder {
+    let mut extractor = Extractor::default();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        extractor.text(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+
+        let Some(end) = tag_end(rest) else {
+            // a lone "<" is text
+            extractor.text("<");
+            rest = &rest[1..];
+            continue;
+        };
+        let tag = Tag::parse(&rest[1..end]);
+        rest = &rest[end + 1..];
+
+        if let Some(tag) = tag {
+            if !tag.closing && is_listed(SKIPPED_TAGS, &tag.name) {
+                rest = skip_element(rest, &tag.name);
+                continue;
+            }
+            extractor.tag(&tag);
+        }
+    }
+    extractor.text(rest);
+
+    extractor.flush();
+    extractor.builder
+}
+
+#[derive(Default)]
+struct Extractor {
+    builder: TextBuilder,
+    current: String,
+    heading: Option<u8>,
+    in_pre: bool,
+}
+
+impl Extractor {
+    fn text(&mut self, raw: &str) {
+        if !raw.is_empty() {
+            self.current.push_str(&decode_entities(raw));
+        }
+    }
+
+    fn tag(&mut self, tag: &Tag) {
+        if let Some(level) = heading_level(&tag.name) {
+            self.flush();
+            self.heading = (!tag.closing).then_some(level);
+        } else if tag.name == "pre" {
+            self.flush();
+            self.in_pre = !tag.closing;
+        } else if tag.name == "br" && self.in_pre {
+            self.current.push('\n');
+        } else if is_listed(BLOCK_TAGS, &tag.name) {
+            self.flush();
+            if tag.name == "li" && !tag.closing {
+                self.current.push_str("- ");
+            }
+        } else if matches!(tag.name.as_str(), "td" | "th") {
+            self.current.push(' ');
+        }
+    }
+
+    fn flush(&mut self) {
+        let text = std::mem::take(&mut self.current);
+        match self.heading.take() {
+            Some(level) => self.builder.push_heading(&text, level),
+            None if self.in_pre => self.builder.push_text(&text),
+            None => self.builder.push_paragraph(&text),
+        }
+    }
+}
+
+struct Tag {
+    name: String,
+    closing: bool,
+}
+
+impl Tag {
+    // the inside of "<...>", None for doctypes and processing instructions
+    fn parse(inner: &str) -> Option<Tag> {
+        let (closing, inner) = match inner.strip_prefix('/') {
+            Some(inner) => (true, inner),
+            None => (false, inner),
+        };
+        let name: String = inner
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        (!name.is_empty()).then_some(Tag { name, closing })
+    }
+}
+
+// the index of the ">" closing the tag at the start of `html`, quotes in attributes
+// may hold a ">" of their own
+fn tag_end(html: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in html.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(i),
+            (None, '<') => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+// what follows the end of the `name` element starting `html`
+fn skip_element<'a>(html: &'a str, name: &str) -> &'a str {
+    let closing = format!("</{}", name);
+    let lowercase = html.to_ascii_lowercase();
+    match lowercase.find(&closing) {
+        Some(start) => {
+            let after = &html[start..];
+            after.find('>').map_or("", |end| &after[end + 1..])
+        }
+        None => "",
+    }
+}
+
+fn heading_level(name: &str) -> Option<u8> {
+    let level = name.strip_prefix('h')?.parse::<u8>().ok()?;
+    (1..=6).contains(&level).then_some(level)
+}
+
+fn is_listed(list: &str, word: &str) -> bool {
+    list.split_whitespace().any(|listed| listed == word)
+}
+
+/// `text` with its character references, like "&amp;" or "&#8212;", replaced
+pub fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let reference = rest
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| decode_entity(&rest[1..end]).map(|c| (c, end)));
+        match reference {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        "ndash" => Some('–'),
+        "mdash" => Some('—'),
+        "hellip" => Some('…'),
+        "lsquo" => Some('‘'),
+        "rsquo" => Some('’'),
+        "ldquo" => Some('“'),
+        "rdquo" => Some('”'),
+        _ => None,
+    }
+}
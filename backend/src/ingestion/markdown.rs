@@ -0,0 +1,49 @@
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+use crate::ingestion::TextBuilder;
+
+/// the paragraphs of a Markdown document, its headings as sections. formatting is
+/// dropped, code blocks keep their lines
+pub fn extract(markdown: &str) -> TextBuilder {
+    let mut builder = TextBuilder::new();
+    let mut current = String::new();
+    let mut in_code = false;
+
+    let parser = Parser::new_ext(
+        markdown,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+    );
+    for event in parser {
+        match event {
+            Event::Start(Tag::Item) => {
+                builder.push_paragraph(&std::mem::take(&mut current));
+                current.push_str("- ");
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                builder.push_paragraph(&std::mem::take(&mut current));
+                in_code = true;
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                builder.push_text(&std::mem::take(&mut current));
+                in_code = false;
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                builder.push_heading(&std::mem::take(&mut current), level as u8);
+            }
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Item
+                | TagEnd::TableHead
+                | TagEnd::TableRow
+                | TagEnd::BlockQuote(_),
+            ) => builder.push_paragraph(&std::mem::take(&mut current)),
+            Event::End(TagEnd::TableCell) => current.push(' '),
+            Event::Text(text) | Event::Code(text) => current.push_str(&text),
+            Event::SoftBreak | Event::HardBreak if !in_code => current.push(' '),
+            _ => {}
+        }
+    }
+    builder.push_paragraph(&current);
+
+    builder
+}
@@ -0,0 +1,483 @@
+use std::{
+    env,
+    io::{Cursor, Read},
+};
+
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use zip::ZipArchive;
+
+use crate::models::{
+    ingestion::CreateIngestionJob,
+    material::{CreateMaterial, MaterialFormat, MaterialPage, MaterialSection},
+};
+
+pub mod cleanup;
+pub mod docx;
+pub mod epub;
+pub mod html;
+pub mod markdown;
+pub mod pages;
+pub mod subtitles;
+pub mod worker;
+
+use cleanup::{Cleanup, CleanupReport};
+use pages::PageSelection;
+
+// big enough for a scanned textbook
+const DEFAULT_MAX_UPLOAD_MB: usize = 50;
+
+/// the largest upload request accepted, from `MAX_UPLOAD_MB`
+pub fn max_upload_bytes() -> usize {
+    let megabytes = match env::var("MAX_UPLOAD_MB") {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            tracing::warn!(
+                "Invalid MAX_UPLOAD_MB '{}', using {}",
+                value,
+                DEFAULT_MAX_UPLOAD_MB
+            );
+            DEFAULT_MAX_UPLOAD_MB
+        }),
+        Err(_) => DEFAULT_MAX_UPLOAD_MB,
+    };
+    megabytes * 1024 * 1024
+}
+
+/// why an uploaded file couldn't be turned into study material
+#[derive(Debug, thiserror::Error)]
+pub enum IngestError {
+    /// a file type we have no extractor for
+    #[error("unsupported file type: {0}")]
+    Unsupported(String),
+    /// the file claims a supported format but can't be read as one
+    #[error("corrupt {format} file: {reason}")]
+    Corrupt {
+        format: &'static str,
+        reason: String,
+    },
+    /// the file was read but holds no text, e.g. a scanned PDF
+    #[error("no text found in {0} file")]
+    Empty(&'static str),
+    /// none of the selected pages are in the PDF
+    #[error("no selected page in a PDF of {0} pages")]
+    PagesOutOfRange(usize),
+}
+
+impl IngestError {
+    fn corrupt(format: MaterialFormat, reason: impl ToString) -> Self {
+        IngestError::Corrupt {
+            format: format.as_str(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// stable identifier the frontend can switch on
+    pub fn kind(&self) -> &'static str {
+        match self {
+            IngestError::Unsupported(_) => "unsupported_type",
+            IngestError::Corrupt { .. } => "corrupt_file",
+            IngestError::Empty(_) => "empty_file",
+            IngestError::PagesOutOfRange(_) => "pages_out_of_range",
+        }
+    }
+
+    /// what to tell the teacher
+    pub fn user_message(&self) -> &'static str {
+        match self {
+            IngestError::Unsupported(_) => {
+                "This file type isn't supported. Upload a PDF, DOCX, EPUB, Markdown, HTML, text or subtitle file."
+            }
+            IngestError::Corrupt { .. } => "The file seems to be damaged and couldn't be read.",
+            IngestError::Empty(_) => "No text could be found in the file.",
+            IngestError::PagesOutOfRange(_) => "The selected pages aren't in the document.",
+        }
+    }
+
+    fn status_code(&self) -> StatusCode {
+        match self {
+            IngestError::Unsupported(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            IngestError::Corrupt { .. }
+            | IngestError::Empty(_)
+            | IngestError::PagesOutOfRange(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+}
+
+impl IntoResponse for IngestError {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "error": self.kind(),
+            "message": self.user_message(),
+        });
+        (self.status_code(), Json(body)).into_response()
+    }
+}
+
+/// the text of an uploaded file, normalised the same way whatever its format
+#[derive(Debug)]
+pub struct ExtractedMaterial {
+    pub format: MaterialFormat,
+    pub text: String,
+    pub sections: Vec<MaterialSection>,
+    pub page_count: Option<usize>, // PDFs only, the pages kept
+    pub pages: Vec<MaterialPage>,
+    pub cleanup: CleanupReport,
+}
+
+impl ExtractedMaterial {
+    /// the material to store for the file `source_name`
+    pub fn into_material(self, source_name: String) -> CreateMaterial {
+        CreateMaterial {
+            source_name,
+            format: self.format,
+            extracted_text: self.text,
+            page_count: self.page_count.map(|count| count as i64),
+            sections: self.sections,
+            pages: self.pages,
+        }
+    }
+}
+
+/// the material of a stored upload, only the selected pages of a PDF when there are some,
+/// cleaned up as configured
+pub fn extract_upload(upload: &CreateIngestionJob) -> Result<CreateMaterial, IngestError> {
+    let extracted = extract_material(
+        upload.file_name.as_deref(),
+        upload.content_type.as_deref(),
+        &upload.data,
+        upload.pages.as_ref(),
+        &Cleanup::from_env(),
+    )
+    .inspect_err(|e| tracing::error!("Material extraction failed: {}", e))?;
+
+    tracing::info!(
+        "Extracted {} material with {} sections",
+        extracted.format.as_str(),
+        extracted.sections.len()
+    );
+    tracing::debug!("Cleaned up extracted text: {:?}", extracted.cleanup);
+    Ok(extracted.into_material(upload.source_name()))
+}
+
+/// runs an extraction on the blocking pool, as parsing is CPU-bound and a malformed file
+/// can panic its parser, which counts as a corrupt file
+pub async fn extract_blocking<T, F>(extract: F) -> Result<T, IngestError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, IngestError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(extract)
+        .await
+        .unwrap_or_else(|e| {
+            Err(IngestError::Corrupt {
+                format: "uploaded",
+                reason: e.to_string(),
+            })
+        })
+}
+
+/// works out what an upload is from its bytes, file name and content type, in that
+/// order of trust, and extracts its text. `selection` keeps only some pages of a PDF,
+/// other formats have no pages and ignore it. the `cleanup` applies to PDFs and plain
+/// text, the other formats get their structure from their markup
+pub fn extract_material(
+    file_name: Option<&str>,
+    content_type: Option<&str>,
+    bytes: &[u8],
+    selection: Option<&PageSelection>,
+    cleanup: &Cleanup,
+) -> Result<ExtractedMaterial, IngestError> {
+    let format = detect_format(file_name, content_type, bytes)?;
+    let mut page_count = None;
+    let mut report = CleanupReport::default();
+
+    let builder = match format {
+        MaterialFormat::Pdf => {
+            let pages = pdf_extract::extract_text_from_mem_by_pages(bytes)
+                .map_err(|e| IngestError::corrupt(format, e))?;
+            let total = pages.len();
+            let (numbers, kept): (Vec<u32>, Vec<String>) = (1..)
+                .zip(pages)
+                .filter(|(number, _)| selection.is_none_or(|s| s.contains(*number)))
+                .unzip();
+            if kept.is_empty() {
+                return Err(IngestError::PagesOutOfRange(total));
+            }
+
+            page_count = Some(kept.len());
+            let (cleaned, cleanup_report) = cleanup.clean_pages(&kept);
+            report = cleanup_report;
+            let mut builder = TextBuilder::new();
+            for (number, page) in numbers.into_iter().zip(&cleaned) {
+                builder.push_page(number, page);
+            }
+            builder
+        }
+        MaterialFormat::Docx => {
+            docx::extract(bytes).map_err(|e| IngestError::corrupt(format, e))?
+        }
+        MaterialFormat::Epub => {
+            epub::extract(bytes).map_err(|e| IngestError::corrupt(format, e))?
+        }
+        _ => {
+            let text = decode_text(bytes)
+                .ok_or_else(|| IngestError::corrupt(format, "the file is not valid UTF-8 text"))?;
+            match format {
+                MaterialFormat::Markdown => markdown::extract(text),
+                MaterialFormat::Html => html::extract(text),
+                MaterialFormat::Srt | MaterialFormat::Vtt => subtitles::extract(text),
+                _ => {
+                    let (cleaned, cleanup_report) = cleanup.clean_pages(&[text.to_string()]);
+                    report = cleanup_report;
+                    let mut builder = TextBuilder::new();
+                    builder.push_text(&cleaned.concat());
+                    builder
+                }
+            }
+        }
+    };
+
+    let (text, sections, pages) = builder.finish();
+    if text.trim().is_empty() {
+        return Err(IngestError::Empty(format.as_str()));
+    }
+
+    Ok(ExtractedMaterial {
+        format,
+        text,
+        sections,
+        page_count,
+        pages,
+        cleanup: report,
+    })
+}
+
+/// the format of an upload. PDFs and zip containers are recognised by their bytes, text
+/// formats by the file extension or content type and, failing both, by sniffing the text
+pub fn detect_format(
+    file_name: Option<&str>,
+    content_type: Option<&str>,
+    bytes: &[u8],
+) -> Result<MaterialFormat, IngestError> {
+    let extension = file_name
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, extension)| extension);
+    let declared = match (extension, content_type) {
+        (Some(extension), _) => {
+            Some(MaterialFormat::from_extension(extension).ok_or_else(|| {
+                IngestError::Unsupported(format!(".{} file", extension.to_ascii_lowercase()))
+            })?)
+        }
+        (None, Some(content_type)) if !is_generic_content_type(content_type) => Some(
+            MaterialFormat::from_content_type(content_type)
+                .ok_or_else(|| IngestError::Unsupported(content_type.to_string()))?,
+        ),
+        _ => None,
+    };
+
+    if is_pdf(bytes) {
+        return Ok(MaterialFormat::Pdf);
+    }
+    if bytes.starts_with(b"PK\x03\x04") {
+        return match declared {
+            Some(format @ (MaterialFormat::Docx | MaterialFormat::Epub)) => Ok(format),
+            _ if epub::looks_like_epub(bytes) => Ok(MaterialFormat::Epub),
+            _ if docx::looks_like_docx(bytes) => Ok(MaterialFormat::Docx),
+            _ => Err(IngestError::Unsupported("zip archive".to_string())),
+        };
+    }
+
+    match declared {
+        // the name promised a container the bytes aren't
+        Some(format) if !format.is_text() => Err(IngestError::corrupt(
+            format,
+            "the file doesn't start like one",
+        )),
+        Some(format) => Ok(format),
+        None => Ok(sniff_text_format(bytes)),
+    }
+}
+
+// the "%PDF" marker may come after some junk, but within the first kilobyte
+fn is_pdf(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(1024)]
+        .windows(4)
+        .any(|window| window == b"%PDF")
+}
+
+/// the zip archive of a DOCX or EPUB file
+fn open_zip(bytes: &[u8]) -> Result<ZipArchive<Cursor<&[u8]>>, anyhow::Error> {
+    Ok(ZipArchive::new(Cursor::new(bytes))?)
+}
+
+/// a file inside a zip archive as UTF-8 text
+fn read_zip_entry(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    name: &str,
+) -> Result<String, anyhow::Error> {
+    let mut entry = archive
+        .by_name(name)
+        .map_err(|e| anyhow::anyhow!("{}: {}", name, e))?;
+    let mut text = String::new();
+    entry.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+// content types browsers send when they don't know better
+fn is_generic_content_type(content_type: &str) -> bool {
+    matches!(
+        content_type.split(';').next().unwrap_or_default().trim(),
+        "" | "application/octet-stream" | "binary/octet-stream"
+    )
+}
+
+fn sniff_text_format(bytes: &[u8]) -> MaterialFormat {
+    let Some(text) = decode_text(bytes) else {
+        return MaterialFormat::PlainText;
+    };
+    let start = text.trim_start();
+    let lowercase_start: String = start.chars().take(64).collect::<String>().to_lowercase();
+
+    if start.starts_with("WEBVTT") {
+        MaterialFormat::Vtt
+    } else if lowercase_start.starts_with("<!doctype html") || lowercase_start.starts_with("<html")
+    {
+        MaterialFormat::Html
+    } else if subtitles::looks_like_srt(start) {
+        MaterialFormat::Srt
+    } else {
+        MaterialFormat::PlainText
+    }
+}
+
+/// the bytes as UTF-8 text without a byte order mark
+fn decode_text(bytes: &[u8]) -> Option<&str> {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    std::str::from_utf8(bytes).ok()
+}
+
+/// assembles extracted text as paragraphs separated by blank lines and remembers where
+/// each heading starts
+#[derive(Debug, Default)]
+pub struct TextBuilder {
+    text: String,
+    sections: Vec<MaterialSection>,
+    pages: Vec<MaterialPage>,
+}
+
+impl TextBuilder {
+    pub fn new() -> Self {
+        TextBuilder::default()
+    }
+
+    /// adds a paragraph, whitespace inside it is collapsed
+    pub fn push_paragraph(&mut self, paragraph: &str) {
+        let paragraph = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+        if paragraph.is_empty() {
+            return;
+        }
+
+        if !self.text.is_empty() {
+            self.text.push_str("\n\n");
+        }
+        self.text.push_str(&paragraph);
+    }
+
+    /// adds text that already has its own line structure, like a plain text file or a
+    /// code block; runs of blank lines become one
+    pub fn push_text(&mut self, text: &str) {
+        let normalized = text.replace("\r\n", "\n").replace('\r', "\n");
+        let mut blank = false;
+        let mut lines = Vec::new();
+        for line in normalized.lines().map(str::trim_end) {
+            if line.trim().is_empty() {
+                blank = !lines.is_empty();
+                continue;
+            }
+            if blank {
+                lines.push("");
+                blank = false;
+            }
+            lines.push(line);
+        }
+        if lines.is_empty() {
+            return;
+        }
+
+        if !self.text.is_empty() {
+            self.text.push_str("\n\n");
+        }
+        self.text.push_str(&lines.join("\n"));
+    }
+
+    /// adds the text of a PDF page and records where it starts. a blank page starts
+    /// where the next one does
+    pub fn push_page(&mut self, number: u32, text: &str) {
+        let offset = if self.text.is_empty() {
+            0
+        } else {
+            self.text.len() + 2
+        };
+        self.push_text(text);
+        self.pages.push(MaterialPage {
+            number,
+            offset: offset.min(self.text.len()),
+        });
+    }
+
+    /// adds a heading as its own paragraph and records it as a section
+    pub fn push_heading(&mut self, title: &str, level: u8) {
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+        if title.is_empty() {
+            return;
+        }
+
+        let offset = if self.text.is_empty() {
+            0
+        } else {
+            self.text.len() + 2
+        };
+        self.push_paragraph(&title);
+        self.sections.push(MaterialSection {
+            title,
+            level: level.max(1),
+            offset,
+        });
+    }
+
+    /// appends another builder's text, keeping its sections
+    pub fn append(&mut self, other: TextBuilder) {
+        if other.text.is_empty() {
+            return;
+        }
+
+        let shift = if self.text.is_empty() {
+            0
+        } else {
+            self.text.push_str("\n\n");
+            self.text.len()
+        };
+        self.text.push_str(&other.text);
+        self.sections
+            .extend(other.sections.into_iter().map(|section| MaterialSection {
+                offset: section.offset + shift,
+                ..section
+            }));
+        self.pages
+            .extend(other.pages.into_iter().map(|page| MaterialPage {
+                offset: page.offset + shift,
+                ..page
+            }));
+    }
+
+    pub fn finish(self) -> (String, Vec<MaterialSection>, Vec<MaterialPage>) {
+        (self.text, self.sections, self.pages)
+    }
+}
+
+#[cfg(test)]
+mod tests;
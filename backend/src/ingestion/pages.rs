@@ -0,0 +1,65 @@
+use std::{fmt, str::FromStr};
+
+/// the pages of a PDF to keep, parsed from an upload's `pages` field like "12-30" or "1-3, 7"
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageSelection {
+    ranges: Vec<(u32, u32)>, // inclusive, page numbers start at 1
+}
+
+impl PageSelection {
+    pub fn contains(&self, page: u32) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(first, last)| (first..=last).contains(&page))
+    }
+}
+
+// written back the way it's parsed, e.g. "1-3, 7"
+impl fmt::Display for PageSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, &(first, last)) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            if first == last {
+                write!(f, "{}", first)?;
+            } else {
+                write!(f, "{}-{}", first, last)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PageSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid page selection: '{}'", s.trim());
+        let page = |number: &str| {
+            number
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|&page| page > 0)
+                .ok_or_else(invalid)
+        };
+
+        let mut ranges = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let range = match part.split_once('-') {
+                Some((first, last)) => (page(first)?, page(last)?),
+                None => (page(part)?, page(part)?),
+            };
+            if range.0 > range.1 {
+                return Err(invalid());
+            }
+            ranges.push(range);
+        }
+
+        if ranges.is_empty() {
+            return Err(invalid());
+        }
+        Ok(PageSelection { ranges })
+    }
+}
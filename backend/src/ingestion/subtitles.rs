@@ -0,0 +1,93 @@
+use crate::ingestion::{TextBuilder, html::decode_entities};
+
+// a pause this long between cues starts a new paragraph
+const PARAGRAPH_GAP_MS: u64 = 3000;
+// captions without pauses are split at the first sentence end after this many characters
+const MAX_PARAGRAPH_CHARS: usize = 800;
+
+/// whether text starts like an SRT file: a cue number, then a timing line
+pub fn looks_like_srt(text: &str) -> bool {
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    let number = lines.next().unwrap_or_default();
+    let timing = lines.next().unwrap_or_default();
+    !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) && timing.contains("-->")
+}
+
+/// the spoken text of an SRT or WebVTT file as paragraphs. cue numbers, timings and
+/// styling are dropped, as are lines repeated by rolling captions
+pub fn extract(text: &str) -> TextBuilder {
+    let normalized = text.replace("\r\n", "\n").replace('\r', "\n");
+    let mut builder = TextBuilder::new();
+    let mut paragraph = String::new();
+    let mut previous_line = String::new();
+    let mut previous_end: Option<u64> = None;
+
+    // cues are separated by blank lines, blocks without a timing line are headers,
+    // NOTE, STYLE or REGION blocks
+    for block in normalized.split("\n\n") {
+        let lines: Vec<&str> = block.lines().collect();
+        let Some(timing) = lines.iter().position(|line| line.contains("-->")) else {
+            continue;
+        };
+        let (start, end) = parse_timing(lines[timing]);
+
+        let pause = match (previous_end, start) {
+            (Some(previous), Some(start)) => start.saturating_sub(previous),
+            _ => 0,
+        };
+        let sentence_ended = paragraph.ends_with(['.', '?', '!']);
+        if pause >= PARAGRAPH_GAP_MS || (paragraph.len() >= MAX_PARAGRAPH_CHARS && sentence_ended) {
+            builder.push_paragraph(&std::mem::take(&mut paragraph));
+        }
+        previous_end = end.or(previous_end);
+
+        for line in &lines[timing + 1..] {
+            let line = decode_entities(&strip_tags(line));
+            let line = line.trim();
+            if line.is_empty() || line == previous_line {
+                continue;
+            }
+
+            if !paragraph.is_empty() {
+                paragraph.push(' ');
+            }
+            paragraph.push_str(line);
+            previous_line = line.to_string();
+        }
+    }
+    builder.push_paragraph(&paragraph);
+
+    builder
+}
+
+// the start and end of "00:01:02,500 --> 00:01:04,000 align:start", in milliseconds
+fn parse_timing(line: &str) -> (Option<u64>, Option<u64>) {
+    let (start, end) = line.split_once("-->").unwrap_or_default();
+    let first_word = |s: &str| s.split_whitespace().next().and_then(parse_timestamp);
+    (first_word(start), first_word(end))
+}
+
+// "01:02:03,450", "01:02:03.450" or "02:03.450"
+fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let (clock, millis) = timestamp.split_once([',', '.']).unwrap_or((timestamp, "0"));
+    let mut seconds = 0;
+    for part in clock.split(':') {
+        seconds = seconds * 60 + part.parse::<u64>().ok()?;
+    }
+    Some(seconds * 1000 + millis.parse::<u64>().ok()?)
+}
+
+// "<i>", "<c.yellow>" or "<00:00:01.000>" styling inside a cue
+fn strip_tags(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => stripped.push(c),
+            _ => {}
+        }
+    }
+    stripped
+}
@@ -0,0 +1,158 @@
+use super::*;
+
+fn fixture(name: &str) -> Vec<u8> {
+    let path = format!(
+        "{}/tests/fixtures/ingestion/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    std::fs::read(&path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path, e))
+}
+
+fn extract(name: &str) -> Result<ExtractedMaterial, IngestError> {
+    extract_material(Some(name), None, &fixture(name), None, &Cleanup::default())
+}
+
+// the sections as (title, level), checking each offset points at its title
+fn sections(material: &ExtractedMaterial) -> Vec<(&str, u8)> {
+    material
+        .sections
+        .iter()
+        .map(|section| {
+            assert!(
+                material.text[section.offset..].starts_with(&section.title),
+                "section {:?} doesn't start at its offset",
+                section.title
+            );
+            (section.title.as_str(), section.level)
+        })
+        .collect()
+}
+
+#[test]
+fn extracts_docx() {
+    let material = extract("lesson.docx").unwrap();
+
+    assert_eq!(material.format, MaterialFormat::Docx);
+    assert_eq!(
+        material.text,
+        "Cell Biology\n\nEvery living thing is made of cells & their parts.\n\n\
+         Organelles\n\nThe nucleus stores DNA."
+    );
+    assert_eq!(
+        sections(&material),
+        [("Cell Biology", 1), ("Organelles", 2)]
+    );
+}
+
+#[test]
+fn extracts_epub_chapters_in_spine_order() {
+    let material = extract("lesson.epub").unwrap();
+
+    assert_eq!(material.format, MaterialFormat::Epub);
+    assert_eq!(
+        material.text,
+        "Chapter One\n\nSeeds need water.\n\nChapter Two\n\nRoots\n\nRoots take it up."
+    );
+    assert_eq!(
+        sections(&material),
+        [("Chapter One", 1), ("Chapter Two", 1), ("Roots", 2)]
+    );
+}
+
+#[test]
+fn extracts_markdown() {
+    let material = extract("lesson.md").unwrap();
+
+    assert_eq!(material.format, MaterialFormat::Markdown);
+    assert_eq!(
+        material.text,
+        "Photosynthesis\n\nPlants make food.\n\nLight\n\n- chlorophyll absorbs light\n\n\
+         - water splits\n\ncode line"
+    );
+    assert_eq!(sections(&material), [("Photosynthesis", 1), ("Light", 2)]);
+}
+
+#[test]
+fn extracts_html_without_scripts() {
+    let material = extract("lesson.html").unwrap();
+
+    assert_eq!(material.format, MaterialFormat::Html);
+    assert_eq!(
+        material.text,
+        "Cells\n\nCells & tissues—ok.\n\n- one\n\n- two"
+    );
+    assert_eq!(sections(&material), [("Cells", 1)]);
+}
+
+#[test]
+fn extracts_plain_text() {
+    let material = extract("lesson.txt").unwrap();
+
+    assert_eq!(material.format, MaterialFormat::PlainText);
+    assert_eq!(material.text, "plain text\n\nsecond");
+    assert!(material.sections.is_empty());
+}
+
+#[test]
+fn extracts_srt_without_timings_or_repeats() {
+    let material = extract("lecture.srt").unwrap();
+
+    assert_eq!(material.format, MaterialFormat::Srt);
+    assert_eq!(material.text, "Hello there. More words.\n\nNew topic.");
+    assert!(material.sections.is_empty());
+}
+
+#[test]
+fn extracts_vtt_without_notes_or_voices() {
+    let material = extract("lecture.vtt").unwrap();
+
+    assert_eq!(material.format, MaterialFormat::Vtt);
+    assert_eq!(
+        material.text,
+        "Cells are the units of life. They divide to grow."
+    );
+    assert!(material.sections.is_empty());
+}
+
+#[test]
+fn a_corrupt_zip_is_unprocessable() {
+    let error = extract("corrupt.docx").unwrap_err();
+
+    assert!(matches!(error, IngestError::Corrupt { format: "docx", .. }));
+    assert_eq!(error.kind(), "corrupt_file");
+    assert_eq!(
+        error.into_response().status(),
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+}
+
+#[test]
+fn an_unknown_extension_is_unsupported() {
+    let error = detect_format(Some("notes.rtf"), None, &fixture("notes.rtf")).unwrap_err();
+
+    assert!(matches!(&error, IngestError::Unsupported(what) if what == ".rtf file"));
+    assert_eq!(error.kind(), "unsupported_type");
+    assert_eq!(
+        error.into_response().status(),
+        StatusCode::UNSUPPORTED_MEDIA_TYPE
+    );
+}
+
+#[test]
+fn a_file_without_text_is_unprocessable() {
+    let error = extract_material(
+        Some("blank.txt"),
+        None,
+        b"  \n\n ",
+        None,
+        &Cleanup::default(),
+    )
+    .unwrap_err();
+
+    assert!(matches!(error, IngestError::Empty(_)));
+    assert_eq!(
+        error.into_response().status(),
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+}
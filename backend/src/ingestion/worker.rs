@@ -0,0 +1,156 @@
+use sqlx::SqlitePool;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        ingestion_jobs::{fail_job, get_job, list_unfinished_jobs, load_upload, set_stage},
+        materials::insert_material,
+        sessions::{get_session, update_material, update_status},
+    },
+    handlers::ai::{concepts::index_concepts, providers::SharedLlmProvider},
+    ingestion::{extract_blocking, extract_upload},
+    models::{
+        ingestion::IngestionStage,
+        session::{Session, SessionMode, SessionStatus},
+    },
+    retrieval::Retriever,
+};
+
+/// hands stored uploads to the background worker that turns them into their session's
+/// material, one at a time
+#[derive(Clone)]
+pub struct IngestionQueue {
+    tx: mpsc::UnboundedSender<Uuid>,
+}
+
+impl IngestionQueue {
+    /// starts the worker, which first picks up the uploads the last run left unfinished
+    pub fn start(pool: SqlitePool, llm: SharedLlmProvider, retriever: Retriever) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            match list_unfinished_jobs(&pool).await {
+                Ok(session_ids) => {
+                    for session_id in session_ids {
+                        process(&pool, &llm, &retriever, session_id).await;
+                    }
+                }
+                Err(e) => tracing::error!("Failed to list unfinished uploads: {}", e),
+            }
+
+            while let Some(session_id) = rx.recv().await {
+                process(&pool, &llm, &retriever, session_id).await;
+            }
+        });
+
+        IngestionQueue { tx }
+    }
+
+    /// queues the stored upload of a processing session
+    pub fn enqueue(&self, session_id: Uuid) {
+        if self.tx.send(session_id).is_err() {
+            tracing::error!(
+                "Ingestion worker has stopped, session {} stays processing",
+                session_id
+            );
+        }
+    }
+}
+
+async fn process(pool: &SqlitePool, llm: &SharedLlmProvider, retriever: &Retriever, id: Uuid) {
+    match run_job(pool, llm, retriever, id).await {
+        Ok(()) => {}
+        Err(e) => match e.downcast_ref::<sqlx::Error>() {
+            // the session was deleted while it waited
+            Some(sqlx::Error::RowNotFound) => {
+                tracing::debug!("Skipped upload of deleted session {}", id)
+            }
+            _ => tracing::error!("Failed to process upload for session {}: {}", id, e),
+        },
+    }
+}
+
+/// extracts, indexes and finds the concepts of a session's upload, then marks the session
+/// ready, or failed if no material could be extracted. chunking and concepts aren't fatal
+/// as they're done again the first time they're needed
+async fn run_job(
+    pool: &SqlitePool,
+    llm: &SharedLlmProvider,
+    retriever: &Retriever,
+    id: Uuid,
+) -> Result<(), anyhow::Error> {
+    let job = get_job(pool, id).await?;
+    let session = match job.stage {
+        IngestionStage::Done | IngestionStage::Failed => return Ok(()),
+        IngestionStage::Queued | IngestionStage::Extracting => match extract(pool, id).await? {
+            Some(session) => session,
+            None => return Ok(()),
+        },
+        // the material was saved before the last run stopped
+        IngestionStage::Indexing | IngestionStage::Concepts => {
+            set_stage(pool, id, IngestionStage::Indexing).await?;
+            get_session(pool, id).await?
+        }
+    };
+
+    match retriever
+        .index(pool, session.id, &session.material_text)
+        .await
+    {
+        Ok(count) => tracing::debug!("Indexed {} chunks for session {}", count, session.id),
+        Err(e) => tracing::warn!("Failed to index material for session {}: {}", session.id, e),
+    }
+
+    if session.mode != SessionMode::Quiz {
+        set_stage(pool, id, IngestionStage::Concepts).await?;
+        match index_concepts(pool, llm.as_ref(), &session).await {
+            Ok(concepts) => tracing::debug!(
+                "Extracted {} concepts for session {}",
+                concepts.len(),
+                session.id
+            ),
+            Err(e) => tracing::warn!(
+                "Failed to extract concepts for session {}: {}",
+                session.id,
+                e
+            ),
+        }
+    }
+
+    set_stage(pool, id, IngestionStage::Done).await?;
+    update_status(pool, id, SessionStatus::Ready).await?;
+    Ok(())
+}
+
+/// saves the upload's material as the session's first and moves the job on to indexing,
+/// `None` when it couldn't be extracted and the session failed
+async fn extract(pool: &SqlitePool, id: Uuid) -> Result<Option<Session>, anyhow::Error> {
+    set_stage(pool, id, IngestionStage::Extracting).await?;
+    let upload = load_upload(pool, id).await?;
+
+    let material = match extract_blocking(move || extract_upload(&upload)).await {
+        Ok(material) => material,
+        Err(e) => {
+            fail_job(pool, id, e.kind(), e.user_message()).await?;
+            update_status(pool, id, SessionStatus::Failed).await?;
+            return Ok(None);
+        }
+    };
+
+    // saved together with the next stage, so a restart can't add the material twice
+    let mut tx = pool.begin().await?;
+    insert_material(&mut tx, id, 0, &material).await?;
+    let session = update_material(
+        &mut *tx,
+        id,
+        &material.extracted_text,
+        &material.sections,
+        &material.source_pages(0),
+    )
+    .await?;
+    set_stage(&mut *tx, id, IngestionStage::Indexing).await?;
+    tx.commit().await?;
+
+    Ok(Some(session))
+}
@@ -0,0 +1,120 @@
+//! the Aazan backend: the API router and everything behind it, shared by the server
+//! binary and the integration tests
+
+use axum::{
+    Router,
+    extract::DefaultBodyLimit,
+    handler::Handler,
+    http::HeaderValue,
+    response::Html,
+    routing::{delete, get, post, put},
+};
+use reqwest::Method;
+use tower_http::{
+    cors::{Any, CorsLayer},
+    trace::TraceLayer,
+};
+
+use crate::{
+    handlers::{
+        clarity_handlers::get_clarity_handler,
+        coverage_handlers::get_coverage_handler,
+        evaluation_handlers::{create_evaluation_handler, get_evaluation_handler},
+        ingestion_handlers::get_ingestion_handler,
+        material_handlers::{
+            add_material_handler, delete_material_handler, list_materials_handler,
+            preview_material_handler,
+        },
+        message_handlers::{create_message_handler, list_messages_handler},
+        persona_handlers::list_personas_handler,
+        session_handlers::{
+            create_session_handler, delete_session_handler, get_session_handler,
+            list_sessions_handler, update_generation_settings_handler, update_status_handler,
+            upload_session_handler,
+        },
+        stream_handlers::sse_handler,
+        summary_handlers::create_summary_handler,
+        usage_handlers::usage_report_handler,
+    },
+    ingestion::max_upload_bytes,
+    state::AppState,
+};
+
+pub mod citations;
+pub mod clarity;
+pub mod database;
+pub mod handlers;
+pub mod ingestion;
+pub mod models;
+pub mod retrieval;
+pub mod state;
+
+/// the API routes over `state`
+pub fn router(state: AppState) -> Router {
+    let cors = CorsLayer::new()
+        .allow_origin([
+            "http://127.0.0.1:8081".parse::<HeaderValue>().unwrap(),
+            "http://localhost:8081".parse::<HeaderValue>().unwrap(),
+        ])
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::DELETE,
+            Method::OPTIONS,
+        ])
+        .allow_headers(Any);
+    // uploaded files are far bigger than the 2 MB allowed for other requests
+    let upload_limit = DefaultBodyLimit::max(max_upload_bytes());
+
+    Router::new()
+        .route("/api/personas", get(list_personas_handler))
+        .route("/api/sessions", get(list_sessions_handler))
+        .route("/api/sessions", post(create_session_handler))
+        .route(
+            "/api/sessions/upload",
+            post(upload_session_handler.layer(upload_limit)),
+        )
+        .route(
+            "/api/materials/preview",
+            post(preview_material_handler.layer(upload_limit)),
+        )
+        .route("/api/sessions/{:id}", get(get_session_handler))
+        .route("/api/sessions/{:id}", delete(delete_session_handler))
+        .route(
+            "/api/sessions/{:id}/settings",
+            put(update_generation_settings_handler),
+        )
+        .route("/api/sessions/{:id}/status", put(update_status_handler))
+        .route("/api/sessions/{:id}/ingestion", get(get_ingestion_handler))
+        .route(
+            "/api/sessions/{:id}/evaluation",
+            get(get_evaluation_handler).post(create_evaluation_handler),
+        )
+        .route(
+            "/api/sessions/{:id}/materials",
+            get(list_materials_handler).post(add_material_handler.layer(upload_limit)),
+        )
+        .route(
+            "/api/sessions/{:id}/materials/{:material_id}",
+            delete(delete_material_handler),
+        )
+        .route("/api/sessions/{:id}/coverage", get(get_coverage_handler))
+        .route("/api/sessions/{:id}/clarity", get(get_clarity_handler))
+        .route("/api/sessions/{:id}/summary", post(create_summary_handler))
+        .route("/api/sessions/{:id}/stream", get(sse_handler))
+        // nested message routes
+        .route(
+            "/api/sessions/{:id}/messages",
+            get(list_messages_handler).post(create_message_handler),
+        )
+        .route("/api/usage", get(usage_report_handler))
+        .route("/", get(home_page))
+        .with_state(state)
+        .layer(TraceLayer::new_for_http())
+        .layer(cors)
+}
+
+async fn home_page() -> Html<&'static str> {
+    Html("<h1>Welcome to Aazan! 🎓</h1><p>Learn by Teaching - Backend is working!</p>")
+}
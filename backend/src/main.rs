@@ -1,30 +1,14 @@
-use crate::handlers::{
-    message_handlers::{create_message_handler, list_messages_handler},
-    session_handlers::{
-        create_session_handler, delete_session_handler, get_session_handler, list_sessions_handler,
-        upload_session_handler,
-    },
-    stream_handlers::sse_handler,
+use aazan::{
+    handlers::ai::providers::{mock::MockProvider, mock_server, provider_from_env},
+    ingestion::worker::IngestionQueue,
+    retrieval::Retriever,
+    router,
+    state::AppState,
 };
-use axum::{
-    Router,
-    http::HeaderValue,
-    response::Html,
-    routing::{delete, get, post},
-};
-use reqwest::Method;
 use sqlx::sqlite::SqlitePoolOptions;
-use std::net::SocketAddr;
-use tower_http::{
-    cors::{Any, CorsLayer},
-    trace::TraceLayer,
-};
+use std::{net::SocketAddr, sync::Arc};
 use tracing_subscriber::{self, layer::SubscriberExt, util::SubscriberInitExt};
 
-mod database;
-mod handlers;
-mod models;
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenvy::dotenv().ok();
@@ -48,30 +32,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     tracing::info!("Database connection pool created.");
 
-    let cors = CorsLayer::new()
-        .allow_origin([
-            "http://127.0.0.1:8081".parse::<HeaderValue>().unwrap(),
-            "http://localhost:8081".parse::<HeaderValue>().unwrap(),
-        ])
-        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
-        .allow_headers(Any);
+    // a local Gemini stand-in for working without network access
+    if let Ok(addr) = std::env::var("MOCK_GEMINI_ADDR") {
+        let addr: SocketAddr = addr.parse().expect("MOCK_GEMINI_ADDR must be host:port");
+        let mock = MockProvider::from_env().expect("Failed to configure mock provider");
+        tokio::spawn(async move {
+            if let Err(e) = mock_server::serve(addr, Arc::new(mock)).await {
+                tracing::error!("Mock Gemini server failed: {}", e);
+            }
+        });
+    }
 
-    let app = Router::new()
-        .route("/api/sessions", get(list_sessions_handler))
-        .route("/api/sessions", post(create_session_handler))
-        .route("/api/sessions/upload", post(upload_session_handler))
-        .route("/api/sessions/{:id}", get(get_session_handler))
-        .route("/api/sessions/{:id}", delete(delete_session_handler))
-        .route("/api/sessions/{:id}/stream", get(sse_handler))
-        // nested message routes
-        .route(
-            "/api/sessions/{:id}/messages",
-            get(list_messages_handler).post(create_message_handler),
-        )
-        .route("/", get(|| home_page()))
-        .with_state(pool)
-        .layer(TraceLayer::new_for_http())
-        .layer(cors);
+    // pick the model backend (gemini, openai-compatible or mock) from the environment
+    let llm = provider_from_env().expect("Failed to configure LLM provider");
+    // how relevant passages of the study material are found for each prompt
+    let retriever = Retriever::from_env().expect("Failed to configure retrieval backend");
+    // uploads are extracted and indexed in the background, one at a time
+    let ingestion = IngestionQueue::start(pool.clone(), llm.clone(), retriever.clone());
+    let state = AppState {
+        pool,
+        llm,
+        retriever,
+        ingestion,
+    };
+
+    let app = router(state);
 
     // Run the server
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
@@ -81,7 +66,3 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
-
-async fn home_page() -> Html<&'static str> {
-    Html("<h1>Welcome to Aazan! 🎓</h1><p>Learn by Teaching - Backend is working!</p>")
-}
@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// how plainly a teacher message is written, measured locally without a model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClarityScore {
+    pub words: i64,
+    pub sentences: i64,
+    pub avg_sentence_words: f64,
+    pub reading_grade: f64,     // Flesch-Kincaid grade level, 0 and up
+    pub reading_ease: f64,      // Flesch reading ease, 0 (hard) to 100 (easy)
+    pub jargon_density: f64,    // share of words that are terms of the material, 0 to 1
+    pub jargon: Vec<String>,    // the material's terms the message uses
+    pub defined: Vec<String>,   // terms the message explains
+    pub undefined: Vec<String>, // terms explained neither here nor earlier
+}
+
+/// the clarity of one teacher message in a session's trend
+#[derive(Debug, Serialize)]
+pub struct ClarityPoint {
+    #[serde(with = "uuid::serde::urn")]
+    pub message_id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub reading_grade: f64,
+    pub avg_sentence_words: f64,
+    pub jargon_density: f64,
+}
+
+/// how the teacher's writing developed over a session. the changes compare the
+/// latest messages with the first ones, negative means simpler
+#[derive(Debug, Serialize)]
+pub struct ClarityTrend {
+    pub points: Vec<ClarityPoint>,
+    pub grade_change: Option<f64>,
+    pub sentence_change: Option<f64>,
+    pub jargon_change: Option<f64>,
+    pub undefined: Vec<String>, // terms the teacher used and never explained
+}
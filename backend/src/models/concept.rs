@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// a key idea of a session's study material
+#[derive(Debug, Clone, Serialize)]
+pub struct Concept {
+    #[serde(with = "uuid::serde::urn")]
+    pub id: Uuid,
+    pub position: i64, // order in which the material introduces it
+    pub name: String,
+    pub description: String,
+}
+
+/// a concept as the extracting model returns it
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConceptDraft {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// a `concepts` row exactly as SQLite stores it
+#[derive(Debug)]
+pub struct ConceptRow {
+    pub id: String,
+    pub position: i64,
+    pub name: String,
+    pub description: String,
+}
+
+impl TryFrom<ConceptRow> for Concept {
+    type Error = sqlx::Error;
+
+    fn try_from(row: ConceptRow) -> Result<Self, Self::Error> {
+        Ok(Concept {
+            id: Uuid::parse_str(&row.id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            position: row.position,
+            name: row.name,
+            description: row.description,
+        })
+    }
+}
+
+/// how thoroughly one teacher message explained a concept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageDepth {
+    Partial,
+    Full,
+}
+
+impl CoverageDepth {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CoverageDepth::Partial => "partial",
+            CoverageDepth::Full => "full",
+        }
+    }
+}
+
+/// where a concept stands over the whole lesson
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageStatus {
+    /// at least one message explained it fully
+    Taught,
+    /// only touched on in passing
+    Partial,
+    Untouched,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConceptCoverage {
+    #[serde(flatten)]
+    pub concept: Concept,
+    pub status: CoverageStatus,
+    pub mentions: i64, // teacher messages that covered it at all
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoverageReport {
+    pub concepts: Vec<ConceptCoverage>,
+    pub taught: usize,
+    pub partial: usize,
+    pub untouched: usize,
+}
+
+impl CoverageReport {
+    pub fn new(concepts: Vec<ConceptCoverage>) -> Self {
+        let count = |status| concepts.iter().filter(|c| c.status == status).count();
+
+        CoverageReport {
+            taught: count(CoverageStatus::Taught),
+            partial: count(CoverageStatus::Partial),
+            untouched: count(CoverageStatus::Untouched),
+            concepts,
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// condensed version of the older part of a lesson, kept so long sessions fit the context window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    #[serde(with = "uuid::serde::urn")]
+    pub session_id: Uuid,
+    pub summary: String,
+    pub covered_until: DateTime<Utc>, // messages up to this timestamp are folded in
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// what became of one of the user's arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgumentOutcome {
+    /// Bodhi countered it
+    Rebutted,
+    /// backed by sound evidence, Bodhi accepts it
+    Accepted,
+    /// neither yet, Bodhi asked for evidence or clarification
+    Open,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArgumentVerdict {
+    pub argument: String, // the user's argument in a few words
+    pub outcome: ArgumentOutcome,
+}
+
+/// what Bodhi reported with a debate reply
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebateTurn {
+    pub stance: String, // the position Bodhi defends
+    #[serde(default)]
+    pub arguments: Vec<ArgumentVerdict>, // the arguments of the user's latest message
+    #[serde(default)]
+    pub conceded: bool, // Bodhi gave up its position
+}
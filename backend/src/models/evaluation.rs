@@ -0,0 +1,121 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const MIN_SCORE: i64 = 1;
+pub const MAX_SCORE: i64 = 5;
+
+/// one rubric criterion: a 1-5 score and why it was given
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RubricScore {
+    pub score: i64,
+    pub feedback: String,
+}
+
+/// the rubric as the grading model returns it
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvaluationDraft {
+    pub accuracy: RubricScore,
+    pub completeness: RubricScore,
+    pub clarity: RubricScore,
+    pub examples: RubricScore,
+    pub question_handling: RubricScore,
+    pub summary: String,
+    #[serde(default)]
+    pub strengths: Vec<String>,
+    #[serde(default)]
+    pub improvements: Vec<String>,
+}
+
+/// end of lesson feedback on the teacher's explanations
+#[derive(Debug, Clone, Serialize)]
+pub struct Evaluation {
+    #[serde(with = "uuid::serde::urn")]
+    pub id: Uuid,
+    #[serde(with = "uuid::serde::urn")]
+    pub session_id: Uuid,
+    pub accuracy: RubricScore,          // does it agree with the material
+    pub completeness: RubricScore,      // were the key points covered
+    pub clarity: RubricScore,           // could a student follow it
+    pub examples: RubricScore,          // were examples and analogies used
+    pub question_handling: RubricScore, // were Bodhi's questions answered
+    pub overall_score: f64,             // mean of the five scores
+    pub summary: String,
+    pub strengths: Vec<String>,
+    pub improvements: Vec<String>,
+    pub model: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// an `evaluations` row exactly as SQLite stores it
+#[derive(Debug)]
+pub struct EvaluationRow {
+    pub id: String,
+    pub session_id: String,
+    pub accuracy_score: i64,
+    pub accuracy_feedback: String,
+    pub completeness_score: i64,
+    pub completeness_feedback: String,
+    pub clarity_score: i64,
+    pub clarity_feedback: String,
+    pub examples_score: i64,
+    pub examples_feedback: String,
+    pub question_handling_score: i64,
+    pub question_handling_feedback: String,
+    pub summary: String,
+    pub strengths: String,    // JSON
+    pub improvements: String, // JSON
+    pub model: String,
+    pub created_at: String,
+}
+
+impl TryFrom<EvaluationRow> for Evaluation {
+    type Error = sqlx::Error;
+
+    fn try_from(row: EvaluationRow) -> Result<Self, Self::Error> {
+        let scores = [
+            row.accuracy_score,
+            row.completeness_score,
+            row.clarity_score,
+            row.examples_score,
+            row.question_handling_score,
+        ];
+
+        Ok(Evaluation {
+            id: Uuid::parse_str(&row.id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            session_id: Uuid::parse_str(&row.session_id)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            accuracy: RubricScore {
+                score: row.accuracy_score,
+                feedback: row.accuracy_feedback,
+            },
+            completeness: RubricScore {
+                score: row.completeness_score,
+                feedback: row.completeness_feedback,
+            },
+            clarity: RubricScore {
+                score: row.clarity_score,
+                feedback: row.clarity_feedback,
+            },
+            examples: RubricScore {
+                score: row.examples_score,
+                feedback: row.examples_feedback,
+            },
+            question_handling: RubricScore {
+                score: row.question_handling_score,
+                feedback: row.question_handling_feedback,
+            },
+            overall_score: scores.iter().sum::<i64>() as f64 / scores.len() as f64,
+            summary: row.summary,
+            strengths: serde_json::from_str(&row.strengths)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            improvements: serde_json::from_str(&row.improvements)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            model: row.model,
+            created_at: row
+                .created_at
+                .parse()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        })
+    }
+}
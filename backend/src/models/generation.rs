@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+/// sampling and safety knobs for Bodhi's replies; every field is optional so a
+/// session only has to store what it overrides.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+    /// Gemini harm block threshold, e.g. "BLOCK_MEDIUM_AND_ABOVE"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub safety_threshold: Option<String>,
+}
+
+impl GenerationSettings {
+    /// the app wide defaults used when a session doesn't override a value
+    pub fn defaults() -> Self {
+        GenerationSettings {
+            temperature: Some(0.8),
+            top_p: Some(0.95),
+            max_output_tokens: Some(1024),
+            stop_sequences: None,
+            safety_threshold: Some("BLOCK_MEDIUM_AND_ABOVE".to_string()),
+        }
+    }
+
+    /// fills every value `self` leaves unset from `fallback`
+    pub fn or(self, fallback: GenerationSettings) -> Self {
+        GenerationSettings {
+            temperature: self.temperature.or(fallback.temperature),
+            top_p: self.top_p.or(fallback.top_p),
+            max_output_tokens: self.max_output_tokens.or(fallback.max_output_tokens),
+            stop_sequences: self.stop_sequences.or(fallback.stop_sequences),
+            safety_threshold: self.safety_threshold.or(fallback.safety_threshold),
+        }
+    }
+}
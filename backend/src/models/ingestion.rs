@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::{ingestion::pages::PageSelection, models::session::SessionStatus};
+
+/// the step an upload has reached in the background pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestionStage {
+    /// stored and waiting for the worker
+    Queued,
+    /// the file's text is being extracted
+    Extracting,
+    /// the text is being chunked and indexed for retrieval
+    Indexing,
+    /// the key concepts of the material are being extracted
+    Concepts,
+    Done,
+    Failed,
+}
+
+impl IngestionStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IngestionStage::Queued => "queued",
+            IngestionStage::Extracting => "extracting",
+            IngestionStage::Indexing => "indexing",
+            IngestionStage::Concepts => "concepts",
+            IngestionStage::Done => "done",
+            IngestionStage::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for IngestionStage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(IngestionStage::Queued),
+            "extracting" => Ok(IngestionStage::Extracting),
+            "indexing" => Ok(IngestionStage::Indexing),
+            "concepts" => Ok(IngestionStage::Concepts),
+            "done" => Ok(IngestionStage::Done),
+            "failed" => Ok(IngestionStage::Failed),
+            _ => Err(format!("Invalid IngestionStage: {}", s)),
+        }
+    }
+}
+
+/// why processing an upload failed, in the same shape as a rejected upload's body
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestionFailure {
+    pub error: String, // e.g. "corrupt_file"
+    pub message: String,
+}
+
+/// the processing of a session's upload, without the file itself
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestionJob {
+    #[serde(with = "uuid::serde::urn")]
+    pub session_id: Uuid,
+    pub file_name: Option<String>,
+    pub stage: IngestionStage,
+    pub failure: Option<IngestionFailure>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// an `ingestion_jobs` row exactly as SQLite stores it, without the file
+#[derive(Debug)]
+pub struct IngestionJobRow {
+    pub session_id: String,
+    pub file_name: Option<String>,
+    pub stage: String,
+    pub error_kind: Option<String>,
+    pub error_message: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl TryFrom<IngestionJobRow> for IngestionJob {
+    type Error = sqlx::Error;
+
+    fn try_from(row: IngestionJobRow) -> Result<Self, Self::Error> {
+        Ok(IngestionJob {
+            session_id: Uuid::parse_str(&row.session_id)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            file_name: row.file_name,
+            stage: row.stage.parse().map_err(|e: String| {
+                sqlx::Error::Decode(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    e,
+                )))
+            })?,
+            failure: row
+                .error_kind
+                .zip(row.error_message)
+                .map(|(error, message)| IngestionFailure { error, message }),
+            created_at: row
+                .created_at
+                .parse()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            updated_at: row
+                .updated_at
+                .parse()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        })
+    }
+}
+
+/// an uploaded file stored for the worker to process
+#[derive(Debug, Clone)]
+pub struct CreateIngestionJob {
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+    pub pages: Option<PageSelection>,
+    pub data: Vec<u8>,
+}
+
+impl CreateIngestionJob {
+    /// the name the material gets once extracted
+    pub fn source_name(&self) -> String {
+        self.file_name
+            .clone()
+            .unwrap_or_else(|| "Uploaded file".to_string())
+    }
+}
+
+/// how far a session's material is, for the frontend to poll while it's processing
+#[derive(Debug, Serialize)]
+pub struct IngestionProgress {
+    pub status: SessionStatus,
+    pub job: Option<IngestionJob>, // sessions created from pasted text have none
+}
@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// a question Bodhi asked during the lesson
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonQuestion {
+    pub question: String,
+    #[serde(default)]
+    pub answered: bool, // whether the teacher answered it
+}
+
+/// the notes as the summarizing model returns them
+#[derive(Debug, Clone, Deserialize)]
+pub struct LessonSummaryDraft {
+    #[serde(default)]
+    pub key_points: Vec<String>,
+    #[serde(default)]
+    pub questions: Vec<LessonQuestion>,
+    #[serde(default)]
+    pub gaps: Vec<String>,
+    #[serde(default)]
+    pub follow_up: Vec<String>,
+}
+
+/// what the teacher can take away from a lesson
+#[derive(Debug, Clone, Serialize)]
+pub struct LessonSummary {
+    #[serde(with = "uuid::serde::urn")]
+    pub session_id: Uuid,
+    pub key_points: Vec<String>,        // what the teacher explained
+    pub questions: Vec<LessonQuestion>, // what Bodhi asked
+    pub gaps: Vec<String>,              // what is still unclear or wasn't covered
+    pub follow_up: Vec<String>,         // what to teach or revise next
+    pub message_count: i64,
+    pub model: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// a `lesson_summaries` row exactly as SQLite stores it
+#[derive(Debug)]
+pub struct LessonSummaryRow {
+    pub session_id: String,
+    pub key_points: String, // JSON
+    pub questions: String,  // JSON
+    pub gaps: String,       // JSON
+    pub follow_up: String,  // JSON
+    pub message_count: i64,
+    pub model: String,
+    pub created_at: String,
+}
+
+impl TryFrom<LessonSummaryRow> for LessonSummary {
+    type Error = sqlx::Error;
+
+    fn try_from(row: LessonSummaryRow) -> Result<Self, Self::Error> {
+        Ok(LessonSummary {
+            session_id: Uuid::parse_str(&row.session_id)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            key_points: serde_json::from_str(&row.key_points)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            questions: serde_json::from_str(&row.questions)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            gaps: serde_json::from_str(&row.gaps).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            follow_up: serde_json::from_str(&row.follow_up)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            message_count: row.message_count,
+            model: row.model,
+            created_at: row
+                .created_at
+                .parse()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        })
+    }
+}
@@ -0,0 +1,271 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::ingestion::cleanup::CleanupReport;
+
+/// the kinds of files study material can be extracted from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterialFormat {
+    Pdf,
+    Docx,
+    Epub,
+    Markdown,
+    Html,
+    PlainText,
+    Srt,
+    Vtt,
+}
+
+impl MaterialFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MaterialFormat::Pdf => "pdf",
+            MaterialFormat::Docx => "docx",
+            MaterialFormat::Epub => "epub",
+            MaterialFormat::Markdown => "markdown",
+            MaterialFormat::Html => "html",
+            MaterialFormat::PlainText => "plain_text",
+            MaterialFormat::Srt => "srt",
+            MaterialFormat::Vtt => "vtt",
+        }
+    }
+
+    /// the format of a file extension, without the dot
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "pdf" => Some(MaterialFormat::Pdf),
+            "docx" => Some(MaterialFormat::Docx),
+            "epub" => Some(MaterialFormat::Epub),
+            "md" | "markdown" => Some(MaterialFormat::Markdown),
+            "html" | "htm" | "xhtml" => Some(MaterialFormat::Html),
+            "txt" | "text" => Some(MaterialFormat::PlainText),
+            "srt" => Some(MaterialFormat::Srt),
+            "vtt" => Some(MaterialFormat::Vtt),
+            _ => None,
+        }
+    }
+
+    /// the format of a MIME type, parameters like "; charset=utf-8" are ignored
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        match essence.to_ascii_lowercase().as_str() {
+            "application/pdf" => Some(MaterialFormat::Pdf),
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => {
+                Some(MaterialFormat::Docx)
+            }
+            "application/epub+zip" => Some(MaterialFormat::Epub),
+            "text/markdown" | "text/x-markdown" => Some(MaterialFormat::Markdown),
+            "text/html" | "application/xhtml+xml" => Some(MaterialFormat::Html),
+            "text/plain" => Some(MaterialFormat::PlainText),
+            "application/x-subrip" | "text/srt" => Some(MaterialFormat::Srt),
+            "text/vtt" => Some(MaterialFormat::Vtt),
+            _ => None,
+        }
+    }
+
+    /// formats that are read as UTF-8 text rather than parsed from a binary container
+    pub fn is_text(self) -> bool {
+        !matches!(
+            self,
+            MaterialFormat::Pdf | MaterialFormat::Docx | MaterialFormat::Epub
+        )
+    }
+}
+
+impl FromStr for MaterialFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pdf" => Ok(MaterialFormat::Pdf),
+            "docx" => Ok(MaterialFormat::Docx),
+            "epub" => Ok(MaterialFormat::Epub),
+            "markdown" => Ok(MaterialFormat::Markdown),
+            "html" => Ok(MaterialFormat::Html),
+            "plain_text" => Ok(MaterialFormat::PlainText),
+            "srt" => Ok(MaterialFormat::Srt),
+            "vtt" => Ok(MaterialFormat::Vtt),
+            _ => Err(format!("Invalid MaterialFormat: {}", s)),
+        }
+    }
+}
+
+/// a heading of the material, `offset` is where it starts in the extracted text (in bytes)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaterialSection {
+    pub title: String,
+    pub level: u8, // 1 for chapters and top headings, deeper levels count up
+    pub offset: usize,
+}
+
+/// where a page of a PDF starts in the extracted text (in bytes)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MaterialPage {
+    pub number: u32, // as printed in the PDF's page order, starting at 1
+    pub offset: usize,
+}
+
+/// a page of a session's material, `start` and `end` are where it is in the session's
+/// material text (in bytes)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourcePage {
+    pub source: String, // the material's source name
+    pub number: u32,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// the page of the material something came from, so the teacher can look it up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageCitation {
+    pub source: String,
+    pub page: u32,
+}
+
+/// one source of a session's study material
+#[derive(Debug, Clone, Serialize)]
+pub struct Material {
+    #[serde(with = "uuid::serde::urn")]
+    pub id: Uuid,
+    #[serde(with = "uuid::serde::urn")]
+    pub session_id: Uuid,
+    pub position: i64,
+    pub source_name: String,
+    pub format: MaterialFormat,
+    pub extracted_text: String,
+    pub page_count: Option<i64>,
+    pub sections: Vec<MaterialSection>,
+    pub pages: Vec<MaterialPage>, // PDFs only
+    pub created_at: DateTime<Utc>,
+}
+
+/// a `materials` row exactly as SQLite stores it
+#[derive(Debug)]
+pub struct MaterialRow {
+    pub id: String,
+    pub session_id: String,
+    pub position: i64,
+    pub source_name: String,
+    pub format: String,
+    pub extracted_text: String,
+    pub page_count: Option<i64>,
+    pub sections: Option<String>, // JSON
+    pub pages: Option<String>,    // JSON
+    pub created_at: String,
+}
+
+impl TryFrom<MaterialRow> for Material {
+    type Error = sqlx::Error;
+
+    fn try_from(row: MaterialRow) -> Result<Self, Self::Error> {
+        Ok(Material {
+            id: Uuid::parse_str(&row.id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            session_id: Uuid::parse_str(&row.session_id)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            position: row.position,
+            source_name: row.source_name,
+            format: row.format.parse().map_err(|e: String| {
+                sqlx::Error::Decode(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    e,
+                )))
+            })?,
+            extracted_text: row.extracted_text,
+            page_count: row.page_count,
+            sections: row
+                .sections
+                .map(|json| serde_json::from_str(&json))
+                .transpose()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+                .unwrap_or_default(),
+            pages: row
+                .pages
+                .map(|json| serde_json::from_str(&json))
+                .transpose()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+                .unwrap_or_default(),
+            created_at: row
+                .created_at
+                .parse()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        })
+    }
+}
+
+impl Material {
+    /// the material's pages as pages of a session whose text has it at `shift`
+    pub fn source_pages(&self, shift: usize) -> Vec<SourcePage> {
+        source_pages(
+            &self.source_name,
+            &self.pages,
+            self.extracted_text.len(),
+            shift,
+        )
+    }
+}
+
+// every page ends where the next one starts, the last one with the text
+fn source_pages(
+    source: &str,
+    pages: &[MaterialPage],
+    text_len: usize,
+    shift: usize,
+) -> Vec<SourcePage> {
+    pages
+        .iter()
+        .enumerate()
+        .map(|(i, page)| SourcePage {
+            source: source.to_string(),
+            number: page.number,
+            start: page.offset + shift,
+            end: pages.get(i + 1).map_or(text_len, |next| next.offset) + shift,
+        })
+        .collect()
+}
+
+/// a material to add to a session
+#[derive(Debug, Clone)]
+pub struct CreateMaterial {
+    pub source_name: String,
+    pub format: MaterialFormat,
+    pub extracted_text: String,
+    pub page_count: Option<i64>,
+    pub sections: Vec<MaterialSection>,
+    pub pages: Vec<MaterialPage>,
+}
+
+impl CreateMaterial {
+    /// material typed or pasted into the new lesson form
+    pub fn pasted(text: String) -> Self {
+        CreateMaterial {
+            source_name: "Pasted text".to_string(),
+            format: MaterialFormat::PlainText,
+            extracted_text: text,
+            page_count: None,
+            sections: Vec::new(),
+            pages: Vec::new(),
+        }
+    }
+
+    /// the material's pages as pages of a session whose text starts with it at `shift`
+    pub fn source_pages(&self, shift: usize) -> Vec<SourcePage> {
+        source_pages(
+            &self.source_name,
+            &self.pages,
+            self.extracted_text.len(),
+            shift,
+        )
+    }
+}
+
+/// an upload's text as extracted and after the cleanup, to check the cleanup on a file
+#[derive(Debug, Serialize)]
+pub struct MaterialPreview {
+    pub format: MaterialFormat,
+    pub before: String,
+    pub after: String,
+    pub cleanup: CleanupReport,
+}
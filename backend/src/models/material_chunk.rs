@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+/// one passage of a session's study material
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialChunk {
+    pub chunk_index: i64,
+    pub content: String,
+}
@@ -1,9 +1,14 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use sqlx::FromRow;
 use std::str::FromStr;
 use uuid::Uuid;
 
+use crate::models::{
+    clarity::ClarityScore, debate::DebateTurn, material::PageCitation,
+    misconception::Misconception, quiz::QuizMessageMeta, turn::TurnSignals, usage::MessageUsage,
+};
+
 // represents the two possible roles in a conversation
 #[derive(Debug, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "TEXT")]
@@ -35,6 +40,14 @@ pub struct Message {
     pub role: MessageRole,
     pub content: String,
     pub timestamp: DateTime<Utc>,
+    pub is_partial: bool, // true if the reply stream was interrupted before it finished
+    pub misconceptions: Vec<Misconception>, // contradictions with the material, teacher messages only
+    pub usage: Option<MessageUsage>,        // cost of generating it, assistant messages only
+    pub signals: Option<TurnSignals>,       // structured replies only
+    pub quiz: Option<QuizMessageMeta>,      // quiz sessions only
+    pub debate: Option<DebateTurn>,         // Bodhi's debate replies only
+    pub clarity: Option<ClarityScore>,      // teacher messages only
+    pub citations: Vec<PageCitation>,       // pages of the material Bodhi's reply refers to
 }
 
 impl Message {
@@ -45,6 +58,8 @@ impl Message {
         role: String,
         content: String,
         timestamp: String,
+        is_partial: bool,
+        misconceptions: Option<String>,
     ) -> Result<Self, sqlx::Error> {
         Ok(Message {
             id: Uuid::parse_str(&id.unwrap()).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
@@ -60,13 +75,75 @@ impl Message {
             timestamp: timestamp
                 .parse::<DateTime<Utc>>()
                 .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            is_partial,
+            misconceptions: decode_misconceptions(misconceptions)?,
+            usage: None,
+            signals: None,
+            quiz: None,
+            debate: None,
+            clarity: None,
+            citations: Vec::new(),
         })
     }
 }
 
+/// parses the JSON `misconceptions` column, NULL means the message wasn't checked
+pub fn decode_misconceptions(json: Option<String>) -> Result<Vec<Misconception>, sqlx::Error> {
+    json.map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+}
+
+/// rebuilds the signals of a structured reply from their columns, NULL `understanding`
+/// means the reply was free text
+pub fn decode_signals(
+    understanding: Option<i64>,
+    confusions: Option<String>,
+    concept: Option<String>,
+) -> Result<Option<TurnSignals>, sqlx::Error> {
+    let Some(understanding) = understanding else {
+        return Ok(None);
+    };
+
+    let confusions = confusions
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+        .unwrap_or_default();
+
+    Ok(Some(TurnSignals {
+        understanding,
+        confusions,
+        concept,
+    }))
+}
+
+/// parses an optional JSON metadata column like `quiz`, `debate` or `clarity`
+pub fn decode_meta<T: DeserializeOwned>(json: Option<String>) -> Result<Option<T>, sqlx::Error> {
+    json.map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+}
+
 // represents the data we expect from the client to post a new message
 #[derive(Debug, Deserialize)]
 pub struct CreateMessage {
     pub role: MessageRole,
     pub content: String,
+    // set by the server, never by the client
+    #[serde(skip)]
+    pub quiz: Option<QuizMessageMeta>,
+    #[serde(skip)]
+    pub debate: Option<DebateTurn>,
+    #[serde(skip)]
+    pub clarity: Option<ClarityScore>,
+    #[serde(skip)]
+    pub citations: Vec<PageCitation>,
+}
+
+// represents the teacher's message sent along with a streaming request
+#[derive(Debug, Deserialize)]
+pub struct StreamMessage {
+    pub content: String,
 }
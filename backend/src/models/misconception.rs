@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::material::PageCitation;
+
+/// something the teacher said that contradicts the study material
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Misconception {
+    pub claim: String,      // what the teacher said
+    pub correction: String, // what the material says instead
+    #[serde(default)]
+    pub evidence: String, // quote from the material
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<PageCitation>, // where the quote is, PDF materials only
+}
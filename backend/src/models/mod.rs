@@ -1,2 +1,17 @@
+pub mod clarity;
+pub mod concept;
+pub mod conversation_summary;
+pub mod debate;
+pub mod evaluation;
+pub mod generation;
+pub mod ingestion;
+pub mod lesson_summary;
+pub mod material;
+pub mod material_chunk;
 pub mod message;
+pub mod misconception;
+pub mod persona;
+pub mod quiz;
 pub mod session;
+pub mod turn;
+pub mod usage;
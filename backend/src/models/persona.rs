@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// a student personality Bodhi can take on for a session
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Persona {
+    pub id: String, // slug, e.g. "curious-child"
+    pub name: String,
+    pub level: String,
+    pub personality: String, // one trait per line
+    pub question_style: String,
+    pub verbosity: String,
+}
+
+impl Persona {
+    pub const DEFAULT_ID: &'static str = "eager-student";
+}
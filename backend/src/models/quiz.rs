@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+pub const MIN_QUESTION_SCORE: i64 = 0;
+pub const MAX_QUESTION_SCORE: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestionKind {
+    MultipleChoice,
+    ShortAnswer,
+    ExplainWhy,
+}
+
+impl QuestionKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QuestionKind::MultipleChoice => "multiple_choice",
+            QuestionKind::ShortAnswer => "short_answer",
+            QuestionKind::ExplainWhy => "explain_why",
+        }
+    }
+
+    /// how the kind is shown to the user
+    pub fn label(self) -> &'static str {
+        match self {
+            QuestionKind::MultipleChoice => "multiple choice",
+            QuestionKind::ShortAnswer => "short answer",
+            QuestionKind::ExplainWhy => "explain why",
+        }
+    }
+}
+
+impl FromStr for QuestionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "multiple_choice" => Ok(QuestionKind::MultipleChoice),
+            "short_answer" => Ok(QuestionKind::ShortAnswer),
+            "explain_why" => Ok(QuestionKind::ExplainWhy),
+            _ => Err(format!("Invalid QuestionKind: {}", s)),
+        }
+    }
+}
+
+/// a question as the generating model returns it
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuizQuestionDraft {
+    pub kind: QuestionKind,
+    pub question: String,
+    #[serde(default)]
+    pub options: Vec<String>,
+    pub answer: String,
+    #[serde(default)]
+    pub evidence: String,
+}
+
+/// one question of a quiz session
+#[derive(Debug, Clone)]
+pub struct QuizQuestion {
+    pub id: Uuid,
+    pub position: i64, // starting at 1
+    pub kind: QuestionKind,
+    pub question: String,
+    pub options: Vec<String>, // multiple choice only
+    pub answer: String,
+    pub evidence: String,
+    pub asked: bool,
+    pub score: Option<i64>, // None until the answer is graded
+}
+
+/// a `quiz_questions` row exactly as SQLite stores it
+#[derive(Debug)]
+pub struct QuizQuestionRow {
+    pub id: String,
+    pub position: i64,
+    pub kind: String,
+    pub question: String,
+    pub options: String, // JSON
+    pub answer: String,
+    pub evidence: String,
+    pub asked_at: Option<String>,
+    pub score: Option<i64>,
+}
+
+impl TryFrom<QuizQuestionRow> for QuizQuestion {
+    type Error = sqlx::Error;
+
+    fn try_from(row: QuizQuestionRow) -> Result<Self, Self::Error> {
+        Ok(QuizQuestion {
+            id: Uuid::parse_str(&row.id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            position: row.position,
+            kind: row.kind.parse().map_err(|e: String| {
+                sqlx::Error::Decode(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    e,
+                )))
+            })?,
+            question: row.question,
+            options: serde_json::from_str(&row.options)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            answer: row.answer,
+            evidence: row.evidence,
+            asked: row.asked_at.is_some(),
+            score: row.score,
+        })
+    }
+}
+
+/// the grade of an answer as the grading model returns it
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuizGrade {
+    pub score: i64,
+    pub feedback: String,
+}
+
+/// what a message of a quiz session is about, stored with the message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizMessageMeta {
+    #[serde(with = "uuid::serde::urn")]
+    pub question_id: Uuid,
+    pub position: i64,
+    pub total: i64,         // questions in the quiz
+    pub score: Option<i64>, // the user's answers only
+}
@@ -1,38 +1,213 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::{fmt, str::FromStr};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+use crate::models::{
+    generation::GenerationSettings,
+    ingestion::CreateIngestionJob,
+    material::{CreateMaterial, MaterialSection, SourcePage},
+};
+
+/// what the user does in a session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionMode {
+    /// the user teaches the material to Bodhi
+    #[default]
+    Teach,
+    /// Bodhi asks the user questions about the material and grades the answers
+    Quiz,
+    /// Bodhi argues a position from the material against the user's
+    Debate,
+}
+
+impl SessionMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SessionMode::Teach => "teach",
+            SessionMode::Quiz => "quiz",
+            SessionMode::Debate => "debate",
+        }
+    }
+}
+
+impl FromStr for SessionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "teach" => Ok(SessionMode::Teach),
+            "quiz" => Ok(SessionMode::Quiz),
+            "debate" => Ok(SessionMode::Debate),
+            _ => Err(format!("Invalid SessionMode: {}", s)),
+        }
+    }
+}
+
+/// where a session is in its life, from processing its upload to being archived
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionStatus {
+    /// the uploaded material is still being extracted and indexed
+    Processing,
+    /// the material is ready and no message has been sent yet
+    Ready,
+    /// the upload couldn't be turned into material
+    Failed,
+    /// the teacher has started teaching
+    Active,
+    /// the teacher finished the lesson
+    Completed,
+    /// put away, no longer taking messages
+    Archived,
+}
+
+impl SessionStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SessionStatus::Processing => "processing",
+            SessionStatus::Ready => "ready",
+            SessionStatus::Failed => "failed",
+            SessionStatus::Active => "active",
+            SessionStatus::Completed => "completed",
+            SessionStatus::Archived => "archived",
+        }
+    }
+
+    /// whether a session may move from this status to `next`
+    pub fn can_become(self, next: SessionStatus) -> bool {
+        use SessionStatus::*;
+        matches!(
+            (self, next),
+            (Processing, Ready | Failed)
+                | (Ready, Active | Archived)
+                | (Active, Completed | Archived)
+                | (Completed, Active | Archived)
+                | (Failed, Archived)
+        )
+    }
+
+    /// whether the session has material to teach, i.e. it isn't processing or failed
+    pub fn has_material(self) -> bool {
+        !matches!(self, SessionStatus::Processing | SessionStatus::Failed)
+    }
+
+    /// why a session with this status doesn't take messages, if it doesn't
+    pub fn closed_reason(self) -> Option<&'static str> {
+        match self {
+            SessionStatus::Processing => Some("The session's material is still being processed"),
+            SessionStatus::Failed => Some("The session's material couldn't be processed"),
+            SessionStatus::Archived => Some("The session is archived"),
+            SessionStatus::Ready | SessionStatus::Active | SessionStatus::Completed => None,
+        }
+    }
+}
+
+impl fmt::Display for SessionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SessionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "processing" => Ok(SessionStatus::Processing),
+            "ready" => Ok(SessionStatus::Ready),
+            "failed" => Ok(SessionStatus::Failed),
+            "active" => Ok(SessionStatus::Active),
+            "completed" => Ok(SessionStatus::Completed),
+            "archived" => Ok(SessionStatus::Archived),
+            _ => Err(format!("Invalid SessionStatus: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Session {
     #[serde(with = "uuid::serde::urn")]
     pub id: Uuid,
     pub topic: String,
     pub material_text: String,
-    pub status: String, // we can make this an enum later
+    pub status: SessionStatus,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub user_id: String, // We'll add this when auth is implemented
+    pub persona_id: String,
+    pub generation_settings: Option<GenerationSettings>, // overrides of the app defaults
+    pub mode: SessionMode,
+    pub material_sections: Vec<MaterialSection>, // headings of the materials, empty for pasted text
+    pub material_pages: Vec<SourcePage>,         // page starts of PDF materials
+}
+
+/// a `sessions` row exactly as SQLite stores it, before parsing ids and timestamps
+#[derive(Debug, FromRow)]
+pub struct SessionRow {
+    pub id: String,
+    pub topic: String,
+    pub material_text: String,
+    pub status: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub user_id: Option<String>,
+    pub persona_id: String,
+    pub generation_settings: Option<String>, // JSON
+    pub mode: String,
+    pub material_sections: Option<String>, // JSON
+    pub material_pages: Option<String>,    // JSON
 }
 
-impl Session {
-    pub fn from_strings(
-        id: String,
-        topic: String,
-        material_text: String,
-        status: String,
-        created_at: String,
-        updated_at: String,
-        user_id: String,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
+impl TryFrom<SessionRow> for Session {
+    type Error = sqlx::Error;
+
+    fn try_from(row: SessionRow) -> Result<Self, Self::Error> {
         Ok(Session {
-            id: Uuid::parse_str(&id)?,
-            topic,
-            material_text,
-            status,
-            created_at: created_at.parse()?,
-            updated_at: updated_at.parse()?,
-            user_id,
+            id: Uuid::parse_str(&row.id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            topic: row.topic,
+            material_text: row.material_text,
+            status: row.status.parse().map_err(|e: String| {
+                sqlx::Error::Decode(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    e,
+                )))
+            })?,
+            created_at: row
+                .created_at
+                .parse()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            updated_at: row
+                .updated_at
+                .parse()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            user_id: row.user_id.unwrap_or_default(),
+            persona_id: row.persona_id,
+            generation_settings: row
+                .generation_settings
+                .map(|json| serde_json::from_str(&json))
+                .transpose()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            mode: row.mode.parse().map_err(|e: String| {
+                sqlx::Error::Decode(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    e,
+                )))
+            })?,
+            material_sections: row
+                .material_sections
+                .map(|json| serde_json::from_str(&json))
+                .transpose()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+                .unwrap_or_default(),
+            material_pages: row
+                .material_pages
+                .map(|json| serde_json::from_str(&json))
+                .transpose()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+                .unwrap_or_default(),
         })
     }
 }
@@ -42,4 +217,18 @@ impl Session {
 pub struct CreateSession {
     pub topic: String,
     pub material_text: String,
+    pub persona_id: Option<String>, // falls back to the default persona
+    pub generation_settings: Option<GenerationSettings>,
+    #[serde(default)]
+    pub mode: SessionMode,
+    #[serde(skip)]
+    pub source: Option<CreateMaterial>, // the uploaded file the text came from, pasted text otherwise
+    #[serde(skip)]
+    pub upload: Option<CreateIngestionJob>, // a file still to be processed, the session starts empty
+}
+
+/// the statuses the teacher sets, the others follow the processing of the material
+#[derive(Debug, Deserialize)]
+pub struct UpdateSessionStatus {
+    pub status: SessionStatus,
 }
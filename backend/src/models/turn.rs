@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+pub const MIN_UNDERSTANDING: i64 = 0;
+pub const MAX_UNDERSTANDING: i64 = 100;
+
+/// machine readable extras of a structured Bodhi reply
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnSignals {
+    pub understanding: i64, // how well Bodhi says it understands the topic so far
+    pub confusions: Vec<String>, // points Bodhi is still unsure about
+    pub concept: Option<String>, // what Bodhi's reply asks about
+}
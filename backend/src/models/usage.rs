@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// what generating one assistant message cost and how long it took
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageUsage {
+    pub model: String,
+    pub prompt_tokens: Option<i64>, // None when the provider doesn't report usage
+    pub response_tokens: Option<i64>,
+    pub finish_reason: Option<String>,
+    pub latency_ms: i64,
+}
+
+/// why the model was called, for the calls that aren't one of Bodhi's replies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallPurpose {
+    MisconceptionCheck,
+    ConceptExtraction,
+    ConceptMapping,
+    /// folding older turns into the rolling summary
+    HistorySummary,
+    Evaluation,
+    LessonNotes,
+    QuizQuestions,
+}
+
+impl CallPurpose {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CallPurpose::MisconceptionCheck => "misconception_check",
+            CallPurpose::ConceptExtraction => "concept_extraction",
+            CallPurpose::ConceptMapping => "concept_mapping",
+            CallPurpose::HistorySummary => "history_summary",
+            CallPurpose::Evaluation => "evaluation",
+            CallPurpose::LessonNotes => "lesson_notes",
+            CallPurpose::QuizQuestions => "quiz_questions",
+        }
+    }
+}
+
+/// usage totals of one session, its replies and the calls behind them
+#[derive(Debug, Serialize)]
+pub struct SessionUsage {
+    #[serde(with = "uuid::serde::urn")]
+    pub session_id: Uuid,
+    pub topic: String,
+    pub messages: i64,
+    pub calls: i64, // e.g. misconception checks and summaries
+    pub prompt_tokens: i64,
+    pub response_tokens: i64,
+    pub avg_latency_ms: f64, // of the replies
+}
+
+/// usage totals of one model on one UTC day
+#[derive(Debug, Serialize)]
+pub struct DailyUsage {
+    pub day: String, // YYYY-MM-DD
+    pub model: String,
+    pub messages: i64,
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub response_tokens: i64,
+    pub avg_latency_ms: f64, // of the replies
+}
+
+/// usage totals of one purpose, Bodhi's replies are "reply"
+#[derive(Debug, Serialize)]
+pub struct PurposeUsage {
+    pub purpose: String,
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub response_tokens: i64,
+    pub avg_latency_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageReport {
+    pub by_session: Vec<SessionUsage>,
+    pub by_day: Vec<DailyUsage>,
+    pub by_purpose: Vec<PurposeUsage>,
+}
@@ -0,0 +1,110 @@
+// roughly 200 tokens, small enough that several passages fit into one prompt
+const TARGET_CHUNK_CHARS: usize = 800;
+
+/// splits material into passages of about `TARGET_CHUNK_CHARS`, packing whole
+/// paragraphs together and only breaking overly long paragraphs between sentences
+pub fn chunk_material(material: &str) -> Vec<String> {
+    let normalized = material.replace("\r\n", "\n");
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for paragraph in normalized.split("\n\n").map(str::trim) {
+        if paragraph.is_empty() {
+            continue;
+        }
+
+        let pieces = if paragraph.chars().count() > TARGET_CHUNK_CHARS {
+            split_sentences(paragraph)
+        } else {
+            vec![paragraph.to_string()]
+        };
+
+        for piece in pieces {
+            if !current.is_empty()
+                && current.chars().count() + piece.chars().count() > TARGET_CHUNK_CHARS
+            {
+                chunks.push(std::mem::take(&mut current));
+            }
+
+            if !current.is_empty() {
+                current.push_str("\n\n");
+            }
+            current.push_str(&piece);
+        }
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+fn split_sentences(paragraph: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+
+    let mut chars = paragraph.chars().peekable();
+    while let Some(c) = chars.next() {
+        current.push(c);
+
+        let at_boundary =
+            matches!(c, '.' | '?' | '!') && chars.peek().is_none_or(|next| next.is_whitespace());
+        if at_boundary {
+            sentences.push(current.trim().to_string());
+            current.clear();
+        }
+    }
+
+    if !current.trim().is_empty() {
+        sentences.push(current.trim().to_string());
+    }
+
+    sentences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_short_paragraphs_together() {
+        let chunks = chunk_material("Cells are small.\r\n\r\n\n\nThey divide.\n\nThey grow.");
+
+        assert_eq!(chunks, ["Cells are small.\n\nThey divide.\n\nThey grow."]);
+    }
+
+    #[test]
+    fn starts_a_new_chunk_when_the_next_paragraph_does_not_fit() {
+        let first = "a".repeat(500);
+        let second = "b".repeat(500);
+
+        let chunks = chunk_material(&format!("{}\n\n{}", first, second));
+
+        assert_eq!(chunks, [first, second]);
+    }
+
+    #[test]
+    fn splits_long_paragraphs_between_sentences() {
+        let sentence = "The membrane lets some molecules into the cell and keeps others out.";
+        let paragraph = vec![sentence; 20].join(" ");
+
+        let chunks = chunk_material(&paragraph);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.chars().count() <= TARGET_CHUNK_CHARS);
+            assert!(chunk.ends_with('.'));
+        }
+        let sentences: Vec<&str> = chunks
+            .iter()
+            .flat_map(|chunk| chunk.split("\n\n"))
+            .collect();
+        assert_eq!(sentences, vec![sentence; 20]);
+    }
+
+    #[test]
+    fn has_no_chunks_for_blank_material() {
+        assert!(chunk_material(" \n\n \n").is_empty());
+    }
+}
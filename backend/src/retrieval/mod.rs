@@ -0,0 +1,340 @@
+use std::{collections::HashSet, env, sync::Arc};
+
+use anyhow::anyhow;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    database::material_chunks::{
+        count_chunks, list_chunks_with_embeddings, replace_chunks, search_chunks,
+    },
+    handlers::ai::context::estimate_tokens,
+    models::material_chunk::MaterialChunk,
+};
+
+pub mod chunking;
+pub mod embeddings;
+
+use chunking::chunk_material;
+use embeddings::{Embedder, HashEmbedder, cosine_similarity};
+
+pub type SharedEmbedder = Arc<dyn Embedder>;
+
+// enough candidates to fill any prompt budget we hand out
+const MAX_CANDIDATES: i64 = 24;
+// keeps the FTS query small for rambling teacher messages
+const MAX_QUERY_TERMS: usize = 32;
+const HASH_EMBEDDING_DIMENSIONS: usize = 256;
+
+/// finds the passages of a session's material that matter for the next Bodhi turn.
+///
+/// chunks are always indexed with SQLite FTS5; with an embedder configured they also
+/// get a vector and are ranked by cosine similarity instead of BM25.
+#[derive(Clone)]
+pub struct Retriever {
+    embedder: Option<SharedEmbedder>,
+}
+
+impl Retriever {
+    pub fn bm25() -> Self {
+        Retriever { embedder: None }
+    }
+
+    pub fn with_embedder(embedder: SharedEmbedder) -> Self {
+        Retriever {
+            embedder: Some(embedder),
+        }
+    }
+
+    /// picks the backend from `RETRIEVAL_BACKEND`: `bm25` (default) or `hash`
+    pub fn from_env() -> Result<Self, anyhow::Error> {
+        let backend = env::var("RETRIEVAL_BACKEND").unwrap_or_else(|_| "bm25".to_string());
+
+        match backend.as_str() {
+            "bm25" => Ok(Retriever::bm25()),
+            "hash" => Ok(Retriever::with_embedder(Arc::new(HashEmbedder::new(
+                HASH_EMBEDDING_DIMENSIONS,
+            )))),
+            other => Err(anyhow!("Unknown RETRIEVAL_BACKEND '{}'", other)),
+        }
+    }
+
+    /// splits `material` into chunks and (re)builds the session's index, returns the chunk count
+    pub async fn index(
+        &self,
+        pool: &SqlitePool,
+        session_id: Uuid,
+        material: &str,
+    ) -> Result<usize, anyhow::Error> {
+        let chunks = chunk_material(material);
+
+        let embeddings = match &self.embedder {
+            Some(embedder) => embedder
+                .embed(&chunks)
+                .await?
+                .into_iter()
+                .map(Some)
+                .collect(),
+            None => vec![None; chunks.len()],
+        };
+
+        let count = chunks.len();
+        replace_chunks(
+            pool,
+            session_id,
+            chunks.into_iter().zip(embeddings).collect(),
+        )
+        .await?;
+
+        Ok(count)
+    }
+
+    /// the passages most relevant to `query` that fit into `max_tokens`, in material order.
+    /// sessions created before chunking existed are indexed on first use.
+    pub async fn retrieve(
+        &self,
+        pool: &SqlitePool,
+        session_id: Uuid,
+        material: &str,
+        query: &str,
+        max_tokens: usize,
+    ) -> Result<Vec<MaterialChunk>, anyhow::Error> {
+        if count_chunks(pool, session_id).await? == 0 {
+            self.index(pool, session_id, material).await?;
+        }
+
+        let mut ranked = match &self.embedder {
+            Some(embedder) => rank_by_embedding(embedder, pool, session_id, query).await?,
+            None => match fts_query(query) {
+                Some(match_query) => {
+                    search_chunks(pool, session_id, &match_query, MAX_CANDIDATES).await?
+                }
+                None => Vec::new(),
+            },
+        };
+
+        // nothing matched (e.g. "hi!"), start from the beginning of the material
+        if ranked.is_empty() {
+            ranked = list_chunks_with_embeddings(pool, session_id)
+                .await?
+                .into_iter()
+                .map(|(chunk, _)| chunk)
+                .collect();
+        }
+
+        let mut kept = Vec::new();
+        let mut used = 0;
+        for chunk in ranked {
+            let cost = estimate_tokens(&chunk.content);
+            if used + cost > max_tokens {
+                continue;
+            }
+            used += cost;
+            kept.push(chunk);
+        }
+        kept.sort_by_key(|chunk| chunk.chunk_index);
+
+        Ok(kept)
+    }
+}
+
+/// ranks every chunk by cosine similarity to the query
+async fn rank_by_embedding(
+    embedder: &SharedEmbedder,
+    pool: &SqlitePool,
+    session_id: Uuid,
+    query: &str,
+) -> Result<Vec<MaterialChunk>, anyhow::Error> {
+    let query_vector = embedder
+        .embed(&[query.to_string()])
+        .await?
+        .pop()
+        .ok_or_else(|| anyhow!("Embedder returned no vector for the query"))?;
+
+    let mut scored: Vec<(f32, MaterialChunk)> = list_chunks_with_embeddings(pool, session_id)
+        .await?
+        .into_iter()
+        .filter_map(|(chunk, embedding)| {
+            embedding.map(|vector| (cosine_similarity(&query_vector, &vector), chunk))
+        })
+        .filter(|(score, _)| *score > 0.0)
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    Ok(scored
+        .into_iter()
+        .take(MAX_CANDIDATES as usize)
+        .map(|(_, chunk)| chunk)
+        .collect())
+}
+
+/// turns free text into an FTS5 query that matches any of its words. every term is
+/// quoted so punctuation and FTS operators in the teacher's message can't break it.
+fn fts_query(text: &str) -> Option<String> {
+    let mut seen = HashSet::new();
+    let terms: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 2)
+        .map(str::to_lowercase)
+        .filter(|word| seen.insert(word.clone()))
+        .take(MAX_QUERY_TERMS)
+        .map(|word| format!("\"{}\"", word))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" OR "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::{
+        database::sessions::create_session,
+        models::session::{CreateSession, SessionMode},
+    };
+
+    // paragraphs too long to share a chunk, each about something else
+    const MEMBRANE: &str = "The membrane wraps the cell and lets some molecules through. ";
+    const MITOCHONDRIA: &str = "Mitochondria release the energy the cell runs on. ";
+    const PHOTOSYNTHESIS: &str = "Chloroplasts turn sunlight into sugar by photosynthesis. ";
+
+    fn material() -> String {
+        [MEMBRANE, MITOCHONDRIA, PHOTOSYNTHESIS]
+            .map(|sentence| sentence.repeat(8).trim().to_string())
+            .join("\n\n")
+    }
+
+    async fn test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!().run(&pool).await.unwrap();
+        pool
+    }
+
+    async fn indexed_session(pool: &SqlitePool, retriever: &Retriever) -> Uuid {
+        let session = create_session(
+            pool,
+            CreateSession {
+                topic: "Cells".to_string(),
+                material_text: material(),
+                persona_id: None,
+                generation_settings: None,
+                mode: SessionMode::Teach,
+                source: None,
+                upload: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            retriever
+                .index(pool, session.id, &material())
+                .await
+                .unwrap(),
+            3
+        );
+        session.id
+    }
+
+    fn hash_retriever() -> Retriever {
+        Retriever::with_embedder(Arc::new(HashEmbedder::new(HASH_EMBEDDING_DIMENSIONS)))
+    }
+
+    fn positions(chunks: &[MaterialChunk]) -> Vec<i64> {
+        chunks.iter().map(|chunk| chunk.chunk_index).collect()
+    }
+
+    #[test]
+    fn quotes_every_term_of_the_fts_query() {
+        assert_eq!(
+            fts_query("Why do CELLS need cells' energy? (NOT sure)").as_deref(),
+            Some(r#""why" OR "cells" OR "need" OR "energy" OR "not" OR "sure""#)
+        );
+        assert_eq!(fts_query("hi!"), None);
+    }
+
+    #[tokio::test]
+    async fn bm25_ranks_the_passage_about_the_query_first() {
+        let pool = test_pool().await;
+        let session_id = indexed_session(&pool, &Retriever::bm25()).await;
+
+        let query = fts_query("Where does the cell get its energy?").unwrap();
+        let ranked = search_chunks(&pool, session_id, &query, MAX_CANDIDATES)
+            .await
+            .unwrap();
+
+        assert_eq!(positions(&ranked)[0], 1);
+        assert!(ranked[0].content.starts_with("Mitochondria"));
+    }
+
+    #[tokio::test]
+    async fn bm25_only_ranks_the_sessions_current_chunks() {
+        let pool = test_pool().await;
+        let retriever = Retriever::bm25();
+        let first = indexed_session(&pool, &retriever).await;
+        let second = indexed_session(&pool, &retriever).await;
+        retriever.index(&pool, first, MEMBRANE).await.unwrap();
+
+        let query = fts_query("sunlight sugar").unwrap();
+        let search = |session_id| search_chunks(&pool, session_id, &query, MAX_CANDIDATES);
+
+        assert!(search(first).await.unwrap().is_empty());
+        let ranked = search(second).await.unwrap();
+        assert_eq!(positions(&ranked), [2]);
+        assert!(ranked[0].content.starts_with("Chloroplasts"));
+    }
+
+    #[tokio::test]
+    async fn hash_embeddings_rank_the_passage_about_the_query_first() {
+        let pool = test_pool().await;
+        let retriever = hash_retriever();
+        let session_id = indexed_session(&pool, &retriever).await;
+        let embedder = retriever.embedder.as_ref().unwrap();
+
+        let ranked = rank_by_embedding(
+            embedder,
+            &pool,
+            session_id,
+            "What do chloroplasts make from sunlight?",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(positions(&ranked)[0], 2);
+    }
+
+    #[tokio::test]
+    async fn retrieves_what_fits_the_budget_in_material_order() {
+        let pool = test_pool().await;
+        let retriever = hash_retriever();
+        let session_id = indexed_session(&pool, &retriever).await;
+        let one_passage = estimate_tokens(&MITOCHONDRIA.repeat(8));
+
+        let kept = retriever
+            .retrieve(
+                &pool,
+                session_id,
+                &material(),
+                "mitochondria energy",
+                one_passage,
+            )
+            .await
+            .unwrap();
+        assert_eq!(positions(&kept), [1]);
+
+        // nothing to match, the material is read from its start
+        let kept = retriever
+            .retrieve(&pool, session_id, &material(), "hi!", 10_000)
+            .await
+            .unwrap();
+        assert_eq!(positions(&kept), [0, 1, 2]);
+    }
+}
@@ -0,0 +1,40 @@
+use axum::extract::FromRef;
+use sqlx::SqlitePool;
+
+use crate::{
+    handlers::ai::providers::SharedLlmProvider, ingestion::worker::IngestionQueue,
+    retrieval::Retriever,
+};
+
+/// shared state handed to every handler; each field can be extracted on its own via `State<T>`
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: SqlitePool,
+    pub llm: SharedLlmProvider,
+    pub retriever: Retriever,
+    pub ingestion: IngestionQueue,
+}
+
+impl FromRef<AppState> for SqlitePool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for SharedLlmProvider {
+    fn from_ref(state: &AppState) -> Self {
+        state.llm.clone()
+    }
+}
+
+impl FromRef<AppState> for Retriever {
+    fn from_ref(state: &AppState) -> Self {
+        state.retriever.clone()
+    }
+}
+
+impl FromRef<AppState> for IngestionQueue {
+    fn from_ref(state: &AppState) -> Self {
+        state.ingestion.clone()
+    }
+}
@@ -0,0 +1,415 @@
+//! a chat turn end to end: the router on an in-memory database, with Bodhi's replies
+//! scripted through the mock provider
+
+use std::{sync::Arc, time::Duration};
+
+use aazan::{
+    database::messages::list_messages_for_session,
+    handlers::ai::{
+        error::AiError,
+        providers::{
+            Completion, LlmProvider, LlmRequest, SharedLlmProvider, StreamItem,
+            mock::{MockFailure, MockProvider, MockReply},
+            resilient::{ResilientProvider, RetryPolicy},
+        },
+        turn::bodhi_turn_schema,
+    },
+    ingestion::worker::IngestionQueue,
+    models::{
+        message::{Message, MessageRole},
+        session::{Session, SessionStatus},
+    },
+    retrieval::Retriever,
+    router,
+    state::AppState,
+};
+use async_trait::async_trait;
+use futures_util::{StreamExt, stream::BoxStream};
+use reqwest::StatusCode;
+use serde_json::json;
+use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
+use uuid::Uuid;
+
+const MATERIAL: &str = "Cells are the smallest units of life. The membrane wraps the cell \
+    and the nucleus holds its DNA. Mitochondria release the energy the cell runs on.";
+const TEACHER_MESSAGE: &str = "Every cell is wrapped in a membrane that lets some things through.";
+
+/// Bodhi's replies come from the script, the checks and concept mapping that run
+/// alongside them get the unscripted mock so they can't take a scripted reply
+struct ScriptedBodhi {
+    bodhi: SharedLlmProvider,
+    side: MockProvider,
+}
+
+impl ScriptedBodhi {
+    fn provider(&self, request: &LlmRequest) -> &dyn LlmProvider {
+        let is_bodhi = request
+            .response_schema
+            .as_ref()
+            .is_some_and(|schema| schema.name == bodhi_turn_schema().name);
+        if is_bodhi {
+            self.bodhi.as_ref()
+        } else {
+            &self.side
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ScriptedBodhi {
+    fn model(&self) -> &str {
+        self.bodhi.model()
+    }
+
+    async fn complete(&self, request: LlmRequest) -> Result<Completion, AiError> {
+        self.provider(&request).complete(request).await
+    }
+
+    fn stream(&self, request: LlmRequest) -> BoxStream<'static, Result<StreamItem, AiError>> {
+        self.provider(&request).stream(request)
+    }
+}
+
+struct TestApp {
+    base_url: String,
+    pool: SqlitePool,
+    client: reqwest::Client,
+}
+
+/// serves the app on a free port, Bodhi's replies retried as the server does
+async fn spawn_app(bodhi: MockProvider) -> TestApp {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!().run(&pool).await.unwrap();
+
+    let policy = RetryPolicy {
+        max_retries: 2,
+        base_delay: Duration::from_millis(10),
+        ..RetryPolicy::default()
+    };
+    let llm: SharedLlmProvider = Arc::new(ScriptedBodhi {
+        bodhi: Arc::new(ResilientProvider::new(Arc::new(bodhi), policy, 4)),
+        side: MockProvider::new(),
+    });
+    let retriever = Retriever::bm25();
+    let state = AppState {
+        ingestion: IngestionQueue::start(pool.clone(), llm.clone(), retriever.clone()),
+        pool: pool.clone(),
+        llm,
+        retriever,
+    };
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router(state)).await.unwrap() });
+
+    TestApp {
+        base_url,
+        pool,
+        client: reqwest::Client::new(),
+    }
+}
+
+impl TestApp {
+    async fn create_session(&self) -> Session {
+        let response = self
+            .client
+            .post(format!("{}/api/sessions", self.base_url))
+            .json(&json!({ "topic": "Cells", "material_text": MATERIAL }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        response.json().await.unwrap()
+    }
+
+    async fn session(&self, id: Uuid) -> Session {
+        self.client
+            .get(format!("{}/api/sessions/{}", self.base_url, id))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap()
+    }
+
+    async fn post_message(&self, id: Uuid) -> reqwest::Response {
+        self.client
+            .post(format!("{}/api/sessions/{}/messages", self.base_url, id))
+            .json(&json!({ "role": "user", "content": TEACHER_MESSAGE }))
+            .send()
+            .await
+            .unwrap()
+    }
+
+    async fn open_stream(&self, id: Uuid) -> reqwest::Response {
+        let response = self
+            .client
+            .get(format!("{}/api/sessions/{}/stream", self.base_url, id))
+            .query(&[("content", TEACHER_MESSAGE)])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        response
+    }
+
+    /// the saved exchange, waiting for a reply that's still being saved in the background
+    async fn saved_messages(&self, id: Uuid) -> Vec<Message> {
+        for _ in 0..100 {
+            let messages = list_messages_for_session(&self.pool, id).await.unwrap();
+            if !messages.is_empty() {
+                return messages;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("No messages were saved for session {}", id);
+    }
+}
+
+/// a structured Bodhi turn as the model would write it
+fn bodhi_turn(reply: &str) -> String {
+    json!({
+        "reply": reply,
+        "understanding": 40,
+        "confusions": [],
+        "concept": "cell membrane"
+    })
+    .to_string()
+}
+
+fn failure(error: MockFailure) -> MockReply {
+    MockReply {
+        text: String::new(),
+        error: Some(error),
+    }
+}
+
+/// one server-sent event, `message` when it names none
+#[derive(Debug)]
+struct SseEvent {
+    event: String,
+    data: String,
+}
+
+/// reads events until the stream ends, or until `stop` is true for one of them
+async fn read_events(
+    response: reqwest::Response,
+    stop: impl Fn(&SseEvent) -> bool,
+) -> Vec<SseEvent> {
+    let mut body = response.bytes_stream();
+    let mut buffer = String::new();
+    let mut events = Vec::new();
+
+    while let Some(chunk) = body.next().await {
+        buffer.push_str(&String::from_utf8_lossy(&chunk.unwrap()));
+        while let Some(end) = buffer.find("\n\n") {
+            let block: String = buffer.drain(..end + 2).collect();
+            let mut event = SseEvent {
+                event: "message".to_string(),
+                data: String::new(),
+            };
+            let mut has_data = false;
+            for line in block.lines() {
+                if let Some(name) = line.strip_prefix("event:") {
+                    event.event = name.trim().to_string();
+                } else if let Some(data) = line.strip_prefix("data:") {
+                    if has_data {
+                        event.data.push('\n');
+                    }
+                    event.data.push_str(data.strip_prefix(' ').unwrap_or(data));
+                    has_data = true;
+                }
+            }
+            // keep-alive comments carry no data
+            if !has_data {
+                continue;
+            }
+            let done = stop(&event);
+            events.push(event);
+            if done {
+                return events;
+            }
+        }
+    }
+    events
+}
+
+// the stream closes with a single `done` or `error` event
+fn is_last(event: &SseEvent) -> bool {
+    event.event == "done" || event.event == "error"
+}
+
+fn streamed_text(events: &[SseEvent]) -> String {
+    events
+        .iter()
+        .filter(|event| event.event == "message")
+        .map(|event| event.data.as_str())
+        .collect()
+}
+
+fn assert_exchange(messages: &[Message], reply: &str, is_partial: bool) {
+    assert_eq!(messages.len(), 2);
+    assert!(matches!(messages[0].role, MessageRole::User));
+    assert_eq!(messages[0].content, TEACHER_MESSAGE);
+    assert!(matches!(messages[1].role, MessageRole::Assistant));
+    assert_eq!(messages[1].content, reply);
+    assert_eq!(messages[1].is_partial, is_partial);
+}
+
+#[tokio::test]
+async fn posting_a_message_saves_the_exchange() {
+    let reply = "So the membrane is like a gate?";
+    let app =
+        spawn_app(MockProvider::new().with_script([MockReply::text(bodhi_turn(reply))])).await;
+    let session = app.create_session().await;
+    assert_eq!(session.status, SessionStatus::Ready);
+
+    let response = app.post_message(session.id).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let returned: Vec<Message> = response.json().await.unwrap();
+
+    assert_exchange(&returned, reply, false);
+    let signals = returned[1].signals.as_ref().unwrap();
+    assert_eq!(signals.understanding, 40);
+    assert!(returned[1].usage.is_some());
+    assert_exchange(&app.saved_messages(session.id).await, reply, false);
+    assert_eq!(app.session(session.id).await.status, SessionStatus::Active);
+}
+
+#[tokio::test]
+async fn streaming_a_message_relays_and_saves_the_reply() {
+    let reply = "So the membrane decides what gets in?";
+    let app =
+        spawn_app(MockProvider::new().with_script([MockReply::text(bodhi_turn(reply))])).await;
+    let session = app.create_session().await;
+
+    let events = read_events(app.open_stream(session.id).await, is_last).await;
+
+    assert_eq!(streamed_text(&events), reply);
+    let done = events.last().unwrap();
+    assert_eq!(done.event, "done");
+    let returned: Vec<Message> = serde_json::from_str(&done.data).unwrap();
+    assert_exchange(&returned, reply, false);
+    assert_exchange(&app.saved_messages(session.id).await, reply, false);
+    assert_eq!(app.session(session.id).await.status, SessionStatus::Active);
+}
+
+#[tokio::test]
+async fn a_rate_limited_message_is_retried() {
+    let reply = "Does every cell have one?";
+    let script = [
+        failure(MockFailure::RateLimited),
+        MockReply::text(bodhi_turn(reply)),
+    ];
+    let app = spawn_app(MockProvider::new().with_script(script)).await;
+    let session = app.create_session().await;
+
+    let response = app.post_message(session.id).await;
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_exchange(
+        &response.json::<Vec<Message>>().await.unwrap(),
+        reply,
+        false,
+    );
+}
+
+#[tokio::test]
+async fn a_rate_limited_stream_is_retried() {
+    let reply = "Does every cell have one?";
+    let script = [
+        failure(MockFailure::RateLimited),
+        MockReply::text(bodhi_turn(reply)),
+    ];
+    let app = spawn_app(MockProvider::new().with_script(script)).await;
+    let session = app.create_session().await;
+
+    let events = read_events(app.open_stream(session.id).await, is_last).await;
+
+    assert!(events.iter().all(|event| event.event != "error"));
+    assert_eq!(streamed_text(&events), reply);
+    assert_exchange(&app.saved_messages(session.id).await, reply, false);
+}
+
+#[tokio::test]
+async fn a_failed_message_saves_nothing() {
+    let app = spawn_app(MockProvider::new().with_script([failure(MockFailure::Blocked)])).await;
+    let session = app.create_session().await;
+
+    let response = app.post_message(session.id).await;
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "blocked");
+    assert!(
+        list_messages_for_session(&app.pool, session.id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(app.session(session.id).await.status, SessionStatus::Ready);
+}
+
+#[tokio::test]
+async fn a_stream_cut_off_mid_reply_is_saved_as_partial() {
+    // the model fails after the first words of its reply
+    let cut_off = MockReply {
+        text: r#"{"reply": "So the membrane is"#.to_string(),
+        error: Some(MockFailure::Unavailable),
+    };
+    let app = spawn_app(MockProvider::new().with_script([cut_off])).await;
+    let session = app.create_session().await;
+
+    let events = read_events(app.open_stream(session.id).await, is_last).await;
+
+    assert_eq!(streamed_text(&events), "So the membrane is");
+    // the error is sent once the partial reply is saved and carries both messages
+    let error = events.last().unwrap();
+    assert_eq!(error.event, "error");
+    assert!(events.iter().all(|event| event.event != "done"));
+    let error: serde_json::Value = serde_json::from_str(&error.data).unwrap();
+    assert_eq!(error["error"], "unavailable");
+    assert_eq!(error["messages"][1]["content"], "So the membrane is");
+    assert_exchange(
+        &app.saved_messages(session.id).await,
+        "So the membrane is",
+        true,
+    );
+}
+
+#[tokio::test]
+async fn a_client_disconnect_saves_what_arrived() {
+    let reply = "So the membrane is a wall with doors in it, and the nucleus keeps the \
+        instructions for building every part of the cell, is that right?";
+    let bodhi = MockProvider::new()
+        .with_script([MockReply::text(bodhi_turn(reply))])
+        .with_chunk_chars(8)
+        .with_chunk_delay(Duration::from_millis(40));
+    let app = spawn_app(bodhi).await;
+    let session = app.create_session().await;
+
+    // the teacher leaves as soon as the first words arrive
+    let events = read_events(app.open_stream(session.id).await, |event| {
+        event.event == "message"
+    })
+    .await;
+    assert_eq!(events.len(), 1);
+
+    let messages = app.saved_messages(session.id).await;
+    assert_eq!(messages.len(), 2);
+    let saved = &messages[1].content;
+    assert!(messages[1].is_partial);
+    assert!(
+        reply.starts_with(saved.as_str()),
+        "{:?} isn't the start of the reply",
+        saved
+    );
+    assert!(saved.len() < reply.len());
+}
@@ -0,0 +1,80 @@
+#![allow(non_snake_case)]
+use dioxus::prelude::*;
+
+use crate::models::clarity_bar::ClarityBarProps;
+
+// the grade the sparkline tops out at, anything harder is off the chart anyway
+const MAX_CHART_GRADE: f64 = 20.0;
+
+pub fn ClarityBar(props: ClarityBarProps) -> Element {
+    let Some(latest) = props.trend.points.last() else {
+        return rsx! {};
+    };
+
+    let grade_class = match latest.reading_grade {
+        g if g <= 8.0 => "text-green-700",
+        g if g <= 12.0 => "text-amber-700",
+        _ => "text-red-700",
+    };
+
+    // since the first messages, lower is simpler
+    let grade_trend = match props.trend.grade_change {
+        Some(change) if change <= -0.5 => format!("▼ {:.1} simpler", -change),
+        Some(change) if change >= 0.5 => format!("▲ {:.1} harder", change),
+        Some(_) => "steady".to_string(),
+        None => String::new(),
+    };
+    let grade = format!("Grade {:.1}", latest.reading_grade);
+    let sentences = format!("{:.0} words/sentence", latest.avg_sentence_words);
+    let jargon = format!("{:.0}% jargon", latest.jargon_density * 100.0);
+
+    // reading grade per teacher message, oldest first
+    let last_index = props.trend.points.len().saturating_sub(1).max(1) as f64;
+    let points = props
+        .trend
+        .points
+        .iter()
+        .enumerate()
+        .map(|(i, point)| {
+            let x = i as f64 * 100.0 / last_index;
+            let y = 20.0 - point.reading_grade.clamp(0.0, MAX_CHART_GRADE);
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    rsx! {
+        div { class: "bg-white border-b px-4 py-2 flex flex-col gap-1",
+            div { class: "flex items-center gap-3 text-sm",
+                span { class: "font-semibold text-gray-700 whitespace-nowrap", "Your clarity" }
+                span { class: "font-semibold {grade_class}", "{grade}" }
+                if !grade_trend.is_empty() {
+                    span { class: "text-xs text-gray-500", "{grade_trend}" }
+                }
+                span { class: "text-gray-600 hidden sm:inline", "{sentences}" }
+                span { class: "text-gray-600 hidden sm:inline", "{jargon}" }
+                if props.trend.points.len() > 1 {
+                    svg {
+                        class: "h-6 w-32 hidden sm:block ml-auto",
+                        view_box: "0 -1 100 22",
+                        preserve_aspect_ratio: "none",
+                        polyline {
+                            points: "{points}",
+                            fill: "none",
+                            stroke: "#059669",
+                            stroke_width: "1.5",
+                        }
+                    }
+                }
+            }
+            if !props.trend.undefined.is_empty() {
+                div { class: "flex flex-wrap items-center gap-1 text-xs",
+                    span { class: "text-gray-500", "Not explained yet:" }
+                    for term in props.trend.undefined.iter() {
+                        span { class: "px-2 py-0.5 rounded-full bg-sky-100 text-sky-900", "{term}" }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+#![allow(non_snake_case)]
+use dioxus::prelude::*;
+
+use crate::models::{api::CoverageStatus, coverage_panel::CoveragePanelProps};
+
+pub fn CoveragePanel(props: CoveragePanelProps) -> Element {
+    let report = &props.report;
+    let total = report.concepts.len();
+
+    rsx! {
+        aside { class: "hidden lg:flex flex-col w-72 bg-white border-l overflow-y-auto",
+            div { class: "p-4 border-b",
+                h2 { class: "text-lg font-semibold text-gray-800", "Concept coverage" }
+                p { class: "text-sm text-gray-500",
+                    "{report.taught} of {total} taught, {report.partial} partially, {report.untouched} untouched"
+                }
+            }
+            if report.concepts.is_empty() {
+                p { class: "p-4 text-sm text-gray-500", "No key concepts found in the material yet." }
+            }
+            ul { class: "flex flex-col divide-y",
+                for concept in report.concepts.iter() {
+                    li {
+                        key: "{concept.id}",
+                        class: "px-4 py-2 flex items-start gap-2",
+                        title: "{concept.description}",
+                        {
+                            let (label, badge_class) = match concept.status {
+                                CoverageStatus::Taught => ("Taught", "bg-green-100 text-green-800"),
+                                CoverageStatus::Partial => ("Partial", "bg-amber-100 text-amber-900"),
+                                CoverageStatus::Untouched => ("Untouched", "bg-gray-100 text-gray-600"),
+                            };
+                            rsx! {
+                                span { class: "flex-1 text-sm text-gray-800", "{concept.name}" }
+                                span { class: "shrink-0 px-2 py-0.5 rounded-full text-xs {badge_class}", "{label}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,18 +1,104 @@
 #![allow(non_snake_case)]
 use dioxus::prelude::*;
 
-use crate::models::message_bubble::{MessageBubbleProps, MessageRole};
+use crate::models::{
+    api::ArgumentOutcome,
+    message_bubble::{MessageBubbleProps, MessageRole},
+};
 
 pub fn MessageBubble(props: MessageBubbleProps) -> Element {
     let (bubble_class, text_class) = match props.role {
+        MessageRole::User if !props.misconceptions.is_empty() => {
+            ("bg-indigo-600 self-end ring-2 ring-amber-400", "text-white")
+        }
         MessageRole::User => ("bg-indigo-600 self-end", "text-white"),
         MessageRole::Assistant => ("bg-white self-start", "text-gray-800"),
     };
 
+    let clarity = props.clarity.as_ref().map(|clarity| {
+        (
+            format!(
+                "Grade {:.1} · {:.0} words/sentence · {:.0}% jargon",
+                clarity.reading_grade,
+                clarity.avg_sentence_words,
+                clarity.jargon_density * 100.0
+            ),
+            clarity.undefined.join(", "),
+        )
+    });
+
+    let citations: Vec<String> = props
+        .citations
+        .iter()
+        .map(|citation| citation.label())
+        .collect();
+
     rsx! {
         div {
             class: "max-w-md p-3 rounded-lg shadow-md {bubble_class}",
-            p { class: "{text_class}", "{props.text}" }
+            p { class: "{text_class} whitespace-pre-wrap", "{props.text}" }
+            if !citations.is_empty() {
+                div { class: "flex flex-wrap gap-1 mt-2",
+                    for label in citations.iter() {
+                        span {
+                            class: "text-xs px-2 py-0.5 rounded-full bg-indigo-50 text-indigo-700",
+                            title: "Found on {label}",
+                            "{label}"
+                        }
+                    }
+                }
+            }
+            if let Some(score) = props.quiz_score {
+                p { class: "text-xs text-indigo-100 font-semibold mt-1", "Score: {score}/10" }
+            }
+            if let Some(debate) = &props.debate {
+                if !debate.arguments.is_empty() {
+                    div { class: "flex flex-wrap gap-1 mt-2",
+                        for verdict in debate.arguments.iter() {
+                            {
+                                let (chip_class, label) = match verdict.outcome {
+                                    ArgumentOutcome::Rebutted => ("bg-red-100 text-red-800", "Rebutted"),
+                                    ArgumentOutcome::Accepted => ("bg-green-100 text-green-800", "Accepted"),
+                                    ArgumentOutcome::Open => ("bg-gray-100 text-gray-700", "Open"),
+                                };
+                                rsx! {
+                                    span {
+                                        class: "text-xs px-2 py-0.5 rounded-full {chip_class}",
+                                        title: "{label}",
+                                        "{label}: {verdict.argument}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                if debate.conceded {
+                    p { class: "text-xs text-green-700 font-semibold mt-1", "Bodhi concedes the point." }
+                }
+            }
+            if let Some((readability, undefined)) = clarity {
+                p { class: "text-xs text-indigo-100 mt-1", "{readability}" }
+                if !undefined.is_empty() {
+                    p { class: "text-xs text-indigo-100 italic", "Not explained: {undefined}" }
+                }
+            }
+            if props.is_partial {
+                p { class: "text-xs text-gray-500 italic mt-1", "(reply interrupted)" }
+            }
+            for misconception in props.misconceptions.iter() {
+                div {
+                    class: "mt-2 p-2 rounded bg-amber-50 border border-amber-300 text-xs text-amber-900",
+                    p { class: "font-semibold", "⚠ This may contradict the material" }
+                    p { "You said: {misconception.claim}" }
+                    p { "The material says: {misconception.correction}" }
+                    if !misconception.evidence.is_empty() {
+                        p { class: "italic mt-1", "\"{misconception.evidence}\"" }
+                    }
+                    if let Some(page) = misconception.page.as_ref().map(|page| page.label()) {
+                        p { class: "mt-1", "See {page}" }
+                    }
+                }
+            }
         }
     }
 }
@@ -1,4 +1,9 @@
+pub mod clarity_bar;
+pub mod coverage_panel;
 pub mod loading_spinner;
 pub mod message_bubble;
 pub mod microphone_button;
+pub mod note_section;
 pub mod session_item;
+pub mod typing_indicator;
+pub mod understanding_meter;
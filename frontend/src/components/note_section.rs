@@ -0,0 +1,20 @@
+#![allow(non_snake_case)]
+use dioxus::prelude::*;
+
+use crate::models::note_section::NoteSectionProps;
+
+pub fn NoteSection(props: NoteSectionProps) -> Element {
+    rsx! {
+        section { class: "bg-white rounded-lg shadow-sm p-4",
+            h2 { class: "font-semibold text-gray-800 mb-2", "{props.title}" }
+            if props.items.is_empty() {
+                p { class: "text-sm text-gray-500", "None." }
+            }
+            ul { class: "list-disc list-inside flex flex-col gap-1 text-sm text-gray-700",
+                for item in props.items.iter() {
+                    li { "{item}" }
+                }
+            }
+        }
+    }
+}
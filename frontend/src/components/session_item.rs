@@ -2,13 +2,15 @@
 use dioxus::prelude::*;
 use uuid::Uuid;
 
-use crate::Route;
+use crate::{models::api::SessionStatus, Route};
 
 #[derive(Props, PartialEq, Clone)]
 pub struct SessionItemProps {
     pub id: Uuid,
     pub title: String,
     pub last_updated: String,
+    #[props(default)]
+    pub status: SessionStatus,
     #[props(default = false)]
     pub is_active: bool,
     pub on_click: EventHandler<()>,
@@ -22,21 +24,35 @@ pub fn SessionItem(props: SessionItemProps) -> Element {
     };
 
     let target_route = Route::Chat { id: props.id };
+    let summary_route = Route::Summary { id: props.id };
 
     rsx! {
-      Link {
-          to: target_route,
-          div {
-              class: "p-4 cursor-pointer {active_class} border-b border-gray-200",
-              onclick: move |_| {
-                  props.on_click.call(());
-                  // Quick fix: Only refresh when switching to a different session (not the current one)
-                  if !props.is_active {
-                      dioxus::document::eval("setTimeout(() => window.location.reload(), 300);");
+      div { class: "flex items-stretch {active_class} border-b border-gray-200",
+          Link {
+              class: "flex-1 min-w-0",
+              to: target_route,
+              div {
+                  class: "p-4 cursor-pointer",
+                  onclick: move |_| {
+                      props.on_click.call(());
+                      // Quick fix: Only refresh when switching to a different session (not the current one)
+                      if !props.is_active {
+                          dioxus::document::eval("setTimeout(() => window.location.reload(), 300);");
+                      }
+                  },
+                  h3 { class: "font-semibold text-gray-800", "{props.title}" }
+                  if let Some(badge) = props.status.badge() {
+                      span { class: "text-xs px-2 py-0.5 rounded-full bg-gray-100 text-gray-600", "{badge}" }
                   }
-              },
-              h3 { class: "font-semibold text-gray-800", "{props.title}" }
-              p { class: "text-sm text-gray-500", "{props.last_updated}" }
+                  p { class: "text-sm text-gray-500", "{props.last_updated}" }
+              }
+          }
+          // the lesson notes, the summary page follows the id so no reload is needed
+          Link {
+              class: "px-3 flex items-center text-xs text-indigo-600 hover:underline",
+              to: summary_route,
+              onclick: move |_| props.on_click.call(()),
+              "Notes"
           }
       }
     }
@@ -0,0 +1,15 @@
+#![allow(non_snake_case)]
+use dioxus::prelude::*;
+
+// shown in place of Bodhi's bubble until the first chunk of the reply arrives
+pub fn TypingIndicator() -> Element {
+    rsx! {
+        div {
+            class: "bg-white self-start p-3 rounded-lg shadow-md flex items-center space-x-1",
+            "aria-label": "Bodhi is typing",
+            span { class: "w-2 h-2 bg-gray-500 rounded-full animate-bounce" }
+            span { class: "w-2 h-2 bg-gray-500 rounded-full animate-bounce [animation-delay:150ms]" }
+            span { class: "w-2 h-2 bg-gray-500 rounded-full animate-bounce [animation-delay:300ms]" }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+#![allow(non_snake_case)]
+use dioxus::prelude::*;
+
+use crate::models::understanding_meter::UnderstandingMeterProps;
+
+pub fn UnderstandingMeter(props: UnderstandingMeterProps) -> Element {
+    let Some(&current) = props.history.last() else {
+        return rsx! {
+            div { class: "bg-white border-b px-4 py-2 text-sm text-gray-500",
+                "Bodhi's understanding will show up after its first reply."
+            }
+        };
+    };
+    let current = current.clamp(0, 100);
+
+    let bar_class = match current {
+        0..=39 => "bg-red-500",
+        40..=69 => "bg-amber-500",
+        _ => "bg-green-500",
+    };
+
+    // change since the previous reply
+    let trend = match props.history.len() {
+        0 | 1 => String::new(),
+        n => match current - props.history[n - 2].clamp(0, 100) {
+            0 => String::new(),
+            delta if delta > 0 => format!("▲ {}", delta),
+            delta => format!("▼ {}", -delta),
+        },
+    };
+
+    // the gauge over the whole session, one point per reply
+    let last_index = props.history.len().saturating_sub(1).max(1) as f64;
+    let points = props
+        .history
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let x = i as f64 * 100.0 / last_index;
+            let y = 20 - (*value).clamp(0, 100) * 20 / 100;
+            format!("{:.1},{}", x, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    rsx! {
+        div { class: "bg-white border-b px-4 py-2 flex flex-col gap-1",
+            div { class: "flex items-center gap-3",
+                span { class: "text-sm font-semibold text-gray-700 whitespace-nowrap", "Bodhi's understanding" }
+                div { class: "flex-1 bg-gray-200 rounded-full h-2",
+                    div {
+                        class: "h-2 rounded-full transition-all {bar_class}",
+                        style: "width: {current}%",
+                    }
+                }
+                span { class: "text-sm font-semibold text-gray-800 w-10 text-right", "{current}%" }
+                if !trend.is_empty() {
+                    span { class: "text-xs text-gray-500 w-10", "{trend}" }
+                }
+                if props.history.len() > 1 {
+                    svg {
+                        class: "h-6 w-32 hidden sm:block",
+                        view_box: "0 -1 100 22",
+                        preserve_aspect_ratio: "none",
+                        polyline {
+                            points: "{points}",
+                            fill: "none",
+                            stroke: "#4f46e5",
+                            stroke_width: "1.5",
+                        }
+                    }
+                }
+            }
+            if !props.confusions.is_empty() {
+                div { class: "flex flex-wrap items-center gap-1 text-xs",
+                    span { class: "text-gray-500", "Still confused about:" }
+                    for confusion in props.confusions.iter() {
+                        span { class: "px-2 py-0.5 rounded-full bg-amber-100 text-amber-900", "{confusion}" }
+                    }
+                }
+            }
+        }
+    }
+}
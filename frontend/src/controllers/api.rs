@@ -1,6 +1,10 @@
+use dioxus::document;
 use uuid::Uuid;
 
-use crate::models::api::{CreateSessionPayload, Message, Session};
+use crate::models::api::{
+    ApiError, ClarityTrend, CoverageReport, CreateSessionPayload, IngestionProgress, LessonSummary,
+    Message, Persona, Session, SessionMode,
+};
 
 pub async fn get_messages(session_id: Uuid) -> Result<Vec<Message>, reqwest::Error> {
     let url = format!("http://localhost:3000/api/sessions/{}/messages", session_id);
@@ -8,15 +12,92 @@ pub async fn get_messages(session_id: Uuid) -> Result<Vec<Message>, reqwest::Err
     Ok(messages)
 }
 
+pub async fn get_session(session_id: Uuid) -> Result<Session, reqwest::Error> {
+    let url = format!("http://localhost:3000/api/sessions/{}", session_id);
+    let session = reqwest::get(&url).await?.json::<Session>().await?;
+    Ok(session)
+}
+
+pub async fn get_coverage(session_id: Uuid) -> Result<CoverageReport, reqwest::Error> {
+    let url = format!("http://localhost:3000/api/sessions/{}/coverage", session_id);
+    let report = reqwest::get(&url)
+        .await?
+        .error_for_status()?
+        .json::<CoverageReport>()
+        .await?;
+    Ok(report)
+}
+
+pub async fn get_clarity(session_id: Uuid) -> Result<ClarityTrend, reqwest::Error> {
+    let url = format!("http://localhost:3000/api/sessions/{}/clarity", session_id);
+    let trend = reqwest::get(&url)
+        .await?
+        .error_for_status()?
+        .json::<ClarityTrend>()
+        .await?;
+    Ok(trend)
+}
+
+pub async fn get_ingestion(session_id: Uuid) -> Result<IngestionProgress, reqwest::Error> {
+    let url = format!(
+        "http://localhost:3000/api/sessions/{}/ingestion",
+        session_id
+    );
+    let progress = reqwest::get(&url)
+        .await?
+        .error_for_status()?
+        .json::<IngestionProgress>()
+        .await?;
+    Ok(progress)
+}
+
+// waits in the browser, the frontend has no timer of its own
+pub async fn wait(millis: u32) {
+    let script = format!(
+        "await new Promise(resolve => setTimeout(resolve, {})); return null;",
+        millis
+    );
+    let _ = document::eval(&script).await;
+}
+
+// the session's lesson notes, written by the backend if the lesson moved on since the last ones.
+// failures come back as the message to show the teacher
+pub async fn summarize_session(session_id: Uuid) -> Result<LessonSummary, String> {
+    let client = reqwest::Client::new();
+    let url = format!("http://localhost:3000/api/sessions/{}/summary", session_id);
+
+    let response = client.post(&url).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        return Err(match serde_json::from_str::<ApiError>(&body) {
+            Ok(api_error) => api_error.describe(),
+            Err(_) => body,
+        });
+    }
+
+    response
+        .json::<LessonSummary>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
 pub async fn list_sessions() -> Result<Vec<Session>, reqwest::Error> {
     let url = "http://localhost:3000/api/sessions";
     let sessions = reqwest::get(url).await?.json::<Vec<Session>>().await?;
     Ok(sessions)
 }
 
+pub async fn list_personas() -> Result<Vec<Persona>, reqwest::Error> {
+    let url = "http://localhost:3000/api/personas";
+    let personas = reqwest::get(url).await?.json::<Vec<Persona>>().await?;
+    Ok(personas)
+}
+
 pub async fn create_session(
     topic: String,
     material_text: String,
+    persona_id: Option<String>,
+    mode: SessionMode,
 ) -> Result<Session, reqwest::Error> {
     let client = reqwest::Client::new();
     let url = "http://localhost:3000/api/sessions";
@@ -24,6 +105,8 @@ pub async fn create_session(
     let payload = CreateSessionPayload {
         topic,
         material_text,
+        persona_id,
+        mode,
     };
 
     let response = client
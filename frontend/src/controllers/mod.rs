@@ -1,2 +1,2 @@
 pub mod api;
-pub mod message_bubble;
+pub mod stream;
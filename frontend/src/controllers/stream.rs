@@ -0,0 +1,62 @@
+use dioxus::prelude::*;
+use uuid::Uuid;
+
+use crate::models::stream::{RawStreamEvent, StreamEvent};
+
+// opens an EventSource on the stream endpoint and forwards every event back to Rust.
+// the source is closed on `done` and `error` so the browser doesn't reconnect and resend the message.
+const STREAM_SCRIPT: &str = r#"
+    const { url, content } = await dioxus.recv();
+    const source = new EventSource(url + "?content=" + encodeURIComponent(content));
+
+    source.onmessage = (event) => {
+        dioxus.send({ kind: "chunk", data: event.data });
+    };
+
+    source.addEventListener("done", (event) => {
+        source.close();
+        dioxus.send({ kind: "done", data: event.data });
+    });
+
+    // fires both for the backend's `error` events and for lost connections
+    source.addEventListener("error", (event) => {
+        source.close();
+        dioxus.send({ kind: "error", data: event.data || "Lost connection to Bodhi." });
+    });
+"#;
+
+/// streams Bodhi's reply to `content`, calling `on_event` for every chunk until the stream ends
+pub async fn stream_reply(
+    session_id: Uuid,
+    content: String,
+    mut on_event: impl FnMut(StreamEvent),
+) {
+    let url = format!("http://localhost:3000/api/sessions/{}/stream", session_id);
+    let mut evaluator = document::eval(STREAM_SCRIPT);
+
+    if let Err(e) = evaluator.send(serde_json::json!({ "url": url, "content": content })) {
+        on_event(StreamEvent::failed(format!(
+            "Failed to start the stream: {:?}",
+            e
+        )));
+        return;
+    }
+
+    loop {
+        match evaluator.recv::<RawStreamEvent>().await {
+            Ok(raw) => {
+                let event = StreamEvent::from(raw);
+                let is_last = !matches!(event, StreamEvent::Chunk(_));
+                on_event(event);
+
+                if is_last {
+                    break;
+                }
+            }
+            Err(e) => {
+                on_event(StreamEvent::failed(format!("Stream interrupted: {:?}", e)));
+                break;
+            }
+        }
+    }
+}
@@ -7,6 +7,7 @@ use crate::models::main::{MobileMenuOpen, NewLessonModalOpen};
 use crate::pages::chat::Chat;
 use crate::pages::new_lesson_modal::NewLessonModal;
 use crate::pages::sidebar::Sidebar;
+use crate::pages::summary::Summary;
 use crate::pages::welcome::Welcome;
 
 mod components;
@@ -20,6 +21,9 @@ enum Route {
     // The chat interface, which takes a session ID from the URL
     #[route("/session/:id")]
     Chat { id: Uuid },
+    // The lesson notes of a session
+    #[route("/session/:id/summary")]
+    Summary { id: Uuid },
     // A welcome page for the root URL
     #[route("/")]
     Welcome {},
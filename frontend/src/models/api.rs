@@ -22,12 +22,257 @@ pub struct Message {
     pub role: MessageRole,
     pub content: String,
     pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub is_partial: bool,
+    #[serde(default)]
+    pub misconceptions: Vec<Misconception>,
+    // what Bodhi reported about its understanding, assistant messages only
+    #[serde(default)]
+    pub signals: Option<TurnSignals>,
+    // the quiz question a message asks or answers, quiz sessions only
+    #[serde(default)]
+    pub quiz: Option<QuizMessageMeta>,
+    // Bodhi's stance and verdicts on the teacher's arguments, debate replies only
+    #[serde(default)]
+    pub debate: Option<DebateTurn>,
+    // how plainly the teacher wrote, teacher messages only
+    #[serde(default)]
+    pub clarity: Option<ClarityScore>,
+    // the pages of the material a reply draws on, assistant messages only
+    #[serde(default)]
+    pub citations: Vec<PageCitation>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct CreateMessage {
-    pub role: MessageRole,
-    pub content: String,
+pub struct QuizMessageMeta {
+    pub position: i64,
+    pub total: i64,
+    #[serde(default)]
+    pub score: Option<i64>, // 0 to 10, answers only
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgumentOutcome {
+    Rebutted,
+    Accepted,
+    Open,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArgumentVerdict {
+    pub argument: String,
+    pub outcome: ArgumentOutcome,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DebateTurn {
+    pub stance: String,
+    #[serde(default)]
+    pub arguments: Vec<ArgumentVerdict>,
+    #[serde(default)]
+    pub conceded: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TurnSignals {
+    pub understanding: i64, // 0 to 100
+    #[serde(default)]
+    pub confusions: Vec<String>,
+    #[serde(default)]
+    pub concept: Option<String>,
+}
+
+// a statement in the teacher's message that contradicts the material
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Misconception {
+    pub claim: String,
+    pub correction: String,
+    #[serde(default)]
+    pub evidence: String,
+    // the page the evidence was found on, page-aware materials only
+    #[serde(default)]
+    pub page: Option<PageCitation>,
+}
+
+// a page of one of the session's materials
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PageCitation {
+    pub source: String,
+    pub page: u32,
+}
+
+impl PageCitation {
+    pub fn label(&self) -> String {
+        format!("p. {} · {}", self.page, self.source)
+    }
+}
+
+// where a key concept of the material stands over the whole lesson
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageStatus {
+    Taught,
+    Partial,
+    Untouched,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConceptCoverage {
+    #[serde(with = "uuid::serde::urn")]
+    pub id: Uuid,
+    pub position: i64,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub status: CoverageStatus,
+    pub mentions: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub concepts: Vec<ConceptCoverage>,
+    pub taught: usize,
+    pub partial: usize,
+    pub untouched: usize,
+}
+
+// the reading level, sentence length and jargon of a teacher message
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClarityScore {
+    pub reading_grade: f64,
+    pub avg_sentence_words: f64,
+    pub jargon_density: f64, // 0 to 1
+    #[serde(default)]
+    pub undefined: Vec<String>, // the material's terms used without explaining them
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClarityPoint {
+    pub reading_grade: f64,
+    pub avg_sentence_words: f64,
+    pub jargon_density: f64,
+}
+
+// the clarity of the teacher's messages over the session, changes are latest minus
+// first messages so negative means simpler
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClarityTrend {
+    pub points: Vec<ClarityPoint>,
+    #[serde(default)]
+    pub grade_change: Option<f64>,
+    #[serde(default)]
+    pub sentence_change: Option<f64>,
+    #[serde(default)]
+    pub jargon_change: Option<f64>,
+    #[serde(default)]
+    pub undefined: Vec<String>,
+}
+
+// a question Bodhi asked during the lesson
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LessonQuestion {
+    pub question: String,
+    #[serde(default)]
+    pub answered: bool,
+}
+
+// the notes the teacher can take away from a lesson
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LessonSummary {
+    #[serde(default)]
+    pub key_points: Vec<String>,
+    #[serde(default)]
+    pub questions: Vec<LessonQuestion>,
+    #[serde(default)]
+    pub gaps: Vec<String>,
+    #[serde(default)]
+    pub follow_up: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// what the user does in a session
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionMode {
+    #[default]
+    Teach, // the user teaches Bodhi
+    Quiz,   // Bodhi quizzes the user
+    Debate, // Bodhi argues against the user's position
+}
+
+// where a session is, from processing its upload to being archived
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionStatus {
+    Processing, // the uploaded material is being extracted
+    #[default]
+    Ready,
+    Failed, // the upload couldn't be read
+    Active,
+    Completed,
+    Archived,
+}
+
+impl SessionStatus {
+    // a short tag for the sidebar, none for sessions that are simply open
+    pub fn badge(self) -> Option<&'static str> {
+        match self {
+            SessionStatus::Processing => Some("Processing"),
+            SessionStatus::Failed => Some("Failed"),
+            SessionStatus::Completed => Some("Completed"),
+            SessionStatus::Archived => Some("Archived"),
+            SessionStatus::Ready | SessionStatus::Active => None,
+        }
+    }
+
+    // why the chat doesn't take messages, none when it does
+    pub fn closed_reason(self) -> Option<&'static str> {
+        match self {
+            SessionStatus::Processing => Some("The material is still being processed"),
+            SessionStatus::Failed => Some("The material couldn't be processed."),
+            SessionStatus::Archived => Some("This session is archived."),
+            SessionStatus::Ready | SessionStatus::Active | SessionStatus::Completed => None,
+        }
+    }
+}
+
+// the step an upload has reached in the backend's background pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestionStage {
+    Queued,
+    Extracting,
+    Indexing,
+    Concepts,
+    Done,
+    Failed,
+}
+
+impl IngestionStage {
+    pub fn label(self) -> &'static str {
+        match self {
+            IngestionStage::Queued => "Waiting to be processed",
+            IngestionStage::Extracting => "Reading the file",
+            IngestionStage::Indexing => "Indexing the material",
+            IngestionStage::Concepts => "Finding the key concepts",
+            IngestionStage::Done => "Done",
+            IngestionStage::Failed => "Failed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct IngestionJob {
+    pub stage: IngestionStage,
+    pub failure: Option<ApiError>, // why it failed, in the shape of a rejected upload
+}
+
+// how far a session's upload is, polled while the session is processing
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct IngestionProgress {
+    pub status: SessionStatus,
+    pub job: Option<IngestionJob>, // sessions created from pasted text have none
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -36,10 +281,42 @@ pub struct Session {
     pub id: Uuid,
     pub topic: String,
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub mode: SessionMode,
+    #[serde(default)]
+    pub status: SessionStatus,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct CreateSessionPayload {
     pub topic: String,
     pub material_text: String,
+    pub persona_id: Option<String>,
+    pub mode: SessionMode,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Persona {
+    pub id: String,
+    pub name: String,
+    pub level: String,
+}
+
+// error body sent by the backend when a request or a stream fails
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct ApiError {
+    pub error: String, // e.g. "rate_limited", "timeout", "blocked"
+    pub message: String,
+    #[serde(default)]
+    pub retry_after_secs: Option<u64>,
+}
+
+impl ApiError {
+    // the message to show the teacher
+    pub fn describe(&self) -> String {
+        match self.retry_after_secs {
+            Some(secs) => format!("{} (retry in {}s)", self.message, secs),
+            None => self.message.clone(),
+        }
+    }
 }
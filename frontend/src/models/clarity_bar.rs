@@ -0,0 +1,9 @@
+use dioxus::prelude::*;
+
+use crate::models::api::ClarityTrend;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct ClarityBarProps {
+    // how plainly the teacher has written over the session
+    pub trend: ClarityTrend,
+}
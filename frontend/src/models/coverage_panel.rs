@@ -0,0 +1,9 @@
+use dioxus::prelude::*;
+
+use crate::models::api::CoverageReport;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct CoveragePanelProps {
+    // the material's key concepts and how far the lesson has covered each
+    pub report: CoverageReport,
+}
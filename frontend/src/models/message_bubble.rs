@@ -1,5 +1,7 @@
 use dioxus::prelude::*;
 
+use crate::models::api::{ClarityScore, DebateTurn, Misconception, PageCitation};
+
 #[derive(PartialEq, Clone, Copy)]
 pub enum MessageRole {
     User,
@@ -10,4 +12,22 @@ pub enum MessageRole {
 pub struct MessageBubbleProps {
     pub text: String,
     pub role: MessageRole,
+    // the reply was cut off before Bodhi finished it
+    #[props(default = false)]
+    pub is_partial: bool,
+    // statements in the teacher's message that contradict the material
+    #[props(default)]
+    pub misconceptions: Vec<Misconception>,
+    // the score of a quiz answer, 0 to 10
+    #[props(default)]
+    pub quiz_score: Option<i64>,
+    // what Bodhi made of the teacher's arguments in a debate
+    #[props(default)]
+    pub debate: Option<DebateTurn>,
+    // how plainly the teacher's message is written
+    #[props(default)]
+    pub clarity: Option<ClarityScore>,
+    // the pages of the material the reply draws on
+    #[props(default)]
+    pub citations: Vec<PageCitation>,
 }
@@ -1,3 +1,8 @@
 pub mod api;
+pub mod clarity_bar;
+pub mod coverage_panel;
 pub mod main;
 pub mod message_bubble;
+pub mod note_section;
+pub mod stream;
+pub mod understanding_meter;
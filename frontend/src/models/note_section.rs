@@ -0,0 +1,8 @@
+use dioxus::prelude::*;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct NoteSectionProps {
+    pub title: String,
+    // one bullet point each
+    pub items: Vec<String>,
+}
@@ -0,0 +1,61 @@
+use serde::Deserialize;
+
+use crate::models::api::{ApiError, Message};
+
+// raw event forwarded by the EventSource script
+#[derive(Clone, Debug, Deserialize)]
+pub struct RawStreamEvent {
+    pub kind: String,
+    pub data: String,
+}
+
+// body of the backend's `error` event, a partial reply saved before the
+// failure comes along with it
+#[derive(Deserialize)]
+struct StreamError {
+    #[serde(flatten)]
+    error: ApiError,
+    #[serde(default)]
+    messages: Vec<Message>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamEvent {
+    // the next piece of Bodhi's reply
+    Chunk(String),
+    // the exchange was saved, carries the persisted user and assistant messages
+    Done(Vec<Message>),
+    // the stream failed or the backend sent an `error` event, carries the
+    // messages saved before it failed
+    Error {
+        message: String,
+        saved: Vec<Message>,
+    },
+}
+
+impl StreamEvent {
+    // a failure before anything was saved
+    pub fn failed(message: String) -> Self {
+        StreamEvent::Error {
+            message,
+            saved: Vec::new(),
+        }
+    }
+}
+
+impl From<RawStreamEvent> for StreamEvent {
+    fn from(raw: RawStreamEvent) -> Self {
+        match raw.kind.as_str() {
+            "chunk" => StreamEvent::Chunk(raw.data),
+            "done" => StreamEvent::Done(serde_json::from_str(&raw.data).unwrap_or_default()),
+            // backend errors are JSON, lost connections are plain text
+            _ => match serde_json::from_str::<StreamError>(&raw.data) {
+                Ok(e) => StreamEvent::Error {
+                    message: e.error.describe(),
+                    saved: e.messages,
+                },
+                Err(_) => StreamEvent::failed(raw.data),
+            },
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use dioxus::prelude::*;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct UnderstandingMeterProps {
+    // Bodhi's understanding after each of its replies, oldest first, 0 to 100
+    pub history: Vec<i64>,
+    // what Bodhi is still unsure about after its latest reply
+    #[props(default)]
+    pub confusions: Vec<String>,
+}
@@ -5,17 +5,29 @@ use futures_util::{FutureExt, StreamExt};
 use serde_json::Value;
 use uuid::Uuid;
 
+use crate::components::clarity_bar::ClarityBar;
+use crate::components::coverage_panel::CoveragePanel;
 use crate::components::loading_spinner::LoadingSpinner;
 use crate::components::microphone_button::MicrophoneButton;
-use crate::controllers::api::get_messages;
-use crate::controllers::message_bubble::send_message;
-use crate::models::api::MessageRole as ApiMessageRole;
+use crate::components::typing_indicator::TypingIndicator;
+use crate::components::understanding_meter::UnderstandingMeter;
+use crate::controllers::api::{
+    get_clarity, get_coverage, get_ingestion, get_messages, get_session, wait,
+};
+use crate::controllers::stream::stream_reply;
+use crate::models::api::{
+    IngestionProgress, MessageRole as ApiMessageRole, SessionMode, SessionStatus,
+};
 use crate::models::main::MobileMenuOpen;
+use crate::models::stream::StreamEvent;
 use crate::{
     components::message_bubble::MessageBubble,
     models::message_bubble::MessageRole as ViewMessageRole,
 };
 
+// how often an uploaded session's processing is checked
+const INGESTION_POLL_MS: u32 = 2_000;
+
 enum SpeechAction {
     Start,
     Stop,
@@ -35,19 +47,169 @@ pub fn Chat(props: ChatProps) -> Element {
         move || async move { get_messages(session_id).await }
     });
 
-    let is_loading = use_memo(move || messages.read().is_none());
+    // debates have no understanding meter, quizzes have neither that nor coverage,
+    // Bodhi asks the questions
+    let session = use_resource({
+        let session_id = props.id;
+        move || async move { get_session(session_id).await }
+    });
+    let mode = use_memo(move || match &*session.read() {
+        Some(Ok(s)) => s.mode,
+        _ => SessionMode::Teach,
+    });
+    let is_quiz = use_memo(move || mode() == SessionMode::Quiz);
+
+    // which of the material's key concepts the lesson has covered so far
+    let coverage = use_resource({
+        let session_id = props.id;
+        move || async move { get_coverage(session_id).await }
+    });
+
+    // an uploaded session is polled until its material is ready, or failed to process
+    let mut ingestion = use_signal(|| None::<IngestionProgress>);
+    use_future({
+        let session_id = props.id;
+        move || async move {
+            let mut session = session.clone();
+            let mut coverage = coverage.clone();
+            let mut waited = false;
+
+            loop {
+                match get_ingestion(session_id).await {
+                    Ok(progress) => {
+                        let processing = progress.status == SessionStatus::Processing;
+                        ingestion.set(Some(progress));
+                        if !processing {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to get the upload's progress: {}", e);
+                        break;
+                    }
+                }
+
+                waited = true;
+                wait(INGESTION_POLL_MS).await;
+            }
+
+            // the material and its concepts are there now
+            if waited {
+                session.restart();
+                coverage.restart();
+            }
+        }
+    });
+    let status = use_memo(move || match (&*ingestion.read(), &*session.read()) {
+        (Some(progress), _) => progress.status,
+        (None, Some(Ok(s))) => s.status,
+        _ => SessionStatus::Ready,
+    });
+    // shown above the disabled input while the session takes no messages
+    let closed_notice = use_memo(move || {
+        let reason = status().closed_reason()?;
+        let job = ingestion
+            .read()
+            .as_ref()
+            .and_then(|progress| progress.job.clone());
+        Some(match (status(), job) {
+            (SessionStatus::Processing, Some(job)) => {
+                format!("{}: {}", reason, job.stage.label())
+            }
+            // the backend's explanation of what was wrong with the file
+            (SessionStatus::Failed, Some(job)) => job
+                .failure
+                .map(|failure| failure.describe())
+                .unwrap_or_else(|| reason.to_string()),
+            _ => reason.to_string(),
+        })
+    });
+    let is_closed = use_memo(move || closed_notice().is_some());
+
+    // how plainly the teacher has been writing, message by message
+    let clarity = use_resource({
+        let session_id = props.id;
+        move || async move { get_clarity(session_id).await }
+    });
+
+    // the teacher's message and Bodhi's growing reply while a stream is open
+    let mut pending_message = use_signal(|| None::<String>);
+    let mut live_reply = use_signal(|| None::<String>);
+    let mut stream_error = use_signal(|| None::<String>);
+
+    // Bodhi's understanding after each reply and what it still finds unclear
+    let understanding = use_memo(move || match &*messages.read() {
+        Some(Ok(message_list)) => {
+            let signals: Vec<_> = message_list
+                .iter()
+                .filter_map(|message| message.signals.as_ref())
+                .collect();
+            let history: Vec<i64> = signals.iter().map(|s| s.understanding).collect();
+            let confusions = signals
+                .last()
+                .map(|s| s.confusions.clone())
+                .unwrap_or_default();
+            Some((history, confusions))
+        }
+        _ => None,
+    });
+
+    // the position Bodhi defends in a debate, as of its latest reply
+    let stance = use_memo(move || match &*messages.read() {
+        Some(Ok(message_list)) => message_list
+            .iter()
+            .rev()
+            .filter_map(|message| message.debate.as_ref())
+            .map(|debate| debate.stance.clone())
+            .find(|stance| !stance.is_empty()),
+        _ => None,
+    });
+
+    let is_streaming = use_memo(move || live_reply.read().is_some());
+    let is_loading = use_memo(move || messages.read().is_none() || is_streaming());
 
     let sender = use_coroutine({
         let session_id = props.id;
         move |mut rx: UnboundedReceiver<String>| {
             let mut messages = messages.clone();
+            let mut coverage = coverage.clone();
+            let mut clarity = clarity.clone();
             let session_id = session_id;
 
             async move {
                 while let Some(content) = rx.next().await {
-                    if let Ok(_new_messages) = send_message(session_id, content).await {
-                        messages.restart();
-                    }
+                    pending_message.set(Some(content.clone()));
+                    live_reply.set(Some(String::new()));
+                    stream_error.set(None);
+
+                    stream_reply(session_id, content, |event| match event {
+                        StreamEvent::Chunk(text) => {
+                            if let Some(reply) = live_reply.write().as_mut() {
+                                reply.push_str(&text);
+                            }
+                        }
+                        // reload the persisted list, which now holds both messages
+                        StreamEvent::Done(saved) => {
+                            tracing::info!("Stream finished, {} messages saved", saved.len());
+                            messages.restart();
+                            coverage.restart();
+                            clarity.restart();
+                        }
+                        StreamEvent::Error { message, saved } => {
+                            tracing::error!("Stream error: {}", message);
+                            stream_error.set(Some(message));
+                            // the partial reply saved before the failure
+                            if !saved.is_empty() {
+                                messages.restart();
+                                coverage.restart();
+                                clarity.restart();
+                            }
+                        }
+                    })
+                    .await;
+
+                    pending_message.set(None);
+                    live_reply.set(None);
                 }
             }
         }
@@ -120,6 +282,9 @@ pub fn Chat(props: ChatProps) -> Element {
     });
 
     use_effect(move || {
+        // subscribe to the live reply so the view follows it as it grows
+        let _ = live_reply.read();
+
         // We only want to scroll if the messages have successfully loaded
         if let Some(Ok(message_list)) = &*messages.read() {
             // And only if there are messages to scroll to
@@ -164,56 +329,122 @@ pub fn Chat(props: ChatProps) -> Element {
                 }
             }
 
-            main {
-              class: "flex-1 overflow-y-auto p-4",
-              id: "message-list",
-              div { class: "flex flex-col space-y-4",
-                  match &*messages.read() {
-                      Some(Ok(message_list)) => {
-                        if message_list.is_empty() {
-                          rsx! {
-                              div { class: "flex-1 flex justify-center items-center",
-                                  p { class: "text-gray-500", "No messages yet. Start the lesson!" }
+            if mode() == SessionMode::Teach {
+                if let Some((history, confusions)) = understanding() {
+                    UnderstandingMeter { history, confusions }
+                }
+            }
+            if !is_quiz() {
+                if let Some(Ok(trend)) = &*clarity.read() {
+                    ClarityBar { trend: trend.clone() }
+                }
+            }
+            if let Some(stance) = stance() {
+                div {
+                    class: "bg-rose-50 border-b border-rose-200 px-4 py-2 text-sm text-rose-900",
+                    span { class: "font-semibold", "Bodhi argues: " }
+                    "{stance}"
+                }
+            }
+
+            div { class: "flex flex-1 min-h-0",
+                main {
+                  class: "flex-1 overflow-y-auto p-4",
+                  id: "message-list",
+                  div { class: "flex flex-col space-y-4",
+                      match &*messages.read() {
+                          Some(Ok(message_list)) => {
+                            if message_list.is_empty() {
+                              rsx! {
+                                  div { class: "flex-1 flex justify-center items-center",
+                                      p { class: "text-gray-500",
+                                      if is_quiz() { "Send any message to start the quiz." } else if mode() == SessionMode::Debate { "No messages yet. Take a position and make your case!" } else { "No messages yet. Start the lesson!" }
+                                  }
+                                  }
                               }
-                          }
-                          } else {
-                            rsx! {
-                                {message_list.iter().map(|message| {
-                                    let view_role = match message.role {
-                                        ApiMessageRole::User => ViewMessageRole::User,
-                                        ApiMessageRole::Assistant => ViewMessageRole::Assistant,
-                                    };
-                                    rsx! {
-                                        MessageBubble {
-                                            key: "{message.id}",
-                                            text: message.content.clone(),
-                                            role: view_role
+                              } else {
+                                rsx! {
+                                    {message_list.iter().map(|message| {
+                                        let view_role = match message.role {
+                                            ApiMessageRole::User => ViewMessageRole::User,
+                                            ApiMessageRole::Assistant => ViewMessageRole::Assistant,
+                                        };
+                                        rsx! {
+                                            MessageBubble {
+                                                key: "{message.id}",
+                                                text: message.content.clone(),
+                                                role: view_role,
+                                                is_partial: message.is_partial,
+                                                misconceptions: message.misconceptions.clone(),
+                                                quiz_score: message.quiz.as_ref().and_then(|quiz| quiz.score),
+                                                debate: message.debate.clone(),
+                                                clarity: message.clarity.clone(),
+                                                citations: message.citations.clone()
+                                            }
                                         }
-                                    }
-                                })}
+                                    })}
+                                }
                             }
-                        }
-                      },
-                      Some(Err(e)) => rsx! { p { "Error fetching messages: {e}" } },
-                      None => rsx! { LoadingSpinner {} },
-                  }
+                          },
+                          Some(Err(e)) => rsx! { p { "Error fetching messages: {e}" } },
+                          None => rsx! { LoadingSpinner {} },
+                      }
+
+                      // the exchange in flight, replaced by the persisted messages once the stream ends
+                      if let Some(text) = pending_message() {
+                          MessageBubble {
+                              text,
+                              role: ViewMessageRole::User
+                          }
+                      }
+                      match live_reply() {
+                          Some(text) if text.is_empty() => rsx! { TypingIndicator {} },
+                          Some(text) => rsx! {
+                              MessageBubble {
+                                  text,
+                                  role: ViewMessageRole::Assistant
+                              }
+                          },
+                          None => rsx! {},
+                      }
+                      if let Some(e) = stream_error() {
+                          div {
+                              class: "self-center bg-red-50 border border-red-200 text-red-700 px-4 py-2 rounded-lg text-sm",
+                              role: "alert",
+                              "{e}"
+                          }
+                      }
+                    }
+                }
+
+                if !is_quiz() {
+                    if let Some(Ok(report)) = &*coverage.read() {
+                        CoveragePanel { report: report.clone() }
+                    }
                 }
             }
 
             footer {
                 class: "bg-white p-4 shadow-inner",
+                if let Some(notice) = closed_notice() {
+                    div {
+                        class: "mb-3 bg-amber-50 border border-amber-200 text-amber-800 px-4 py-2 rounded-lg text-sm",
+                        role: "status",
+                        "{notice}"
+                    }
+                }
                 div { class: "flex items-center",
                     input {
                         class: "flex-1 border rounded-full py-2 px-4 mr-4 disabled:bg-gray-100",
-                        placeholder: if is_loading() { "Loading..." } else { "Teach your lesson here..." },
+                        placeholder: if is_streaming() { "Bodhi is replying..." } else if is_loading() { "Loading..." } else if is_closed() { "This session doesn't take messages" } else if is_quiz() { "Type your answer here..." } else if mode() == SessionMode::Debate { "Make your case here..." } else { "Teach your lesson here..." },
                         r#type: "text",
                         value: "{new_message_text}",
                         oninput: move |event| new_message_text.set(event.value().clone()),
-                        disabled: is_loading(),
+                        disabled: is_loading() || is_closed(),
                     }
                     button {
                         class: "w-10 h-10 text-white rounded-full flex items-center justify-center hover:bg-indigo-700 transition-colors mr-4 disabled:opacity-50",
-                        disabled: is_loading(),
+                        disabled: is_loading() || is_closed(),
                         onclick: move |_| {
                             if !new_message_text.read().is_empty() {
                                 sender.send(new_message_text.read().clone());
@@ -243,7 +474,7 @@ pub fn Chat(props: ChatProps) -> Element {
                         }
                     }
                     MicrophoneButton {
-                      disabled: is_loading(),
+                      disabled: is_loading() || is_closed(),
                       on_click: move |is_recording| {
                           if is_recording {
                               speech_manager.send(SpeechAction::Start);
@@ -1,4 +1,5 @@
 pub mod chat;
 pub mod new_lesson_modal;
 pub mod sidebar;
+pub mod summary;
 pub mod welcome;
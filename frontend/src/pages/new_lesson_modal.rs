@@ -2,7 +2,11 @@
 use anyhow;
 use dioxus::prelude::*;
 
-use crate::{controllers::api::create_session, Route};
+use crate::{
+    controllers::api::{create_session, list_personas},
+    models::api::SessionMode,
+    Route,
+};
 
 #[derive(Props, PartialEq, Clone)]
 pub struct NewLessonModalProps {
@@ -12,30 +16,41 @@ pub struct NewLessonModalProps {
 pub fn NewLessonModal(props: NewLessonModalProps) -> Element {
     let mut topic = use_signal(|| String::new());
     let mut material = use_signal(|| String::new());
+    // empty means the backend's default persona
+    let mut persona_id = use_signal(|| String::new());
+    let mut mode = use_signal(SessionMode::default);
+    let personas = use_resource(list_personas);
     let mut is_loading = use_signal(|| false);
     let mut error_message = use_signal(|| String::new());
     let navigator = use_navigator();
 
     // Use Dioxus 0.7's new action pattern for better async handling
-    let mut create_session_action = use_action(move |(topic, material): (String, String)| {
-        let navigator = navigator.clone();
-        let on_close = props.on_close.clone();
-        async move {
-            if topic.is_empty() || material.is_empty() {
-                return Err::<(), anyhow::Error>(anyhow::anyhow!(
-                    "Topic and material cannot be empty"
-                ));
-            }
+    let mut create_session_action = use_action(
+        move |(topic, material, persona_id, mode): (
+            String,
+            String,
+            Option<String>,
+            SessionMode,
+        )| {
+            let navigator = navigator.clone();
+            let on_close = props.on_close.clone();
+            async move {
+                if topic.is_empty() || material.is_empty() {
+                    return Err::<(), anyhow::Error>(anyhow::anyhow!(
+                        "Topic and material cannot be empty"
+                    ));
+                }
 
-            create_session(topic, material)
-                .await
-                .map(|session| {
-                    on_close.call(());
-                    navigator.push(Route::Chat { id: session.id });
-                })
-                .map_err(|e| anyhow::anyhow!("Failed to create session: {}", e))
-        }
-    });
+                create_session(topic, material, persona_id, mode)
+                    .await
+                    .map(|session| {
+                        on_close.call(());
+                        navigator.push(Route::Chat { id: session.id });
+                    })
+                    .map_err(|e| anyhow::anyhow!("Failed to create session: {}", e))
+            }
+        },
+    );
 
     let mut handle_create_session = move |_| {
         if is_loading() {
@@ -44,6 +59,7 @@ pub fn NewLessonModal(props: NewLessonModalProps) -> Element {
 
         let topic = topic.read().clone();
         let material = material.read().clone();
+        let persona_id = Some(persona_id.read().clone()).filter(|id| !id.is_empty());
 
         if topic.trim().is_empty() || material.trim().is_empty() {
             error_message.set("Please fill in both topic and material".to_string());
@@ -52,7 +68,7 @@ pub fn NewLessonModal(props: NewLessonModalProps) -> Element {
 
         error_message.set(String::new());
         is_loading.set(true);
-        create_session_action.call((topic, material));
+        create_session_action.call((topic, material, persona_id, mode()));
     };
 
     // Handle action results
@@ -177,6 +193,56 @@ pub fn NewLessonModal(props: NewLessonModalProps) -> Element {
                             }
                         }
 
+                        // Persona select
+                        div {
+                            label {
+                                class: "block text-sm font-semibold text-gray-700 mb-3",
+                                r#for: "persona",
+                                "Who is Bodhi today?"
+                            }
+                            select {
+                                class: "w-full border border-gray-200 rounded-xl shadow-sm py-4 px-5 text-base focus:outline-none focus:ring-2 focus:ring-indigo-500 focus:border-indigo-500 transition-all duration-200 bg-gray-50 hover:bg-white focus:bg-white disabled:opacity-50 disabled:bg-gray-100",
+                                id: "persona",
+                                value: "{persona_id}",
+                                onchange: move |event| persona_id.set(event.value()),
+                                disabled: is_loading(),
+                                option { value: "", "Default student" }
+                                if let Some(Ok(persona_list)) = &*personas.read() {
+                                    for persona in persona_list {
+                                        option {
+                                            key: "{persona.id}",
+                                            value: "{persona.id}",
+                                            "{persona.name} ({persona.level})"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+
+                        // Mode select
+                        div {
+                            label {
+                                class: "block text-sm font-semibold text-gray-700 mb-3",
+                                r#for: "mode",
+                                "What do you want to do?"
+                            }
+                            select {
+                                class: "w-full border border-gray-200 rounded-xl shadow-sm py-4 px-5 text-base focus:outline-none focus:ring-2 focus:ring-indigo-500 focus:border-indigo-500 transition-all duration-200 bg-gray-50 hover:bg-white focus:bg-white disabled:opacity-50 disabled:bg-gray-100",
+                                id: "mode",
+                                onchange: move |event| {
+                                    mode.set(match event.value().as_str() {
+                                        "quiz" => SessionMode::Quiz,
+                                        "debate" => SessionMode::Debate,
+                                        _ => SessionMode::Teach,
+                                    })
+                                },
+                                disabled: is_loading(),
+                                option { value: "teach", selected: mode() == SessionMode::Teach, "Teach the material to Bodhi" }
+                                option { value: "quiz", selected: mode() == SessionMode::Quiz, "Let Bodhi quiz me on it" }
+                                option { value: "debate", selected: mode() == SessionMode::Debate, "Defend a position against Bodhi" }
+                            }
+                        }
+
                         // Submit button
                         div { class: "flex justify-end space-x-4 pt-4",
                             button {
@@ -45,6 +45,7 @@ pub fn Sidebar(props: SidebarProps) -> Element {
                             id: session.id,
                             title: session.topic.clone(),
                             last_updated: session.updated_at.format("%Y-%m-%d").to_string(),
+                            status: session.status,
                             is_active: is_session_active(session.id),
                             on_click: move |_| props.on_close_menu.call(()),
                         }
@@ -0,0 +1,98 @@
+#![allow(non_snake_case)]
+use dioxus::prelude::*;
+use uuid::Uuid;
+
+use crate::components::loading_spinner::LoadingSpinner;
+use crate::components::note_section::NoteSection;
+use crate::controllers::api::summarize_session;
+use crate::models::main::MobileMenuOpen;
+use crate::Route;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct SummaryProps {
+    pub id: Uuid,
+}
+
+pub fn Summary(props: SummaryProps) -> Element {
+    let mut menu_state = use_context::<MobileMenuOpen>();
+
+    // follows the id, the page stays mounted when switching between sessions' notes
+    let id = props.id;
+    let summary = use_resource(use_reactive!(
+        |(id,)| async move { summarize_session(id).await }
+    ));
+
+    rsx! {
+        div { class: "flex flex-col h-full bg-gray-100 flex-1",
+            header { class: "bg-white shadow-md p-4 flex justify-between items-center",
+                div { class: "flex items-center gap-4",
+                    h1 { class: "text-2xl font-bold text-gray-800", "Lesson notes" }
+                    Link {
+                        class: "text-sm text-indigo-600 hover:underline",
+                        to: Route::Chat { id: props.id },
+                        "Back to the lesson"
+                    }
+                }
+                button {
+                    class: "p-2 rounded-md hover:bg-gray-100 md:hidden",
+                    onclick: move |_| {
+                        menu_state.is_open.set(true);
+                    },
+                    svg {
+                        xmlns: "http://www.w3.org/2000/svg",
+                        width: "24",
+                        height: "24",
+                        view_box: "0 0 24 24",
+                        fill: "none",
+                        stroke: "currentColor",
+                        stroke_width: "2",
+                        stroke_linecap: "round",
+                        stroke_linejoin: "round",
+                        line { x1: "3", y1: "12", x2: "21", y2: "12" }
+                        line { x1: "3", y1: "6", x2: "21", y2: "6" }
+                        line { x1: "3", y1: "18", x2: "21", y2: "18" }
+                    }
+                }
+            }
+
+            main { class: "flex-1 overflow-y-auto p-4",
+                match &*summary.read() {
+                    Some(Ok(notes)) => {
+                        let written = notes.created_at.format("%Y-%m-%d %H:%M").to_string();
+                        rsx! {
+                            div { class: "max-w-3xl mx-auto flex flex-col gap-4",
+                                p { class: "text-xs text-gray-500", "Written {written}" }
+                                NoteSection { title: "Key points explained", items: notes.key_points.clone() }
+                                section { class: "bg-white rounded-lg shadow-sm p-4",
+                                    h2 { class: "font-semibold text-gray-800 mb-2", "Questions Bodhi asked" }
+                                    if notes.questions.is_empty() {
+                                        p { class: "text-sm text-gray-500", "None." }
+                                    }
+                                    ul { class: "flex flex-col gap-2",
+                                        for question in notes.questions.iter() {
+                                            li { class: "flex items-start gap-2 text-sm text-gray-700",
+                                                span { class: "flex-1", "{question.question}" }
+                                                if question.answered {
+                                                    span { class: "shrink-0 px-2 py-0.5 rounded-full text-xs bg-green-100 text-green-800", "Answered" }
+                                                } else {
+                                                    span { class: "shrink-0 px-2 py-0.5 rounded-full text-xs bg-amber-100 text-amber-900", "Open" }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                                NoteSection { title: "Unresolved gaps", items: notes.gaps.clone() }
+                                NoteSection { title: "Suggested follow-up", items: notes.follow_up.clone() }
+                            }
+                        }
+                    },
+                    Some(Err(e)) => rsx! {
+                        p { class: "text-center text-gray-500 mt-8", "{e}" }
+                    },
+                    // writing the notes takes a model call when the lesson moved on
+                    None => rsx! { LoadingSpinner {} },
+                }
+            }
+        }
+    }
+}